@@ -0,0 +1,396 @@
+use crate::fs_write::{WriteConflict, WriteError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Model aliases Claude Code accepts in agent frontmatter.
+/// Full model identifiers (e.g. `claude-sonnet-4-5`) are accepted as well.
+pub const KNOWN_AGENT_MODELS: &[&str] = &["sonnet", "opus", "haiku", "inherit"];
+
+/// Parsed representation of a `.claude/agents/*.md` file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentDefinition {
+    pub name: String,
+    pub description: String,
+    /// `None` means the key was omitted and the agent inherits all tools
+    pub tools: Option<Vec<String>>,
+    pub model: Option<String>,
+    pub color: Option<String>,
+    /// Any other frontmatter keys (permissionMode, skills, ...) kept verbatim
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    pub body: String,
+}
+
+/// A single problem found while parsing or validating an agent file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AgentValidationError {
+    MalformedFrontmatter { message: String },
+    MissingField { field: String },
+    InvalidField { field: String, message: String },
+    UnknownModel { model: String, allowed: Vec<String> },
+    DuplicateTool { tool: String },
+}
+
+impl fmt::Display for AgentValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedFrontmatter { message } => {
+                write!(f, "Malformed frontmatter: {}", message)
+            }
+            Self::MissingField { field } => write!(f, "Missing required field '{}'", field),
+            Self::InvalidField { field, message } => {
+                write!(f, "Invalid value for '{}': {}", field, message)
+            }
+            Self::UnknownModel { model, allowed } => write!(
+                f,
+                "Unknown model '{}' (expected one of: {})",
+                model,
+                allowed.join(", ")
+            ),
+            Self::DuplicateTool { tool } => write!(f, "Tool '{}' is listed more than once", tool),
+        }
+    }
+}
+
+/// Error returned by `write_agent`, serialized to the frontend as `{ kind, message, ... }`
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AgentWriteError {
+    Invalid {
+        message: String,
+        errors: Vec<AgentValidationError>,
+    },
+    Conflict(WriteConflict),
+    Io {
+        message: String,
+    },
+}
+
+impl From<String> for AgentWriteError {
+    fn from(message: String) -> Self {
+        Self::Io { message }
+    }
+}
+
+impl From<WriteError> for AgentWriteError {
+    fn from(error: WriteError) -> Self {
+        match error {
+            WriteError::Conflict(conflict) => Self::Conflict(conflict),
+            WriteError::Io { message } => Self::Io { message },
+        }
+    }
+}
+
+impl From<Vec<AgentValidationError>> for AgentWriteError {
+    fn from(errors: Vec<AgentValidationError>) -> Self {
+        let message = errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("; ");
+        Self::Invalid { message, errors }
+    }
+}
+
+/// Split a markdown document into its raw YAML frontmatter and body
+fn split_frontmatter(content: &str) -> Result<(&str, &str), AgentValidationError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let first_line_end = content.find('\n').unwrap_or(content.len());
+    if content[..first_line_end].trim_end() != "---" {
+        return Err(AgentValidationError::MalformedFrontmatter {
+            message: "File must start with a '---' frontmatter block".to_string(),
+        });
+    }
+
+    let rest = &content[(first_line_end + 1).min(content.len())..];
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return Ok((yaml, body));
+        }
+        offset += line.len();
+    }
+
+    Err(AgentValidationError::MalformedFrontmatter {
+        message: "Frontmatter block is not closed with '---'".to_string(),
+    })
+}
+
+fn required_string(
+    map: &serde_yaml::Mapping,
+    field: &str,
+    errors: &mut Vec<AgentValidationError>,
+) -> String {
+    match map.get(field) {
+        None | Some(serde_yaml::Value::Null) => {
+            errors.push(AgentValidationError::MissingField {
+                field: field.to_string(),
+            });
+            String::new()
+        }
+        Some(serde_yaml::Value::String(s)) if s.trim().is_empty() => {
+            errors.push(AgentValidationError::MissingField {
+                field: field.to_string(),
+            });
+            String::new()
+        }
+        Some(serde_yaml::Value::String(s)) => s.clone(),
+        Some(_) => {
+            errors.push(AgentValidationError::InvalidField {
+                field: field.to_string(),
+                message: "expected a string".to_string(),
+            });
+            String::new()
+        }
+    }
+}
+
+fn optional_string(
+    map: &serde_yaml::Mapping,
+    field: &str,
+    errors: &mut Vec<AgentValidationError>,
+) -> Option<String> {
+    match map.get(field) {
+        None | Some(serde_yaml::Value::Null) => None,
+        Some(serde_yaml::Value::String(s)) => Some(s.trim().to_string()),
+        Some(_) => {
+            errors.push(AgentValidationError::InvalidField {
+                field: field.to_string(),
+                message: "expected a string".to_string(),
+            });
+            None
+        }
+    }
+}
+
+/// Tools may be written as a comma-separated string or as a YAML list
+fn parse_tools(
+    value: Option<&serde_yaml::Value>,
+    errors: &mut Vec<AgentValidationError>,
+) -> Option<Vec<String>> {
+    let raw: Vec<String> = match value {
+        None | Some(serde_yaml::Value::Null) => return None,
+        Some(serde_yaml::Value::String(s)) => s.split(',').map(|t| t.to_string()).collect(),
+        Some(serde_yaml::Value::Sequence(items)) => {
+            let mut tools = Vec::new();
+            for item in items {
+                match item {
+                    serde_yaml::Value::String(s) => tools.push(s.clone()),
+                    _ => {
+                        errors.push(AgentValidationError::InvalidField {
+                            field: "tools".to_string(),
+                            message: "every tool entry must be a string".to_string(),
+                        });
+                        return None;
+                    }
+                }
+            }
+            tools
+        }
+        Some(_) => {
+            errors.push(AgentValidationError::InvalidField {
+                field: "tools".to_string(),
+                message: "expected a comma-separated string or a list".to_string(),
+            });
+            return None;
+        }
+    };
+
+    let mut tools: Vec<String> = Vec::new();
+    for tool in raw.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if tools.iter().any(|existing| existing == tool) {
+            let duplicate = AgentValidationError::DuplicateTool {
+                tool: tool.to_string(),
+            };
+            if !errors.contains(&duplicate) {
+                errors.push(duplicate);
+            }
+            continue;
+        }
+        tools.push(tool.to_string());
+    }
+    Some(tools)
+}
+
+fn is_known_model(model: &str) -> bool {
+    KNOWN_AGENT_MODELS.contains(&model) || model.starts_with("claude-")
+}
+
+/// Parse and validate an agent markdown file.
+/// Returns every problem found rather than stopping at the first one.
+pub fn parse_agent_definition(content: &str) -> Result<AgentDefinition, Vec<AgentValidationError>> {
+    let (yaml, body) = split_frontmatter(content).map_err(|e| vec![e])?;
+
+    let parsed: serde_yaml::Value = if yaml.trim().is_empty() {
+        serde_yaml::Value::Mapping(serde_yaml::Mapping::new())
+    } else {
+        serde_yaml::from_str(yaml).map_err(|e| {
+            vec![AgentValidationError::MalformedFrontmatter {
+                message: e.to_string(),
+            }]
+        })?
+    };
+
+    let serde_yaml::Value::Mapping(map) = parsed else {
+        return Err(vec![AgentValidationError::MalformedFrontmatter {
+            message: "Frontmatter must be a YAML mapping of key: value pairs".to_string(),
+        }]);
+    };
+
+    let mut errors = Vec::new();
+    let name = required_string(&map, "name", &mut errors);
+    let description = required_string(&map, "description", &mut errors);
+    let tools = parse_tools(map.get("tools"), &mut errors);
+    let model = optional_string(&map, "model", &mut errors);
+    let color = optional_string(&map, "color", &mut errors);
+
+    if let Some(ref model) = model {
+        if !is_known_model(model) {
+            errors.push(AgentValidationError::UnknownModel {
+                model: model.clone(),
+                allowed: KNOWN_AGENT_MODELS.iter().map(|m| m.to_string()).collect(),
+            });
+        }
+    }
+
+    let mut extra = serde_json::Map::new();
+    for (key, value) in &map {
+        let Some(key) = key.as_str() else {
+            errors.push(AgentValidationError::MalformedFrontmatter {
+                message: "Frontmatter keys must be strings".to_string(),
+            });
+            continue;
+        };
+        if matches!(key, "name" | "description" | "tools" | "model" | "color") {
+            continue;
+        }
+        match serde_json::to_value(value) {
+            Ok(json) => {
+                extra.insert(key.to_string(), json);
+            }
+            Err(e) => errors.push(AgentValidationError::InvalidField {
+                field: key.to_string(),
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(AgentDefinition {
+        name,
+        description,
+        tools,
+        model,
+        color,
+        extra,
+        body: body.trim_start_matches(['\r', '\n']).to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(frontmatter: &str) -> Result<AgentDefinition, Vec<AgentValidationError>> {
+        parse_agent_definition(&format!("---\n{}---\n\nYou review code.\n", frontmatter))
+    }
+
+    fn malformed(result: Result<AgentDefinition, Vec<AgentValidationError>>) -> bool {
+        matches!(
+            result.unwrap_err().as_slice(),
+            [AgentValidationError::MalformedFrontmatter { .. }]
+        )
+    }
+
+    #[test]
+    fn parses_a_complete_agent() {
+        let definition = agent(
+            "name: reviewer\ndescription: Reviews diffs\ntools: Read, Grep\nmodel: sonnet\ncolor: blue\n",
+        )
+        .unwrap();
+        assert_eq!(definition.name, "reviewer");
+        assert_eq!(definition.description, "Reviews diffs");
+        assert_eq!(definition.tools, Some(vec!["Read".into(), "Grep".into()]));
+        assert_eq!(definition.model.as_deref(), Some("sonnet"));
+        assert_eq!(definition.color.as_deref(), Some("blue"));
+        assert!(definition.extra.is_empty());
+        assert_eq!(definition.body, "You review code.\n");
+    }
+
+    #[test]
+    fn rejects_missing_or_unterminated_frontmatter() {
+        assert!(malformed(parse_agent_definition("name: reviewer\n")));
+        assert!(malformed(parse_agent_definition(
+            "---\nname: reviewer\ndescription: Reviews diffs\n\nbody"
+        )));
+        assert!(malformed(parse_agent_definition("---\n- a\n- b\n---\n")));
+    }
+
+    #[test]
+    fn reports_every_missing_required_field() {
+        assert_eq!(
+            agent("description: '  '\n").unwrap_err(),
+            vec![
+                AgentValidationError::MissingField {
+                    field: "name".into()
+                },
+                AgentValidationError::MissingField {
+                    field: "description".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_aliases_and_claude_ids_but_not_unknown_models() {
+        for model in ["opus", "inherit", "claude-sonnet-4-5"] {
+            let frontmatter = format!("name: a\ndescription: b\nmodel: {}\n", model);
+            assert_eq!(agent(&frontmatter).unwrap().model.as_deref(), Some(model));
+        }
+        let errors = agent("name: a\ndescription: b\nmodel: gpt-4o\n").unwrap_err();
+        assert!(matches!(
+            errors.as_slice(),
+            [AgentValidationError::UnknownModel { model, .. }] if model == "gpt-4o"
+        ));
+    }
+
+    #[test]
+    fn tools_may_be_a_list_or_a_comma_string() {
+        let from_list = agent("name: a\ndescription: b\ntools:\n  - Read\n  - Bash\n").unwrap();
+        let from_string = agent("name: a\ndescription: b\ntools: 'Read,  Bash ,'\n").unwrap();
+        assert_eq!(from_list.tools, Some(vec!["Read".into(), "Bash".into()]));
+        assert_eq!(from_string.tools, from_list.tools);
+        // Omitting the key inherits every tool, which is not the same as an empty list
+        assert_eq!(agent("name: a\ndescription: b\n").unwrap().tools, None);
+    }
+
+    #[test]
+    fn duplicate_tools_are_reported_once() {
+        assert_eq!(
+            agent("name: a\ndescription: b\ntools: Read, Bash, Read, Read\n").unwrap_err(),
+            vec![AgentValidationError::DuplicateTool {
+                tool: "Read".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_kept_in_extra() {
+        let definition = agent(
+            "name: a\ndescription: b\npermissionMode: acceptEdits\nskills:\n  - pdf\n  - xlsx\n",
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::Value::Object(definition.extra.clone()),
+            serde_json::json!({ "permissionMode": "acceptEdits", "skills": ["pdf", "xlsx"] })
+        );
+        // And are passed through to the frontend alongside the known fields
+        let json = serde_json::to_value(&definition).unwrap();
+        assert_eq!(json["extra"]["permissionMode"], "acceptEdits");
+    }
+}
@@ -0,0 +1,500 @@
+use crate::fs_write::{read_with_version, write_atomic, ExpectedVersion, WriteError};
+use crate::hooks_config::{HookCommand, HookSource, HooksConfigFile, RemovedHook};
+use crate::mcp_config::{MCPConfigFile, MCPConfigLocation, MCPServerConfig};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// An MCP server taken out of its config while disabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashedMCPServer {
+    /// File the entry came from
+    pub source_path: String,
+    /// Local scope only: the project entry in ~/.claude.json
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    pub scope: String,
+    pub name: String,
+    /// Position under `mcpServers`, so re-enabling puts the entry back where it was
+    pub index: usize,
+    pub config: MCPServerConfig,
+}
+
+/// A hook taken out of its settings file while disabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashedHook {
+    pub source_path: String,
+    pub scope: String,
+    /// Id the disabled hook is listed under. Kept apart from live hook ids, which
+    /// renumber as identical hooks come and go.
+    pub id: String,
+    pub event_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<String>,
+    pub hook: HookCommand,
+    /// Matcher group and position in it, so re-enabling puts the hook back where it was.
+    /// Missing from entries stashed by older versions, which go to the end of their group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_index: Option<usize>,
+    /// Unmodelled keys of the matcher group, when disabling removed its last hook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_group: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// Entries Vinsly disabled where Claude Code has no switch of its own.
+/// Lives in the app data directory so Claude Code never sees the stashed entries.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisabledStash {
+    #[serde(default)]
+    pub mcp_servers: Vec<StashedMCPServer>,
+    #[serde(default)]
+    pub hooks: Vec<StashedHook>,
+}
+
+fn same_location(server: &StashedMCPServer, location: &MCPConfigLocation) -> bool {
+    Path::new(&server.source_path) == location.path
+        && server.project.as_deref().map(normalize) == location.project.as_deref().map(normalize)
+}
+
+fn normalize(project_path: &str) -> String {
+    project_path
+        .replace('\\', "/")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+impl DisabledStash {
+    /// Disabled servers that belong to `location`
+    pub fn mcp_servers_at<'a>(
+        &'a self,
+        location: &'a MCPConfigLocation,
+    ) -> impl Iterator<Item = &'a StashedMCPServer> + 'a {
+        self.mcp_servers
+            .iter()
+            .filter(move |server| same_location(server, location))
+    }
+
+    pub fn take_mcp_server(
+        &mut self,
+        location: &MCPConfigLocation,
+        name: &str,
+    ) -> Option<StashedMCPServer> {
+        let index = self
+            .mcp_servers
+            .iter()
+            .position(|server| server.name == name && same_location(server, location))?;
+        Some(self.mcp_servers.remove(index))
+    }
+
+    /// Disabled hooks that belong to the settings file at `source_path`
+    pub fn hooks_at<'a>(
+        &'a self,
+        source_path: &'a Path,
+    ) -> impl Iterator<Item = &'a StashedHook> + 'a {
+        self.hooks
+            .iter()
+            .filter(move |hook| Path::new(&hook.source_path) == source_path)
+    }
+
+    pub fn take_hook(&mut self, source_path: &Path, id: &str) -> Option<StashedHook> {
+        let index = self
+            .hooks
+            .iter()
+            .position(|hook| hook.id == id && Path::new(&hook.source_path) == source_path)?;
+        Some(self.hooks.remove(index))
+    }
+
+    /// Move server `name` out of `config` into the stash.
+    /// Returns false when it is already disabled.
+    pub fn disable_mcp_server(
+        &mut self,
+        config: &mut MCPConfigFile,
+        location: &MCPConfigLocation,
+        scope: &str,
+        name: &str,
+    ) -> Result<bool, String> {
+        let Some((index, name, server_config)) = config.mcp_servers.shift_remove_full(name) else {
+            if self.mcp_servers_at(location).any(|s| s.name == name) {
+                return Ok(false);
+            }
+            return Err(format!("Server '{}' not found in {} scope", name, scope));
+        };
+        self.mcp_servers.push(StashedMCPServer {
+            source_path: location.path.to_string_lossy().to_string(),
+            project: location.project.clone(),
+            scope: scope.to_string(),
+            name,
+            index,
+            config: server_config,
+        });
+        Ok(true)
+    }
+
+    /// Move stashed server `name` back into `config` at its old position.
+    /// Returns false when it is already enabled.
+    pub fn enable_mcp_server(
+        &mut self,
+        config: &mut MCPConfigFile,
+        location: &MCPConfigLocation,
+        scope: &str,
+        name: &str,
+    ) -> Result<bool, String> {
+        if config.mcp_servers.contains_key(name) {
+            if self.mcp_servers_at(location).any(|s| s.name == name) {
+                return Err(format!(
+                    "A server named '{}' was added in {} scope while it was disabled; rename or remove it first",
+                    name, scope
+                ));
+            }
+            return Ok(false);
+        }
+        let Some(stashed) = self.take_mcp_server(location, name) else {
+            return Err(format!("Server '{}' not found in {} scope", name, scope));
+        };
+        let index = stashed.index.min(config.mcp_servers.len());
+        config
+            .mcp_servers
+            .shift_insert(index, stashed.name, stashed.config);
+        Ok(true)
+    }
+
+    /// Move hook `id` out of `config` into the stash and return the id it is listed
+    /// under while disabled. Returns `None` when it is already disabled.
+    pub fn disable_hook(
+        &mut self,
+        config: &mut HooksConfigFile,
+        source: HookSource<'_>,
+        id: &str,
+    ) -> Result<Option<String>, String> {
+        let removed = match config.take_hook(source, id) {
+            Ok(removed) => removed,
+            Err(_) if self.hooks_at(source.path).any(|h| h.id == id) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let stashed_id = format!(
+            "{}:disabled-{}",
+            source.scope,
+            uuid::Uuid::new_v4().simple()
+        );
+        self.hooks.push(StashedHook {
+            source_path: source.path.to_string_lossy().to_string(),
+            scope: source.scope.to_string(),
+            id: stashed_id.clone(),
+            event_type: removed.event_type,
+            matcher: removed.matcher,
+            hook: removed.hook,
+            group_index: removed.position.map(|(group, _)| group),
+            hook_index: removed.position.map(|(_, hook)| hook),
+            removed_group: removed.removed_group,
+        });
+        Ok(Some(stashed_id))
+    }
+
+    /// Move stashed hook `id` back to where it was and return its id afterwards.
+    /// Returns `None` when it is already enabled.
+    pub fn enable_hook(
+        &mut self,
+        config: &mut HooksConfigFile,
+        source: HookSource<'_>,
+        id: &str,
+    ) -> Result<Option<String>, String> {
+        let Some(stashed) = self.take_hook(source.path, id) else {
+            if config.locations(source).iter().any(|l| l.id == id) {
+                return Ok(None);
+            }
+            return Err(format!("Hook '{}' not found in {} scope", id, source.scope));
+        };
+        let position = stashed.group_index.zip(stashed.hook_index);
+        Ok(Some(config.put_back_hook(
+            source,
+            RemovedHook {
+                event_type: stashed.event_type,
+                matcher: stashed.matcher,
+                hook: stashed.hook,
+                position,
+                removed_group: stashed.removed_group,
+            },
+        )))
+    }
+}
+
+pub fn read_disabled_stash(path: &Path) -> Result<DisabledStash, String> {
+    Ok(read_disabled_stash_with_version(path)?.0)
+}
+
+/// Like `read_disabled_stash`, plus the version to write the edited stash back against
+pub fn read_disabled_stash_with_version(
+    path: &Path,
+) -> Result<(DisabledStash, Option<ExpectedVersion>), String> {
+    let Some((content, version)) = read_with_version(path)? else {
+        return Ok((DisabledStash::default(), None));
+    };
+    if content.trim().is_empty() {
+        return Ok((DisabledStash::default(), Some(version)));
+    }
+
+    let stash = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse disabled entries: {}", e))?;
+    Ok((stash, Some(version)))
+}
+
+pub fn write_disabled_stash(
+    path: &Path,
+    stash: &DisabledStash,
+    expected: Option<&ExpectedVersion>,
+) -> Result<(), WriteError> {
+    let content = serde_json::to_string_pretty(stash)
+        .map_err(|e| format!("Failed to serialize disabled entries: {}", e))?;
+    write_atomic(path, content.as_bytes(), expected)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vinsly-stash-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write the stash out and read it back, as separate commands would
+    fn reload(stash: &DisabledStash, dir: &Path) -> DisabledStash {
+        let path = dir.join("disabled.json");
+        write_disabled_stash(&path, stash, None).unwrap();
+        read_disabled_stash(&path).unwrap()
+    }
+
+    #[test]
+    fn mcp_servers_come_back_identical_and_in_place() {
+        let dir = temp_dir();
+        let location = MCPConfigLocation::project_entry(dir.join(".claude.json"), "/work/app");
+        let original: MCPConfigFile = serde_json::from_value(serde_json::json!({
+            "mcpServers": {
+                "first": { "command": "a" },
+                "linear": {
+                    "type": "http",
+                    "url": "https://mcp.linear.app/mcp",
+                    "headers": { "Authorization": "Bearer ${LINEAR_TOKEN}" },
+                    "alwaysAllow": ["list_issues"]
+                },
+                "last": { "command": "c" }
+            }
+        }))
+        .unwrap();
+        let mut config = original.clone();
+        let mut stash = DisabledStash::default();
+
+        assert!(stash
+            .disable_mcp_server(&mut config, &location, "local", "linear")
+            .unwrap());
+        assert!(!stash
+            .disable_mcp_server(&mut config, &location, "local", "linear")
+            .unwrap());
+        assert_eq!(
+            config.mcp_servers.keys().collect::<Vec<_>>(),
+            ["first", "last"]
+        );
+
+        let mut stash = reload(&stash, &dir);
+        assert_eq!(stash.mcp_servers[0].index, 1);
+        assert!(stash
+            .enable_mcp_server(&mut config, &location, "local", "linear")
+            .unwrap());
+        assert!(!stash
+            .enable_mcp_server(&mut config, &location, "local", "linear")
+            .unwrap());
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::to_value(&original).unwrap()
+        );
+        assert!(stash.mcp_servers.is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn re_enabling_refuses_to_overwrite_a_server_added_meanwhile() {
+        let location = MCPConfigLocation::file(PathBuf::from("/home/me/.claude.json"));
+        let mut config: MCPConfigFile = serde_json::from_value(
+            serde_json::json!({ "mcpServers": { "db": { "command": "a" } } }),
+        )
+        .unwrap();
+        let mut stash = DisabledStash::default();
+        stash
+            .disable_mcp_server(&mut config, &location, "user", "db")
+            .unwrap();
+        config.mcp_servers.insert(
+            "db".to_string(),
+            MCPServerConfig {
+                command: Some("b".to_string()),
+                ..Default::default()
+            },
+        );
+
+        assert!(stash
+            .enable_mcp_server(&mut config, &location, "user", "db")
+            .is_err());
+        assert_eq!(stash.mcp_servers.len(), 1);
+        assert!(stash
+            .enable_mcp_server(&mut config, &location, "user", "missing")
+            .is_err());
+    }
+
+    #[test]
+    fn hooks_come_back_identical_and_in_their_group() {
+        let dir = temp_dir();
+        let source = dir.join("settings.json");
+        let original: HooksConfigFile = serde_json::from_value(serde_json::json!({
+            "hooks": {
+                "PreToolUse": [
+                    { "matcher": "Bash", "hooks": [
+                        { "type": "command", "command": "lint" },
+                        { "type": "command", "command": "test", "timeout": 60, "runInBackground": true }
+                    ] },
+                    { "matcher": "Edit", "note": "formatting", "hooks": [
+                        { "type": "command", "command": "fmt" }
+                    ] },
+                    { "matcher": "Bash", "hooks": [{ "type": "command", "command": "audit" }] }
+                ]
+            }
+        }))
+        .unwrap();
+        let source = HookSource::new("user", &source);
+        let ids: Vec<String> = original
+            .locations(source)
+            .into_iter()
+            .map(|l| l.id)
+            .collect();
+
+        // The middle of a group, and the only hook of a group with its own keys
+        for id in [&ids[1], &ids[2]] {
+            let mut config = original.clone();
+            let mut stash = DisabledStash::default();
+            let stashed_id = stash
+                .disable_hook(&mut config, source, id)
+                .unwrap()
+                .unwrap();
+            assert!(!ids.contains(&stashed_id));
+            assert_eq!(
+                stash
+                    .disable_hook(&mut config, source, &stashed_id)
+                    .unwrap(),
+                None
+            );
+
+            let mut stash = reload(&stash, &dir);
+            let restored = stash.enable_hook(&mut config, source, &stashed_id).unwrap();
+            assert_eq!(restored.as_ref(), Some(id));
+            assert_eq!(stash.enable_hook(&mut config, source, id).unwrap(), None);
+            assert_eq!(
+                serde_json::to_value(&config).unwrap(),
+                serde_json::to_value(&original).unwrap()
+            );
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn identical_hooks_disabled_in_turn_keep_apart_from_live_ids() {
+        let source = HookSource::new("user", Path::new("/home/me/.claude/settings.json"));
+        let original: HooksConfigFile = serde_json::from_value(serde_json::json!({
+            "hooks": { "Stop": [{ "matcher": "", "hooks": [
+                { "type": "command", "command": "notify" },
+                { "type": "command", "command": "notify" }
+            ] }] }
+        }))
+        .unwrap();
+        let mut config = original.clone();
+        let mut stash = DisabledStash::default();
+        let live = config.locations(source)[0].id.clone();
+
+        // The second hook takes over the first one's id once that is disabled
+        let first = stash
+            .disable_hook(&mut config, source, &live)
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.locations(source)[0].id, live);
+        let second = stash
+            .disable_hook(&mut config, source, &live)
+            .unwrap()
+            .unwrap();
+        assert_ne!(first, live);
+        assert_ne!(first, second);
+
+        stash.enable_hook(&mut config, source, &second).unwrap();
+        assert_eq!(
+            stash.enable_hook(&mut config, source, &first).unwrap(),
+            Some(live)
+        );
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::to_value(&original).unwrap()
+        );
+    }
+
+    #[test]
+    fn hooks_stashed_without_a_position_join_their_matcher_group() {
+        let source = PathBuf::from("/work/app/.claude/settings.json");
+        let mut config: HooksConfigFile = serde_json::from_value(serde_json::json!({
+            "hooks": { "Stop": [{ "matcher": "", "hooks": [{ "type": "command", "command": "a" }] }] }
+        }))
+        .unwrap();
+        let mut stash: DisabledStash = serde_json::from_value(serde_json::json!({
+            "hooks": [{
+                "sourcePath": "/work/app/.claude/settings.json",
+                "scope": "project",
+                "id": "project:0123456789abcdef",
+                "eventType": "Stop",
+                "matcher": "",
+                "hook": { "type": "command", "command": "b" }
+            }]
+        }))
+        .unwrap();
+
+        let source = HookSource::new("project", &source);
+        stash
+            .enable_hook(&mut config, source, "project:0123456789abcdef")
+            .unwrap();
+        assert_eq!(config.hooks["Stop"].len(), 1);
+        assert_eq!(
+            config.hooks["Stop"][0].hooks[1].command.as_deref(),
+            Some("b")
+        );
+    }
+
+    #[test]
+    fn project_paths_match_across_trailing_slashes_and_separators() {
+        let claude_json = PathBuf::from("/home/me/.claude.json");
+        let mut stash = DisabledStash::default();
+        let mut config: MCPConfigFile = serde_json::from_value(
+            serde_json::json!({ "mcpServers": { "db": { "command": "a" } } }),
+        )
+        .unwrap();
+        let disabled_at = MCPConfigLocation::project_entry(claude_json.clone(), "C:\\work\\app\\");
+        stash
+            .disable_mcp_server(&mut config, &disabled_at, "local", "db")
+            .unwrap();
+
+        for project in ["C:/work/app", "C:/work/app/", "C:\\work\\app"] {
+            let location = MCPConfigLocation::project_entry(claude_json.clone(), project);
+            assert_eq!(stash.mcp_servers_at(&location).count(), 1, "{}", project);
+        }
+        let elsewhere = MCPConfigLocation::project_entry(claude_json.clone(), "C:/work/app2");
+        assert_eq!(stash.mcp_servers_at(&elsewhere).count(), 0);
+        let other_file =
+            MCPConfigLocation::project_entry(PathBuf::from("/tmp/.claude.json"), "C:/work/app");
+        assert_eq!(stash.mcp_servers_at(&other_file).count(), 0);
+
+        let location = MCPConfigLocation::project_entry(claude_json, "C:/work/app/");
+        assert!(stash
+            .enable_mcp_server(&mut config, &location, "local", "db")
+            .unwrap());
+    }
+}
@@ -0,0 +1,387 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// Serializes the check-then-rename step across concurrent commands in this process
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Identity of a file's contents at a point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileVersion {
+    /// Lowercase hex SHA-256 of the raw file bytes
+    pub hash: String,
+    /// Modification time in milliseconds since the Unix epoch, if the platform reports one
+    pub mtime_ms: Option<u64>,
+}
+
+/// What the editor believes is on disk. Either field may be given; both are checked when present.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExpectedVersion {
+    #[serde(default)]
+    pub hash: Option<String>,
+    #[serde(default)]
+    pub mtime_ms: Option<u64>,
+}
+
+/// The file changed on disk since the caller loaded it
+#[derive(Debug, Clone, Serialize)]
+pub struct WriteConflict {
+    pub message: String,
+    pub path: String,
+    /// `None` when the file has been deleted
+    pub current_content: Option<String>,
+    pub current_version: Option<FileVersion>,
+}
+
+/// Error returned by writers that go through `write_atomic`,
+/// serialized to the frontend as `{ kind, message, ... }`
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WriteError {
+    Conflict(WriteConflict),
+    Io { message: String },
+}
+
+impl ExpectedVersion {
+    /// Pin the hash of `bytes` just read from a file, for read-modify-write cycles
+    /// inside a single command
+    pub fn of(bytes: &[u8]) -> Self {
+        Self {
+            hash: Some(content_hash(bytes)),
+            mtime_ms: None,
+        }
+    }
+}
+
+/// Temp files created by `write_atomic`, which watchers should ignore
+pub fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.') && n.ends_with(".tmp"))
+}
+
+impl From<String> for WriteError {
+    fn from(message: String) -> Self {
+        Self::Io { message }
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Conflict(conflict) => f.write_str(&conflict.message),
+            Self::Io { message } => f.write_str(message),
+        }
+    }
+}
+
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn mtime_ms(metadata: &fs::Metadata) -> Option<u64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+}
+
+fn read_current(path: &Path) -> Result<Option<(Vec<u8>, FileVersion)>, String> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let metadata =
+        fs::metadata(path).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+    let version = FileVersion {
+        hash: content_hash(&bytes),
+        mtime_ms: mtime_ms(&metadata),
+    };
+    Ok(Some((bytes, version)))
+}
+
+/// Read a file as text together with its version on disk, for editors to send back
+/// as `expected` when saving. Returns `None` when the file does not exist.
+pub fn read_versioned(path: &Path) -> Result<Option<(String, FileVersion)>, String> {
+    let Some((bytes, version)) = read_current(path)? else {
+        return Ok(None);
+    };
+    let content = String::from_utf8(bytes)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(Some((content, version)))
+}
+
+/// Read a file as text together with the version of exactly those bytes, so the
+/// version a command writes against always matches what it parsed.
+/// Returns `None` when the file does not exist.
+pub fn read_with_version(path: &Path) -> Result<Option<(String, ExpectedVersion)>, String> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let expected = ExpectedVersion::of(&bytes);
+    let content = String::from_utf8(bytes)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(Some((content, expected)))
+}
+
+fn check_expected(path: &Path, expected: &ExpectedVersion) -> Result<(), WriteError> {
+    if expected.hash.is_none() && expected.mtime_ms.is_none() {
+        return Ok(());
+    }
+
+    let current = read_current(path)?;
+    let matches = match &current {
+        None => false,
+        Some((_, version)) => {
+            let hash_ok = expected
+                .hash
+                .as_ref()
+                .is_none_or(|h| h.eq_ignore_ascii_case(&version.hash));
+            let mtime_ok = expected
+                .mtime_ms
+                .is_none_or(|m| version.mtime_ms == Some(m));
+            hash_ok && mtime_ok
+        }
+    };
+    if matches {
+        return Ok(());
+    }
+
+    let message = if current.is_some() {
+        format!("{} was modified by another program", path.display())
+    } else {
+        format!("{} was deleted by another program", path.display())
+    };
+    let (current_content, current_version) = match current {
+        Some((bytes, version)) => (
+            Some(String::from_utf8_lossy(&bytes).to_string()),
+            Some(version),
+        ),
+        None => (None, None),
+    };
+    Err(WriteError::Conflict(WriteConflict {
+        message,
+        path: path.to_string_lossy().to_string(),
+        current_content,
+        current_version,
+    }))
+}
+
+/// Write through a symlink to its target so dotfile managers keep working
+fn resolve_target(path: &Path) -> PathBuf {
+    if path.is_symlink() {
+        if let Ok(target) = fs::canonicalize(path) {
+            return target;
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Write `content` to `path` via a temp file in the same directory and an atomic rename,
+/// so a crash never leaves a truncated file behind.
+/// When `expected` is given and the file no longer matches it, nothing is written and
+/// a `WriteError::Conflict` carrying the on-disk content is returned instead.
+/// Returns the version of the newly written file.
+pub fn write_atomic(
+    path: &Path,
+    content: &[u8],
+    expected: Option<&ExpectedVersion>,
+) -> Result<FileVersion, WriteError> {
+    let target = resolve_target(path);
+    let parent = target
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .ok_or_else(|| format!("Invalid path: {}", target.display()))?;
+    fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let file_name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = parent.join(format!(
+        ".{}.{}.tmp",
+        file_name,
+        uuid::Uuid::new_v4().simple()
+    ));
+
+    // Keep the permissions of the file being replaced (e.g. 0600 on ~/.claude.json).
+    // The temp file is created with them so its contents are never more readable.
+    let permissions = fs::metadata(&target).ok().map(|m| m.permissions());
+    let write_temp = || -> Result<(), String> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if let Some(permissions) = &permissions {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(permissions.mode());
+        }
+        let mut file = options
+            .open(&temp_path)
+            .map_err(|e| format!("Failed to create temporary file: {}", e))?;
+        // The umask may have narrowed the mode; restore it exactly
+        if let Some(permissions) = &permissions {
+            let _ = file.set_permissions(permissions.clone());
+        }
+        file.write_all(content)
+            .map_err(|e| format!("Failed to write temporary file: {}", e))?;
+        file.sync_all()
+            .map_err(|e| format!("Failed to flush temporary file: {}", e))?;
+        Ok(())
+    };
+    if let Err(e) = write_temp() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(expected) = expected {
+        if let Err(e) = check_expected(&target, expected) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+    }
+    if let Err(e) = fs::rename(&temp_path, &target) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("Failed to replace {}: {}", target.display(), e).into());
+    }
+
+    let metadata = fs::metadata(&target).ok();
+    Ok(FileVersion {
+        hash: content_hash(content),
+        mtime_ms: metadata.as_ref().and_then(mtime_ms),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vinsly-write-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn conflict(result: Result<FileVersion, WriteError>) -> WriteConflict {
+        match result {
+            Err(WriteError::Conflict(conflict)) => conflict,
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn writes_when_the_expected_version_still_matches() {
+        let dir = temp_dir();
+        let path = dir.join("agent.md");
+        fs::write(&path, "v1").unwrap();
+
+        let (content, expected) = read_with_version(&path).unwrap().unwrap();
+        assert_eq!(content, "v1");
+        let version = write_atomic(&path, b"v2", Some(&expected)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v2");
+        assert_eq!(version, read_versioned(&path).unwrap().unwrap().1);
+
+        assert!(read_with_version(&dir.join("missing.md"))
+            .unwrap()
+            .is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn hash_mismatch_is_a_conflict_with_the_current_file() {
+        let dir = temp_dir();
+        let path = dir.join("agent.md");
+        fs::write(&path, "v1").unwrap();
+        let expected = ExpectedVersion::of(b"v1");
+        fs::write(&path, "edited elsewhere").unwrap();
+
+        let conflict = conflict(write_atomic(&path, b"v2", Some(&expected)));
+        assert_eq!(
+            conflict.current_content.as_deref(),
+            Some("edited elsewhere")
+        );
+        assert_eq!(
+            conflict.current_version,
+            read_versioned(&path).unwrap().map(|(_, version)| version)
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "edited elsewhere");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn mtime_mismatch_is_a_conflict_with_the_current_file() {
+        let dir = temp_dir();
+        let path = dir.join("agent.md");
+        fs::write(&path, "v1").unwrap();
+        let (_, current) = read_versioned(&path).unwrap().unwrap();
+        let expected = ExpectedVersion {
+            hash: None,
+            mtime_ms: current.mtime_ms.map(|m| m - 1000),
+        };
+
+        let conflict = conflict(write_atomic(&path, b"v2", Some(&expected)));
+        assert_eq!(conflict.current_content.as_deref(), Some("v1"));
+        assert_eq!(conflict.current_version, Some(current));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v1");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn deleted_file_is_a_conflict() {
+        let dir = temp_dir();
+        let path = dir.join("agent.md");
+        fs::write(&path, "v1").unwrap();
+        let (_, expected) = read_with_version(&path).unwrap().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let conflict = conflict(write_atomic(&path, b"v2", Some(&expected)));
+        assert!(conflict.message.contains("deleted"));
+        assert_eq!(conflict.current_content, None);
+        assert_eq!(conflict.current_version, None);
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn no_temp_files_are_left_behind() {
+        let dir = temp_dir();
+        let path = dir.join("settings.json");
+        write_atomic(&path, b"{}", None).unwrap();
+        let _ = write_atomic(&path, b"{ }", Some(&ExpectedVersion::of(b"stale")));
+
+        let names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["settings.json"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_of_the_replaced_file_are_kept() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir();
+        let path = dir.join(".claude.json");
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomic(&path, b"{\"mcpServers\": {}}", None).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,849 @@
+use crate::fs_write::{read_with_version, write_atomic, ExpectedVersion, WriteError};
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+fn default_hook_type() -> String {
+    "command".to_string()
+}
+
+/// A single hook as the frontend edits it: matcher and command side by side.
+/// This is also the legacy flat shape older Vinsly versions wrote to `hooks.<Event>[]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookConfig {
+    /// Execution type: "command" or "prompt"
+    #[serde(rename = "type", default = "default_hook_type")]
+    pub hook_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+/// One entry of a matcher group's `hooks` array
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookCommand {
+    #[serde(rename = "type", default = "default_hook_type")]
+    pub hook_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Keys Vinsly doesn't model, kept verbatim
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Canonical `hooks.<Event>[]` entry: `{ matcher, hooks: [{ type, command, timeout }] }`.
+/// Deserializing also accepts the legacy flat shape and converts it.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookMatcherGroup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<String>,
+    pub hooks: Vec<HookCommand>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct NestedGroup {
+    #[serde(default)]
+    matcher: Option<String>,
+    hooks: Vec<HookCommand>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl HookMatcherGroup {
+    fn from_value(value: serde_json::Value) -> Result<Self, String> {
+        if value.get("hooks").is_some() {
+            let group: NestedGroup = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            return Ok(Self {
+                matcher: group.matcher,
+                hooks: group.hooks,
+                extra: group.extra,
+            });
+        }
+
+        let legacy: HookConfig = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        if legacy.command.is_none() && legacy.prompt.is_none() {
+            return Err("expected a 'hooks' array or a 'command'".to_string());
+        }
+        let (matcher, hook) = legacy.into_parts();
+        Ok(Self {
+            matcher,
+            hooks: vec![hook],
+            extra: serde_json::Map::new(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for HookMatcherGroup {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        Self::from_value(value).map_err(serde::de::Error::custom)
+    }
+}
+
+impl HookConfig {
+    /// Split into the group-level matcher and the hook itself
+    pub fn into_parts(self) -> (Option<String>, HookCommand) {
+        let hook = HookCommand {
+            hook_type: self.hook_type,
+            command: self.command,
+            prompt: self.prompt,
+            timeout: self.timeout,
+            extra: serde_json::Map::new(),
+        };
+        (self.matcher, hook)
+    }
+}
+
+/// The settings file a `HooksConfigFile` was read from. Hook ids are tied to it, so the
+/// same hook in two projects' settings files gets two different ids.
+#[derive(Debug, Clone, Copy)]
+pub struct HookSource<'a> {
+    pub scope: &'a str,
+    pub path: &'a Path,
+}
+
+impl<'a> HookSource<'a> {
+    pub fn new(scope: &'a str, path: &'a Path) -> Self {
+        Self { scope, path }
+    }
+}
+
+/// Stable identity of a hook: a hash of its settings file, event, matcher and the hook
+/// itself, prefixed with the scope.
+/// `occurrence` tells apart byte-identical hooks within the same event and matcher.
+pub fn hook_id(
+    source: HookSource<'_>,
+    event_type: &str,
+    matcher: Option<&str>,
+    hook: &HookCommand,
+    occurrence: usize,
+) -> String {
+    let path = source.path.to_string_lossy();
+    let mut hasher = Sha256::new();
+    for part in [
+        source.scope,
+        path.as_ref(),
+        event_type,
+        matcher.unwrap_or(""),
+        &hook.hook_type,
+        hook.command.as_deref().unwrap_or(""),
+        hook.prompt.as_deref().unwrap_or(""),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(
+        hook.timeout
+            .map(|t| t.to_string())
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+    let digest: String = hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    if occurrence == 0 {
+        format!("{}:{}", source.scope, digest)
+    } else {
+        format!("{}:{}-{}", source.scope, digest, occurrence)
+    }
+}
+
+/// Position of a hook inside a `HooksConfigFile`
+#[derive(Debug, Clone)]
+pub struct HookLocation {
+    pub id: String,
+    pub event_type: String,
+    pub group_index: usize,
+    pub hook_index: usize,
+}
+
+/// A hook taken out by `take_hook`, with what `put_back_hook` needs to restore it
+#[derive(Debug, Clone)]
+pub struct RemovedHook {
+    pub event_type: String,
+    pub matcher: Option<String>,
+    pub hook: HookCommand,
+    /// Group and hook index it was taken from
+    pub position: Option<(usize, usize)>,
+    /// Keys of its matcher group Vinsly doesn't model, when taking the hook emptied the group
+    pub removed_group: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HooksConfigFile {
+    #[serde(default)]
+    pub hooks: IndexMap<String, Vec<HookMatcherGroup>>,
+}
+
+impl HooksConfigFile {
+    /// Append to the last group with the same matcher, creating the group if needed.
+    /// Returns the group and hook index it landed at.
+    fn push_hook(
+        &mut self,
+        event_type: &str,
+        matcher: Option<String>,
+        hook: HookCommand,
+    ) -> (usize, usize) {
+        let groups = self.hooks.entry(event_type.to_string()).or_default();
+        match groups.iter().rposition(|g| g.matcher == matcher) {
+            Some(group_index) => {
+                groups[group_index].hooks.push(hook);
+                (group_index, groups[group_index].hooks.len() - 1)
+            }
+            None => {
+                groups.push(HookMatcherGroup {
+                    matcher,
+                    hooks: vec![hook],
+                    extra: serde_json::Map::new(),
+                });
+                (groups.len() - 1, 0)
+            }
+        }
+    }
+
+    fn id_at(
+        &self,
+        source: HookSource<'_>,
+        event_type: &str,
+        group_index: usize,
+        hook_index: usize,
+    ) -> String {
+        self.locations(source)
+            .into_iter()
+            .find(|l| {
+                l.event_type == event_type
+                    && l.group_index == group_index
+                    && l.hook_index == hook_index
+            })
+            .map(|l| l.id)
+            .unwrap_or_default()
+    }
+
+    /// Add a hook to the group with the same matcher and return its id
+    pub fn add_hook(
+        &mut self,
+        source: HookSource<'_>,
+        event_type: &str,
+        hook_config: HookConfig,
+    ) -> String {
+        let (matcher, hook) = hook_config.into_parts();
+        self.restore_hook(source, event_type, matcher, hook)
+    }
+
+    /// Put back a hook exactly as `remove_hook` returned it and return its id
+    pub fn restore_hook(
+        &mut self,
+        source: HookSource<'_>,
+        event_type: &str,
+        matcher: Option<String>,
+        hook: HookCommand,
+    ) -> String {
+        let (group_index, hook_index) = self.push_hook(event_type, matcher, hook);
+        self.id_at(source, event_type, group_index, hook_index)
+    }
+
+    /// Put back a hook taken out by `take_hook` at its old position and return its id.
+    /// When the file changed so that position no longer fits, behaves like `restore_hook`.
+    pub fn put_back_hook(&mut self, source: HookSource<'_>, removed: RemovedHook) -> String {
+        let RemovedHook {
+            event_type,
+            matcher,
+            hook,
+            position,
+            removed_group,
+        } = removed;
+        let Some((group_index, hook_index)) = position else {
+            return self.restore_hook(source, &event_type, matcher, hook);
+        };
+
+        let groups = self.hooks.entry(event_type.clone()).or_default();
+        match removed_group {
+            Some(extra) if group_index <= groups.len() => {
+                groups.insert(
+                    group_index,
+                    HookMatcherGroup {
+                        matcher,
+                        hooks: vec![hook],
+                        extra,
+                    },
+                );
+                return self.id_at(source, &event_type, group_index, 0);
+            }
+            None => {
+                if let Some(group) = groups
+                    .get_mut(group_index)
+                    .filter(|group| group.matcher == matcher)
+                {
+                    let hook_index = hook_index.min(group.hooks.len());
+                    group.hooks.insert(hook_index, hook);
+                    return self.id_at(source, &event_type, group_index, hook_index);
+                }
+            }
+            Some(_) => {}
+        }
+        self.restore_hook(source, &event_type, matcher, hook)
+    }
+
+    /// Every hook with its id, in file order
+    pub fn locations(&self, source: HookSource<'_>) -> Vec<HookLocation> {
+        let mut locations = Vec::new();
+        for (event_type, groups) in &self.hooks {
+            let mut seen: HashMap<String, usize> = HashMap::new();
+            for (group_index, group) in groups.iter().enumerate() {
+                for (hook_index, hook) in group.hooks.iter().enumerate() {
+                    let base = hook_id(source, event_type, group.matcher.as_deref(), hook, 0);
+                    let occurrence = seen.entry(base).or_insert(0);
+                    let id = hook_id(
+                        source,
+                        event_type,
+                        group.matcher.as_deref(),
+                        hook,
+                        *occurrence,
+                    );
+                    *occurrence += 1;
+                    locations.push(HookLocation {
+                        id,
+                        event_type: event_type.clone(),
+                        group_index,
+                        hook_index,
+                    });
+                }
+            }
+        }
+        locations
+    }
+
+    fn locate(&self, source: HookSource<'_>, id: &str) -> Result<HookLocation, String> {
+        self.locations(source)
+            .into_iter()
+            .find(|location| location.id == id)
+            .ok_or_else(|| {
+                format!(
+                    "Hook '{}' no longer matches any hook in {} scope; it may have been changed outside Vinsly. Reload and try again.",
+                    id, source.scope
+                )
+            })
+    }
+
+    fn drop_empty(&mut self, event_type: &str) {
+        if let Some(groups) = self.hooks.get_mut(event_type) {
+            groups.retain(|g| !g.hooks.is_empty());
+            if groups.is_empty() {
+                self.hooks.shift_remove(event_type);
+            }
+        }
+    }
+
+    /// Remove the hook with the given id and return its event, matcher and entry.
+    /// Empty groups and events are dropped.
+    pub fn remove_hook(
+        &mut self,
+        source: HookSource<'_>,
+        id: &str,
+    ) -> Result<(String, Option<String>, HookCommand), String> {
+        let removed = self.take_hook(source, id)?;
+        Ok((removed.event_type, removed.matcher, removed.hook))
+    }
+
+    /// Like `remove_hook`, but remembers where the hook was for `put_back_hook`
+    pub fn take_hook(&mut self, source: HookSource<'_>, id: &str) -> Result<RemovedHook, String> {
+        let location = self.locate(source, id)?;
+        let groups = self
+            .hooks
+            .get_mut(&location.event_type)
+            .ok_or_else(|| format!("No hooks found for event type '{}'", location.event_type))?;
+        let group = &mut groups[location.group_index];
+        let matcher = group.matcher.clone();
+        let hook = group.hooks.remove(location.hook_index);
+        let removed_group = group.hooks.is_empty().then(|| group.extra.clone());
+        self.drop_empty(&location.event_type);
+        Ok(RemovedHook {
+            event_type: location.event_type,
+            matcher,
+            hook,
+            position: Some((location.group_index, location.hook_index)),
+            removed_group,
+        })
+    }
+
+    /// Replace the hook with the given id and return the replacement's id.
+    /// Stays in place when event and matcher are unchanged, otherwise moves to the
+    /// matching group of the new event.
+    pub fn update_hook(
+        &mut self,
+        source: HookSource<'_>,
+        id: &str,
+        event_type: String,
+        hook_config: HookConfig,
+    ) -> Result<String, String> {
+        let location = self.locate(source, id)?;
+        let (matcher, mut hook) = hook_config.into_parts();
+
+        let group = &mut self.hooks[&location.event_type][location.group_index];
+        if location.event_type == event_type && group.matcher == matcher {
+            let previous = &mut group.hooks[location.hook_index];
+            hook.extra = std::mem::take(&mut previous.extra);
+            *previous = hook;
+            return Ok(self.id_at(
+                source,
+                &event_type,
+                location.group_index,
+                location.hook_index,
+            ));
+        }
+
+        let previous = group.hooks.remove(location.hook_index);
+        hook.extra = previous.extra;
+        self.drop_empty(&location.event_type);
+        let (group_index, hook_index) = self.push_hook(&event_type, matcher, hook);
+        Ok(self.id_at(source, &event_type, group_index, hook_index))
+    }
+
+    /// Reorder the hooks of one event. `ordered_ids` must list every hook of the event
+    /// exactly once. Adjacent hooks that share a matcher end up in the same group.
+    pub fn reorder_hooks(
+        &mut self,
+        source: HookSource<'_>,
+        event_type: &str,
+        ordered_ids: &[String],
+    ) -> Result<(), String> {
+        let locations: Vec<HookLocation> = self
+            .locations(source)
+            .into_iter()
+            .filter(|location| location.event_type == event_type)
+            .collect();
+
+        let mut unique = ordered_ids.to_vec();
+        unique.sort();
+        unique.dedup();
+        if unique.len() != ordered_ids.len() || ordered_ids.len() != locations.len() {
+            return Err(format!(
+                "Reorder must list each of the {} hooks of '{}' exactly once; reload and try again.",
+                locations.len(),
+                event_type
+            ));
+        }
+
+        let groups = self.hooks.get(event_type).cloned().unwrap_or_default();
+        let mut reordered: Vec<HookMatcherGroup> = Vec::new();
+        for id in ordered_ids {
+            let location = self.locate(source, id)?;
+            if location.event_type != event_type {
+                return Err(format!("Hook '{}' does not belong to '{}'", id, event_type));
+            }
+            let group = &groups[location.group_index];
+            let hook = group.hooks[location.hook_index].clone();
+            match reordered.last_mut() {
+                Some(last) if last.matcher == group.matcher => last.hooks.push(hook),
+                _ => reordered.push(HookMatcherGroup {
+                    matcher: group.matcher.clone(),
+                    hooks: vec![hook],
+                    extra: group.extra.clone(),
+                }),
+            }
+        }
+
+        if let Some(slot) = self.hooks.get_mut(event_type) {
+            *slot = reordered;
+        }
+        Ok(())
+    }
+}
+
+fn read_settings_object(path: &Path) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    Ok(read_settings_object_with_version(path)?.0)
+}
+
+fn read_settings_object_with_version(
+    path: &Path,
+) -> Result<(serde_json::Map<String, serde_json::Value>, Option<ExpectedVersion>), String> {
+    let Some((content, version)) = read_with_version(path)? else {
+        return Ok((serde_json::Map::new(), None));
+    };
+
+    if content.trim().is_empty() {
+        return Ok((serde_json::Map::new(), Some(version)));
+    }
+
+    match serde_json::from_str(&content) {
+        Ok(serde_json::Value::Object(map)) => Ok((map, Some(version))),
+        Ok(_) => Err("Failed to parse settings file: expected a JSON object".to_string()),
+        Err(e) => Err(format!("Failed to parse settings file: {}", e)),
+    }
+}
+
+/// Read the `hooks` key of a settings file.
+/// Entries that match neither the nested nor the legacy shape are an error rather than
+/// being skipped, so a later write can't silently drop them.
+pub fn read_hooks_from_settings_file(path: &Path) -> Result<HooksConfigFile, String> {
+    Ok(read_hooks_with_version(path)?.0)
+}
+
+/// Like `read_hooks_from_settings_file`, plus the version to pass to
+/// `write_hooks_to_settings_file` when writing the edited hooks back
+pub fn read_hooks_with_version(
+    path: &Path,
+) -> Result<(HooksConfigFile, Option<ExpectedVersion>), String> {
+    let (mut settings, version) = read_settings_object_with_version(path)?;
+
+    let hooks_value = match settings.remove("hooks") {
+        None | Some(serde_json::Value::Null) => return Ok((HooksConfigFile::default(), version)),
+        Some(serde_json::Value::Object(map)) => map,
+        Some(_) => return Err("Invalid hooks config: 'hooks' must be an object".to_string()),
+    };
+
+    let mut hooks = IndexMap::new();
+    for (event_type, entries) in hooks_value {
+        let serde_json::Value::Array(entries) = entries else {
+            return Err(format!(
+                "Invalid hooks config: '{}' must be an array",
+                event_type
+            ));
+        };
+        let mut groups = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.into_iter().enumerate() {
+            let group = HookMatcherGroup::from_value(entry)
+                .map_err(|e| format!("Invalid hook entry {}[{}]: {}", event_type, index, e))?;
+            groups.push(group);
+        }
+        hooks.insert(event_type, groups);
+    }
+
+    Ok((HooksConfigFile { hooks }, version))
+}
+
+/// Replace the `hooks` key of a settings file, always in the nested shape,
+/// leaving every other setting untouched
+pub fn write_hooks_to_settings_file(
+    path: &Path,
+    hooks_config: &HooksConfigFile,
+    expected: Option<&ExpectedVersion>,
+) -> Result<(), WriteError> {
+    // Read existing settings to preserve other fields
+    let mut settings = read_settings_object(path)?;
+
+    let hooks_value = serde_json::to_value(&hooks_config.hooks)
+        .map_err(|e| format!("Failed to serialize hooks: {}", e))?;
+    settings.insert("hooks".to_string(), hooks_value);
+
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    write_atomic(path, content.as_bytes(), expected)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn settings_file(content: serde_json::Value) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("vinsly-hooks-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        fs::write(&path, serde_json::to_string_pretty(&content).unwrap()).unwrap();
+        (dir, path)
+    }
+
+    fn read_json(path: &Path) -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn legacy_flat_entries_are_written_back_nested() {
+        let (dir, path) = settings_file(serde_json::json!({
+            "model": "opus",
+            "hooks": {
+                "PreToolUse": [
+                    { "type": "command", "matcher": "Bash", "command": "./check.sh", "timeout": 30 }
+                ]
+            }
+        }));
+
+        let config = read_hooks_from_settings_file(&path).unwrap();
+        let group = &config.hooks["PreToolUse"][0];
+        assert_eq!(group.matcher.as_deref(), Some("Bash"));
+        assert_eq!(group.hooks[0].command.as_deref(), Some("./check.sh"));
+        assert_eq!(group.hooks[0].timeout, Some(30));
+
+        write_hooks_to_settings_file(&path, &config, None).unwrap();
+        assert_eq!(
+            read_json(&path),
+            serde_json::json!({
+                "model": "opus",
+                "hooks": {
+                    "PreToolUse": [
+                        {
+                            "matcher": "Bash",
+                            "hooks": [{ "type": "command", "command": "./check.sh", "timeout": 30 }]
+                        }
+                    ]
+                }
+            })
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unknown_keys_on_groups_and_commands_survive_a_round_trip() {
+        let original = serde_json::json!({
+            "hooks": {
+                "Stop": [
+                    {
+                        "matcher": "",
+                        "description": "group note",
+                        "hooks": [
+                            { "type": "command", "command": "say done", "runInBackground": true }
+                        ]
+                    }
+                ]
+            }
+        });
+        let (dir, path) = settings_file(original.clone());
+
+        let (config, expected) = read_hooks_with_version(&path).unwrap();
+        write_hooks_to_settings_file(&path, &config, expected.as_ref()).unwrap();
+        assert_eq!(read_json(&path), original);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn malformed_entries_fail_the_read_instead_of_being_dropped() {
+        for hooks in [
+            serde_json::json!({ "PreToolUse": [{ "matcher": "Bash" }] }),
+            serde_json::json!({ "PreToolUse": [{ "matcher": "Bash", "hooks": "./check.sh" }] }),
+            serde_json::json!({ "PreToolUse": { "matcher": "Bash" } }),
+            serde_json::json!(["PreToolUse"]),
+        ] {
+            let (dir, path) = settings_file(serde_json::json!({ "hooks": hooks }));
+            assert!(
+                read_hooks_from_settings_file(&path).is_err(),
+                "{} should not parse",
+                hooks
+            );
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+
+    fn user() -> HookSource<'static> {
+        HookSource::new("user", Path::new("/home/me/.claude/settings.json"))
+    }
+
+    fn project(settings_path: &'static str) -> HookSource<'static> {
+        HookSource::new("project", Path::new(settings_path))
+    }
+
+    fn command_hook(matcher: &str, command: &str) -> HookConfig {
+        HookConfig {
+            hook_type: "command".to_string(),
+            matcher: Some(matcher.to_string()),
+            command: Some(command.to_string()),
+            prompt: None,
+            timeout: None,
+        }
+    }
+
+    /// PreToolUse: Bash [lint, test], Edit [fmt]; Stop: [notify]
+    fn sample_config() -> HooksConfigFile {
+        let mut config = HooksConfigFile::default();
+        config.add_hook(user(), "PreToolUse", command_hook("Bash", "lint"));
+        config.add_hook(user(), "PreToolUse", command_hook("Bash", "test"));
+        config.add_hook(user(), "PreToolUse", command_hook("Edit", "fmt"));
+        config.add_hook(user(), "Stop", command_hook("", "notify"));
+        config
+    }
+
+    fn ids(config: &HooksConfigFile) -> Vec<String> {
+        config.locations(user()).into_iter().map(|l| l.id).collect()
+    }
+
+    fn id_of(config: &HooksConfigFile, command: &str) -> String {
+        config
+            .locations(user())
+            .into_iter()
+            .find(|l| {
+                config.hooks[&l.event_type][l.group_index].hooks[l.hook_index]
+                    .command
+                    .as_deref()
+                    == Some(command)
+            })
+            .map(|l| l.id)
+            .unwrap()
+    }
+
+    fn commands(config: &HooksConfigFile, event_type: &str) -> Vec<String> {
+        config.hooks[event_type]
+            .iter()
+            .flat_map(|g| g.hooks.iter().filter_map(|h| h.command.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn ids_survive_unrelated_reorders_and_removals() {
+        let mut config = sample_config();
+        let (lint, test, fmt, notify) = (
+            id_of(&config, "lint"),
+            id_of(&config, "test"),
+            id_of(&config, "fmt"),
+            id_of(&config, "notify"),
+        );
+
+        config
+            .reorder_hooks(
+                user(),
+                "PreToolUse",
+                &[fmt.clone(), test.clone(), lint.clone()],
+            )
+            .unwrap();
+        config.remove_hook(user(), &notify).unwrap();
+
+        let mut after = ids(&config);
+        after.sort();
+        let mut expected = vec![lint, test, fmt];
+        expected.sort();
+        assert_eq!(after, expected);
+    }
+
+    #[test]
+    fn identical_hooks_get_occurrence_suffixes() {
+        let mut config = HooksConfigFile::default();
+        let first = config.add_hook(user(), "Stop", command_hook("", "notify"));
+        let second = config.add_hook(user(), "Stop", command_hook("", "notify"));
+        let third = config.add_hook(user(), "Stop", command_hook("", "notify"));
+
+        assert!(!first.contains('-'));
+        assert_eq!(second, format!("{}-1", first));
+        assert_eq!(third, format!("{}-2", first));
+        // The same hook in another settings file is a different hook
+        assert_ne!(
+            config.locations(project("/work/a/.claude/settings.json"))[0].id,
+            first
+        );
+        assert_ne!(
+            config.locations(project("/work/a/.claude/settings.json"))[0].id,
+            config.locations(project("/work/b/.claude/settings.json"))[0].id
+        );
+    }
+
+    #[test]
+    fn update_remove_and_reorder_act_on_the_addressed_hook() {
+        let mut config = sample_config();
+
+        let new_id = config
+            .update_hook(
+                user(),
+                &id_of(&config, "test"),
+                "PreToolUse".to_string(),
+                command_hook("Bash", "test --quick"),
+            )
+            .unwrap();
+        assert_eq!(new_id, id_of(&config, "test --quick"));
+        assert_eq!(
+            commands(&config, "PreToolUse"),
+            ["lint", "test --quick", "fmt"]
+        );
+
+        // Changing the event moves the hook to that event's matching group
+        config
+            .update_hook(
+                user(),
+                &id_of(&config, "fmt"),
+                "Stop".to_string(),
+                command_hook("", "fmt"),
+            )
+            .unwrap();
+        assert_eq!(commands(&config, "PreToolUse"), ["lint", "test --quick"]);
+        assert_eq!(commands(&config, "Stop"), ["notify", "fmt"]);
+        assert_eq!(config.hooks["Stop"].len(), 1);
+
+        let (event_type, matcher, removed) =
+            config.remove_hook(user(), &id_of(&config, "lint")).unwrap();
+        assert_eq!(event_type, "PreToolUse");
+        assert_eq!(matcher.as_deref(), Some("Bash"));
+        assert_eq!(removed.command.as_deref(), Some("lint"));
+        assert_eq!(commands(&config, "PreToolUse"), ["test --quick"]);
+
+        let (notify, fmt) = (id_of(&config, "notify"), id_of(&config, "fmt"));
+        config
+            .reorder_hooks(user(), "Stop", &[fmt, notify])
+            .unwrap();
+        assert_eq!(commands(&config, "Stop"), ["fmt", "notify"]);
+    }
+
+    #[test]
+    fn stale_or_unknown_ids_are_rejected() {
+        let mut config = sample_config();
+        let lint = id_of(&config, "lint");
+        config.remove_hook(user(), &lint).unwrap();
+        let before = serde_json::to_value(&config).unwrap();
+
+        assert!(config.remove_hook(user(), &lint).is_err());
+        assert!(config
+            .update_hook(
+                user(),
+                &lint,
+                "PreToolUse".to_string(),
+                command_hook("Bash", "x")
+            )
+            .is_err());
+        assert!(config.remove_hook(user(), "user:0000000000000000").is_err());
+        // An id from another settings file never matches
+        let notify = id_of(&config, "notify");
+        assert!(config
+            .remove_hook(project("/work/a/.claude/settings.json"), &notify)
+            .is_err());
+        assert_eq!(serde_json::to_value(&config).unwrap(), before);
+    }
+
+    #[test]
+    fn reorder_rejects_incomplete_or_duplicated_lists() {
+        let mut config = sample_config();
+        let (lint, test, fmt) = (
+            id_of(&config, "lint"),
+            id_of(&config, "test"),
+            id_of(&config, "fmt"),
+        );
+        let before = serde_json::to_value(&config).unwrap();
+
+        for ordered in [
+            vec![lint.clone(), test.clone()],
+            vec![lint.clone(), test.clone(), test.clone()],
+            vec![
+                lint.clone(),
+                test.clone(),
+                fmt.clone(),
+                id_of(&config, "notify"),
+            ],
+            vec![
+                lint.clone(),
+                test.clone(),
+                "user:0000000000000000".to_string(),
+            ],
+        ] {
+            assert!(config
+                .reorder_hooks(user(), "PreToolUse", &ordered)
+                .is_err());
+        }
+        assert_eq!(serde_json::to_value(&config).unwrap(), before);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::fs_write::write_atomic;
+use std::fs;
+use std::path::Path;
+
+/// Names of the entries Vinsly stored in the OS keychain, which can't be enumerated.
+/// Kept as a JSON array beside the app's other state; missing or unreadable files are empty.
+pub fn read_name_list(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Add `name` to or remove it from the list at `path`
+pub fn set_name_listed(path: &Path, name: &str, listed: bool) -> Result<(), String> {
+    let mut names = read_name_list(path);
+    if names.iter().any(|n| n == name) == listed {
+        return Ok(());
+    }
+    if listed {
+        names.push(name.to_string());
+    } else {
+        names.retain(|n| n != name);
+    }
+    let content = serde_json::to_string_pretty(&names)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    write_atomic(path, content.as_bytes(), None).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_lists_record_each_name_once() {
+        let dir = std::env::temp_dir().join(format!("vinsly-names-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("names.json");
+
+        set_name_listed(&path, "linear", true).unwrap();
+        set_name_listed(&path, "linear", true).unwrap();
+        set_name_listed(&path, "sentry", true).unwrap();
+        assert_eq!(read_name_list(&path), vec!["linear", "sentry"]);
+
+        set_name_listed(&path, "linear", false).unwrap();
+        assert_eq!(read_name_list(&path), vec!["sentry"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
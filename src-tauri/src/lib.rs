@@ -1,6 +1,55 @@
+pub mod agent;
+pub mod disabled_stash;
+pub mod fs_write;
+pub mod hooks_config;
+pub mod keychain_index;
+pub mod mcp_client;
+pub mod mcp_config;
+pub mod mcp_env;
+pub mod mcp_export;
+pub mod mcp_import;
+pub mod mcp_inventory;
+pub mod mcp_resolve;
+pub mod mcp_secrets;
+pub mod mcp_validate;
+pub mod oauth;
+pub mod oauth_refresh;
 pub mod scanner;
+pub mod session_export;
 pub mod terminal;
-
+pub mod text_diff;
+pub mod token_usage;
+pub mod transcript;
+pub mod transcript_index;
+pub mod transcript_search;
+pub mod watcher;
+
+use agent::{parse_agent_definition, AgentDefinition, AgentWriteError};
+use disabled_stash::{
+    read_disabled_stash, read_disabled_stash_with_version, write_disabled_stash, DisabledStash,
+};
+use fs_write::{read_versioned, write_atomic, ExpectedVersion, FileVersion, WriteError};
+use hooks_config::{
+    read_hooks_from_settings_file, read_hooks_with_version, write_hooks_to_settings_file,
+    HookCommand, HookConfig, HookSource, HooksConfigFile,
+};
+use indexmap::IndexMap;
+use keychain_index::{read_name_list, set_name_listed};
+use mcp_client::{McpError, ServerInfo, ToolCallOutcome, TransportKind};
+use mcp_env::{unresolved_message, ExpandedConfig};
+use mcp_export::ExportReport;
+use mcp_import::{ImportCandidate, ImportSource, McpClient};
+use mcp_resolve::{EffectiveServer, ScopeDefinitions};
+use mcp_secrets::{SecretCandidate, SecretField};
+use mcp_validate::{Diagnostic, MCPWriteError, ScopeServers, ServerDiagnostics};
+use mcp_inventory::{cache_key, cached_inventory, load_inventory, store_inventory, McpInventory};
+use mcp_config::{
+    disabled_mcpjson_servers, json_pointer_token, mcpjson_approval_locations, read_mcp_config_at,
+    read_mcp_config_lenient, read_mcp_config_with_version, read_raw_mcp_servers,
+    set_mcpjson_server_enabled, write_mcp_config_at, MCPConfigFile, MCPConfigLocation,
+    MCPServerConfig, McpjsonApproval, McpjsonApprovals,
+};
+use oauth_refresh::{KeyringTokenStore, TokenStore, KEYRING_SERVICE};
 use scanner::{scan_directory, scan_project_directories, DEFAULT_DISCOVERY_DEPTH};
 use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
@@ -15,7 +64,7 @@ use tokio::sync::Mutex;
 use walkdir::WalkDir;
 use zip::write::FileOptions;
 use zip::{CompressionMethod, ZipArchive, ZipWriter};
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tauri::menu::{Menu, MenuItem};
 use tauri::tray::TrayIconBuilder;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -42,6 +91,8 @@ static HOME_DISCOVERY_MUTEX: OnceLock<Mutex<()>> = OnceLock::new();
 #[cfg(target_os = "macos")]
 static SCAN_HELPER_PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
 static CLAUDE_CLI_PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
+static TRANSCRIPT_INDEX: OnceLock<Mutex<Option<transcript_index::TranscriptIndex>>> = OnceLock::new();
+static TRANSCRIPT_SEARCH_INDEX: OnceLock<Mutex<Option<transcript_search::SearchIndex>>> = OnceLock::new();
 
 fn home_discovery_cache() -> &'static Mutex<Option<DiscoveryCacheEntry>> {
     HOME_DISCOVERY_CACHE.get_or_init(|| Mutex::new(None))
@@ -51,6 +102,14 @@ fn home_discovery_mutex() -> &'static Mutex<()> {
     HOME_DISCOVERY_MUTEX.get_or_init(|| Mutex::new(()))
 }
 
+fn transcript_index() -> &'static Mutex<Option<transcript_index::TranscriptIndex>> {
+    TRANSCRIPT_INDEX.get_or_init(|| Mutex::new(None))
+}
+
+fn transcript_search_index() -> &'static Mutex<Option<transcript_search::SearchIndex>> {
+    TRANSCRIPT_SEARCH_INDEX.get_or_init(|| Mutex::new(None))
+}
+
 #[cfg(target_os = "macos")]
 fn scan_helper_path() -> Option<PathBuf> {
     SCAN_HELPER_PATH
@@ -330,6 +389,17 @@ struct AgentFile {
     path: String,
     content: String,
     scope: String, // "project" or "global"
+    /// Parsed frontmatter, or None when the file does not validate
+    definition: Option<AgentDefinition>,
+    /// Version of `content`, sent back as `expected` when saving
+    version: FileVersion,
+}
+
+/// Where a writer put the file and the version it now has on disk
+#[derive(Debug, Serialize)]
+struct SavedFile {
+    path: String,
+    version: FileVersion,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -719,8 +789,9 @@ async fn list_agents(
         let path = entry.path();
 
         if path.extension().and_then(|s| s.to_str()) == Some("md") {
-            let content = std::fs::read_to_string(&path)
-                .map_err(|e| format!("Failed to read file: {}", e))?;
+            let Some((content, version)) = read_versioned(&path)? else {
+                continue;
+            };
 
             let name = path
                 .file_stem()
@@ -728,11 +799,14 @@ async fn list_agents(
                 .unwrap_or("")
                 .to_string();
 
+            let definition = parse_agent_definition(&content).ok();
             agents.push(AgentFile {
                 name,
                 path: path.to_string_lossy().to_string(),
                 content,
                 scope: scope.clone(),
+                definition,
+                version,
             });
         }
     }
@@ -749,14 +823,18 @@ async fn read_agent(path: String) -> Result<String, String> {
 }
 
 // Write an agent file
+// The content is parsed and validated first so a broken agent never reaches .claude/agents/
+// Pass `expected` to refuse the write if the file changed since it was loaded
 #[tauri::command]
 async fn write_agent(
     scope: String,
     name: String,
     content: String,
     project_path: Option<String>,
-) -> Result<String, String> {
+    expected: Option<ExpectedVersion>,
+) -> Result<SavedFile, AgentWriteError> {
     validate_entry_name(&name)?;
+    parse_agent_definition(&content)?;
     let agents_dir = get_agents_dir(&scope, project_path)?;
 
     // Create directory if it doesn't exist
@@ -767,10 +845,12 @@ async fn write_agent(
     let mut file_path = agents_dir;
     file_path.push(format!("{}.md", name));
 
-    std::fs::write(&file_path, content)
-        .map_err(|e| format!("Failed to write agent file: {}", e))?;
+    let version = write_atomic(&file_path, content.as_bytes(), expected.as_ref())?;
 
-    Ok(file_path.to_string_lossy().to_string())
+    Ok(SavedFile {
+        path: file_path.to_string_lossy().to_string(),
+        version,
+    })
 }
 
 // Expand path to handle ~ (tilde) and relative paths
@@ -817,8 +897,9 @@ async fn list_agents_from_directory(directory: String) -> Result<Vec<AgentFile>,
         let path = entry.path();
 
         if path.extension().and_then(|s| s.to_str()) == Some("md") {
-            let content = std::fs::read_to_string(&path)
-                .map_err(|e| format!("Failed to read file: {}", e))?;
+            let Some((content, version)) = read_versioned(&path)? else {
+                continue;
+            };
 
             let name = path
                 .file_stem()
@@ -826,11 +907,14 @@ async fn list_agents_from_directory(directory: String) -> Result<Vec<AgentFile>,
                 .unwrap_or("")
                 .to_string();
 
+            let definition = parse_agent_definition(&content).ok();
             agents.push(AgentFile {
                 name,
                 path: path.to_string_lossy().to_string(),
                 content,
                 scope: "project".to_string(),
+                definition,
+                version,
             });
         }
     }
@@ -869,7 +953,8 @@ async fn write_skill(
     name: String,
     content: String,
     project_path: Option<String>,
-) -> Result<String, String> {
+    expected: Option<ExpectedVersion>,
+) -> Result<String, WriteError> {
     validate_entry_name(&name)?;
     let skills_dir = get_skills_dir(&scope, project_path)?;
     fs::create_dir_all(&skills_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
@@ -880,7 +965,7 @@ async fn write_skill(
     fs::create_dir_all(&skill_dir).map_err(|e| format!("Failed to create skill folder: {}", e))?;
 
     let skill_file = skill_dir.join("SKILL.md");
-    fs::write(&skill_file, content).map_err(|e| format!("Failed to write skill: {}", e))?;
+    write_atomic(&skill_file, content.as_bytes(), expected.as_ref())?;
     Ok(skill_file.to_string_lossy().to_string())
 }
 
@@ -957,7 +1042,7 @@ async fn migrate_skill(
     // If old and new are the same, just write the content
     if old_dir == new_dir {
         let skill_file = new_dir.join("SKILL.md");
-        fs::write(&skill_file, content).map_err(|e| format!("Failed to write skill: {}", e))?;
+        write_atomic(&skill_file, content.as_bytes(), None).map_err(|e| e.to_string())?;
         return Ok(skill_file.to_string_lossy().to_string());
     }
 
@@ -996,7 +1081,7 @@ async fn migrate_skill(
 
     // Write new SKILL.md content
     let skill_file = new_dir.join("SKILL.md");
-    fs::write(&skill_file, content).map_err(|e| format!("Failed to write skill: {}", e))?;
+    write_atomic(&skill_file, content.as_bytes(), None).map_err(|e| e.to_string())?;
 
     Ok(skill_file.to_string_lossy().to_string())
 }
@@ -1611,6 +1696,8 @@ struct ClaudeMemoryFile {
     path: String,
     content: String,
     exists: bool,
+    /// Version of `content`, sent back as `expected` when saving; None when the file is missing
+    version: Option<FileVersion>,
 }
 
 #[tauri::command]
@@ -1620,14 +1707,13 @@ async fn read_claude_memory(
 ) -> Result<ClaudeMemoryFile, String> {
     let memory_path = get_claude_memory_path(&scope, project_path)?;
 
-    if memory_path.exists() {
-        let content = fs::read_to_string(&memory_path)
-            .map_err(|e| format!("Failed to read CLAUDE.md: {}", e))?;
+    if let Some((content, version)) = read_versioned(&memory_path)? {
         Ok(ClaudeMemoryFile {
             scope,
             path: memory_path.to_string_lossy().to_string(),
             content,
             exists: true,
+            version: Some(version),
         })
     } else {
         Ok(ClaudeMemoryFile {
@@ -1635,6 +1721,7 @@ async fn read_claude_memory(
             path: memory_path.to_string_lossy().to_string(),
             content: String::new(),
             exists: false,
+            version: None,
         })
     }
 }
@@ -1644,19 +1731,17 @@ async fn write_claude_memory(
     scope: String,
     content: String,
     project_path: Option<String>,
-) -> Result<String, String> {
+    expected: Option<ExpectedVersion>,
+) -> Result<SavedFile, WriteError> {
     let memory_path = get_claude_memory_path(&scope, project_path)?;
 
-    // Create parent .claude directory if it doesn't exist
-    if let Some(parent) = memory_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create .claude directory: {}", e))?;
-    }
-
-    fs::write(&memory_path, content)
-        .map_err(|e| format!("Failed to write CLAUDE.md: {}", e))?;
+    // Parent .claude directory is created by write_atomic if it doesn't exist
+    let version = write_atomic(&memory_path, content.as_bytes(), expected.as_ref())?;
 
-    Ok(memory_path.to_string_lossy().to_string())
+    Ok(SavedFile {
+        path: memory_path.to_string_lossy().to_string(),
+        version,
+    })
 }
 
 #[tauri::command]
@@ -1678,6 +1763,8 @@ struct SlashCommandFile {
     path: String,
     content: String,
     scope: String,
+    /// Version of `content`, sent back as `expected` when saving
+    version: FileVersion,
 }
 
 #[tauri::command]
@@ -1701,8 +1788,9 @@ async fn list_slash_commands(
         let path = entry.path();
 
         if path.extension().and_then(|s| s.to_str()) == Some("md") {
-            let content = fs::read_to_string(&path)
-                .map_err(|e| format!("Failed to read command file: {}", e))?;
+            let Some((content, version)) = read_versioned(&path)? else {
+                continue;
+            };
 
             let name = path
                 .file_stem()
@@ -1715,6 +1803,7 @@ async fn list_slash_commands(
                 path: path.to_string_lossy().to_string(),
                 content,
                 scope: scope.clone(),
+                version,
             });
         }
     }
@@ -1736,7 +1825,8 @@ async fn write_slash_command(
     name: String,
     content: String,
     project_path: Option<String>,
-) -> Result<String, String> {
+    expected: Option<ExpectedVersion>,
+) -> Result<SavedFile, WriteError> {
     validate_entry_name(&name)?;
     let commands_dir = get_commands_dir(&scope, project_path)?;
 
@@ -1748,10 +1838,12 @@ async fn write_slash_command(
     let mut file_path = commands_dir;
     file_path.push(format!("{}.md", name));
 
-    fs::write(&file_path, content)
-        .map_err(|e| format!("Failed to write command file: {}", e))?;
+    let version = write_atomic(&file_path, content.as_bytes(), expected.as_ref())?;
 
-    Ok(file_path.to_string_lossy().to_string())
+    Ok(SavedFile {
+        path: file_path.to_string_lossy().to_string(),
+        version,
+    })
 }
 
 #[tauri::command]
@@ -1785,8 +1877,9 @@ async fn list_slash_commands_from_directory(directory: String) -> Result<Vec<Sla
         let path = entry.path();
 
         if path.extension().and_then(|s| s.to_str()) == Some("md") {
-            let content = fs::read_to_string(&path)
-                .map_err(|e| format!("Failed to read command file: {}", e))?;
+            let Some((content, version)) = read_versioned(&path)? else {
+                continue;
+            };
 
             let name = path
                 .file_stem()
@@ -1799,6 +1892,7 @@ async fn list_slash_commands_from_directory(directory: String) -> Result<Vec<Sla
                 path: path.to_string_lossy().to_string(),
                 content,
                 scope: "project".to_string(),
+                version,
             });
         }
     }
@@ -2145,28 +2239,6 @@ async fn import_memories_archive(
 // MCP Server Configuration Commands
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct MCPServerConfig {
-    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
-    server_type: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    url: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    command: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    args: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    headers: Option<std::collections::HashMap<String, String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    env: Option<std::collections::HashMap<String, String>>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-struct MCPConfigFile {
-    #[serde(rename = "mcpServers", default)]
-    mcp_servers: std::collections::HashMap<String, MCPServerConfig>,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 struct MCPServerInfo {
     name: String,
@@ -2174,72 +2246,122 @@ struct MCPServerInfo {
     url: Option<String>,
     command: Option<String>,
     args: Option<Vec<String>>,
-    headers: Option<std::collections::HashMap<String, String>>,
-    env: Option<std::collections::HashMap<String, String>>,
+    headers: Option<IndexMap<String, String>>,
+    env: Option<IndexMap<String, String>>,
     scope: String,
     source_path: String,
+    /// Project the entry belongs to, for project and local scope
+    project_path: Option<String>,
     enabled: bool,
 }
 
-fn get_mcp_config_path(scope: &str, project_path: Option<String>) -> Result<PathBuf, String> {
+// User scope: ~/.claude.json, project scope: <project>/.mcp.json,
+// local scope: projects["<project>"] inside ~/.claude.json (private to this machine)
+fn get_mcp_config_location(
+    scope: &str,
+    project_path: Option<String>,
+) -> Result<MCPConfigLocation, String> {
     match scope {
         "user" => {
             let home_dir =
                 dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
-            Ok(home_dir.join(".claude.json"))
+            Ok(MCPConfigLocation::file(home_dir.join(".claude.json")))
         }
         "project" => {
             if let Some(proj_path) = project_path {
-                Ok(PathBuf::from(proj_path).join(".mcp.json"))
+                Ok(MCPConfigLocation::file(PathBuf::from(proj_path).join(".mcp.json")))
             } else {
                 Err("Project scope requires a project_path parameter".to_string())
             }
         }
         "local" => {
             if let Some(proj_path) = project_path {
-                Ok(PathBuf::from(proj_path)
-                    .join(".claude")
-                    .join("settings.local.json"))
-            } else {
                 let home_dir =
                     dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
-                Ok(home_dir.join(".claude").join("settings.local.json"))
+                Ok(MCPConfigLocation::project_entry(
+                    home_dir.join(".claude.json"),
+                    &proj_path,
+                ))
+            } else {
+                Err("Local scope requires a project_path parameter".to_string())
             }
         }
         _ => Err(format!("Invalid MCP scope: {}", scope)),
     }
 }
 
-fn read_mcp_config_from_file(path: &Path) -> Result<MCPConfigFile, String> {
-    if !path.exists() {
-        return Ok(MCPConfigFile::default());
-    }
-
-    let content = fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read MCP config file: {}", e))?;
-
-    if content.trim().is_empty() {
-        return Ok(MCPConfigFile::default());
+fn mcp_server_info(
+    name: String,
+    server_config: MCPServerConfig,
+    scope: &str,
+    source_path: &Path,
+    project_path: Option<&str>,
+    enabled: bool,
+) -> MCPServerInfo {
+    MCPServerInfo {
+        name,
+        server_type: infer_server_type(&server_config),
+        url: server_config.url,
+        command: server_config.command,
+        args: server_config.args,
+        headers: server_config.headers,
+        env: server_config.env,
+        scope: scope.to_string(),
+        source_path: source_path.to_string_lossy().to_string(),
+        project_path: project_path.map(|p| p.to_string()),
+        enabled,
     }
-
-    serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse MCP config: {}", e))
 }
 
-fn write_mcp_config_to_file(path: &Path, config: &MCPConfigFile) -> Result<(), String> {
-    // Create parent directory if it doesn't exist
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create directory: {}", e))?;
-    }
+// Live servers of a location, minus `disabled` names, followed by the ones stashed while disabled
+fn mcp_server_infos(
+    config: MCPConfigFile,
+    scope: &str,
+    location: &MCPConfigLocation,
+    project_path: Option<&str>,
+    disabled: &[String],
+    stash: &DisabledStash,
+) -> Vec<MCPServerInfo> {
+    let mut servers: Vec<MCPServerInfo> = config
+        .mcp_servers
+        .into_iter()
+        .map(|(name, server_config)| {
+            let enabled = !disabled.contains(&name);
+            mcp_server_info(name, server_config, scope, &location.path, project_path, enabled)
+        })
+        .collect();
+    servers.extend(stash.mcp_servers_at(location).map(|stashed| {
+        mcp_server_info(
+            stashed.name.clone(),
+            stashed.config.clone(),
+            scope,
+            &location.path,
+            project_path,
+            false,
+        )
+    }));
+    servers
+}
 
-    let content = serde_json::to_string_pretty(config)
-        .map_err(|e| format!("Failed to serialize MCP config: {}", e))?;
+/// Vinsly's stash of disabled entries, next to the theme cache in the app data directory
+fn get_disabled_stash_path() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join("com.vinsly.desktop").join("disabled.json"))
+        .ok_or_else(|| "Failed to get app data directory".to_string())
+}
 
-    fs::write(path, content)
-        .map_err(|e| format!("Failed to write MCP config file: {}", e))?;
+fn get_claude_json_path() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+    Ok(home_dir.join(".claude.json"))
+}
 
-    Ok(())
+/// Where `.mcp.json` approvals for a project may be recorded: the project's own locations,
+/// then the user settings, which apply to every project
+fn mcpjson_settings_locations(project_path: &str) -> Result<Vec<MCPConfigLocation>, String> {
+    let home_dir = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+    let mut locations = mcpjson_approval_locations(&get_claude_json_path()?, project_path);
+    locations.push(MCPConfigLocation::file(home_dir.join(".claude").join("settings.json")));
+    Ok(locations)
 }
 
 fn infer_server_type(config: &MCPServerConfig) -> String {
@@ -2255,44 +2377,40 @@ fn infer_server_type(config: &MCPServerConfig) -> String {
 #[tauri::command]
 async fn list_mcp_servers(project_path: Option<String>) -> Result<Vec<MCPServerInfo>, String> {
     let mut servers = Vec::new();
+    let stash = read_disabled_stash(&get_disabled_stash_path()?).unwrap_or_default();
 
     // Read user-level config (~/.claude.json)
-    let user_path = get_mcp_config_path("user", None)?;
-    if let Ok(config) = read_mcp_config_from_file(&user_path) {
-        for (name, server_config) in config.mcp_servers {
-            servers.push(MCPServerInfo {
-                name: name.clone(),
-                server_type: infer_server_type(&server_config),
-                url: server_config.url,
-                command: server_config.command,
-                args: server_config.args,
-                headers: server_config.headers,
-                env: server_config.env,
-                scope: "user".to_string(),
-                source_path: user_path.to_string_lossy().to_string(),
-                enabled: true,
-            });
-        }
+    let user_location = get_mcp_config_location("user", None)?;
+    if let Ok(config) = read_mcp_config_lenient(&user_location) {
+        servers.extend(mcp_server_infos(config, "user", &user_location, None, &[], &stash));
     }
 
-    // Read project-level config (.mcp.json) if project_path provided
     if let Some(ref proj_path) = project_path {
-        let project_config_path = get_mcp_config_path("project", Some(proj_path.clone()))?;
-        if let Ok(config) = read_mcp_config_from_file(&project_config_path) {
-            for (name, server_config) in config.mcp_servers {
-                servers.push(MCPServerInfo {
-                    name: name.clone(),
-                    server_type: infer_server_type(&server_config),
-                    url: server_config.url,
-                    command: server_config.command,
-                    args: server_config.args,
-                    headers: server_config.headers,
-                    env: server_config.env,
-                    scope: "project".to_string(),
-                    source_path: project_config_path.to_string_lossy().to_string(),
-                    enabled: true,
-                });
-            }
+        // Read project-level config (.mcp.json); these are turned off through Claude Code's own list
+        let project_location = get_mcp_config_location("project", Some(proj_path.clone()))?;
+        if let Ok(config) = read_mcp_config_lenient(&project_location) {
+            let disabled = disabled_mcpjson_servers(&mcpjson_settings_locations(proj_path)?);
+            servers.extend(mcp_server_infos(
+                config,
+                "project",
+                &project_location,
+                Some(proj_path),
+                &disabled,
+                &stash,
+            ));
+        }
+
+        // Read local config (this project's entry in ~/.claude.json)
+        let local_location = get_mcp_config_location("local", Some(proj_path.clone()))?;
+        if let Ok(config) = read_mcp_config_lenient(&local_location) {
+            servers.extend(mcp_server_infos(
+                config,
+                "local",
+                &local_location,
+                Some(proj_path),
+                &[],
+                &stash,
+            ));
         }
     }
 
@@ -2304,8 +2422,8 @@ async fn read_mcp_config(
     scope: String,
     project_path: Option<String>,
 ) -> Result<MCPConfigFile, String> {
-    let config_path = get_mcp_config_path(&scope, project_path)?;
-    read_mcp_config_from_file(&config_path)
+    let location = get_mcp_config_location(&scope, project_path)?;
+    read_mcp_config_at(&location)
 }
 
 #[tauri::command]
@@ -2313,32 +2431,73 @@ async fn write_mcp_config(
     scope: String,
     config: MCPConfigFile,
     project_path: Option<String>,
-) -> Result<String, String> {
-    let config_path = get_mcp_config_path(&scope, project_path)?;
-    write_mcp_config_to_file(&config_path, &config)?;
-    Ok(config_path.to_string_lossy().to_string())
+    expected: Option<ExpectedVersion>,
+) -> Result<String, MCPWriteError> {
+    let location = get_mcp_config_location(&scope, project_path)?;
+    let mut diagnostics = Vec::new();
+    for (name, server_config) in &config.mcp_servers {
+        diagnostics.extend(validate_mcp_server_config(&location, name, server_config).await?);
+    }
+    mcp_validate::ensure_valid(diagnostics)?;
+
+    write_mcp_config_at(&location, &config, expected.as_ref())?;
+    Ok(location.path.to_string_lossy().to_string())
+}
+
+/// Diagnostics for a server entry about to be written to `location`
+async fn validate_mcp_server_config(
+    location: &MCPConfigLocation,
+    name: &str,
+    server_config: &MCPServerConfig,
+) -> Result<Vec<Diagnostic>, String> {
+    let entry = serde_json::to_value(server_config)
+        .map_err(|e| format!("Failed to serialize MCP server: {}", e))?;
+    let (servers_pointer, _) = read_raw_mcp_servers(location)?;
+    let pointer = format!("{}/{}", servers_pointer, json_pointer_token(name));
+    let env = tauri::async_runtime::spawn_blocking(mcp_env::login_shell_env)
+        .await
+        .map_err(|e| format!("Failed to read environment: {}", e))?;
+    let env = mcp_validate::SystemEnvironment(|name: &str| env.get(name).cloned());
+    Ok(mcp_validate::validate_server(&entry, &pointer, &env))
 }
 
+/// Add a server, replacing one with the same name unless `overwrite` is `Some(false)`
+/// (used by imports, which must never replace an existing server)
 #[tauri::command]
 async fn add_mcp_server(
     scope: String,
     name: String,
     server_config: MCPServerConfig,
     project_path: Option<String>,
-) -> Result<String, String> {
+    overwrite: Option<bool>,
+) -> Result<String, MCPWriteError> {
     // Validate server name
     if name.trim().is_empty() {
-        return Err("Server name cannot be empty".to_string());
+        return Err("Server name cannot be empty".to_string().into());
     }
 
-    let config_path = get_mcp_config_path(&scope, project_path)?;
-    let mut config = read_mcp_config_from_file(&config_path)?;
+    let location = get_mcp_config_location(&scope, project_path)?;
+    mcp_validate::ensure_valid(validate_mcp_server_config(&location, &name, &server_config).await?)?;
+    let (mut config, expected) = read_mcp_config_with_version(&location)?;
 
-    // Add the new server
+    if overwrite == Some(false) {
+        let stash = read_disabled_stash(&get_disabled_stash_path()?).unwrap_or_default();
+        if config.mcp_servers.contains_key(&name)
+            || stash.mcp_servers_at(&location).any(|s| s.name == name)
+        {
+            return Err(format!("A server named '{}' already exists in {} scope", name, scope).into());
+        }
+    }
+
+    // Add the new server, keeping any fields of the entry it replaces that Vinsly doesn't model
+    let mut server_config = server_config;
+    if let Some(previous) = config.mcp_servers.get(&name) {
+        server_config.inherit_unknown_fields(previous);
+    }
     config.mcp_servers.insert(name, server_config);
 
-    write_mcp_config_to_file(&config_path, &config)?;
-    Ok(config_path.to_string_lossy().to_string())
+    write_mcp_config_at(&location, &config, expected.as_ref())?;
+    Ok(location.path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
@@ -2346,28 +2505,483 @@ async fn remove_mcp_server(
     scope: String,
     name: String,
     project_path: Option<String>,
-) -> Result<String, String> {
-    let config_path = get_mcp_config_path(&scope, project_path)?;
-    let mut config = read_mcp_config_from_file(&config_path)?;
+) -> Result<String, WriteError> {
+    let location = get_mcp_config_location(&scope, project_path)?;
+    let (mut config, expected) = read_mcp_config_with_version(&location)?;
+
+    if config.mcp_servers.shift_remove(&name).is_none() {
+        // A disabled server only exists in the stash
+        let stash_path = get_disabled_stash_path()?;
+        let (mut stash, stash_expected) = read_disabled_stash_with_version(&stash_path)?;
+        if stash.take_mcp_server(&location, &name).is_none() {
+            return Err(format!("Server '{}' not found in {} scope", name, scope).into());
+        }
+        write_disabled_stash(&stash_path, &stash, stash_expected.as_ref())?;
+        return Ok(location.path.to_string_lossy().to_string());
+    }
 
-    if config.mcp_servers.remove(&name).is_none() {
-        return Err(format!("Server '{}' not found in {} scope", name, scope));
+    write_mcp_config_at(&location, &config, expected.as_ref())?;
+    Ok(location.path.to_string_lossy().to_string())
+}
+
+/// Turn an MCP server off without deleting it, or back on.
+/// Project servers use Claude Code's `disabledMcpjsonServers`; user and local servers are
+/// moved into Vinsly's stash and restored verbatim, at their old position, on enable.
+#[tauri::command]
+async fn set_mcp_server_enabled(
+    scope: String,
+    name: String,
+    enabled: bool,
+    project_path: Option<String>,
+) -> Result<String, WriteError> {
+    let location = get_mcp_config_location(&scope, project_path.clone())?;
+    if scope == "project" {
+        let proj_path = project_path.unwrap_or_default();
+        if !read_mcp_config_at(&location)?.mcp_servers.contains_key(&name) {
+            return Err(format!("Server '{}' not found in {} scope", name, scope).into());
+        }
+        set_mcpjson_server_enabled(&get_claude_json_path()?, &proj_path, &name, enabled)?;
+        return Ok(location.path.to_string_lossy().to_string());
     }
 
-    write_mcp_config_to_file(&config_path, &config)?;
-    Ok(config_path.to_string_lossy().to_string())
+    let stash_path = get_disabled_stash_path()?;
+    let (mut stash, stash_expected) = read_disabled_stash_with_version(&stash_path)?;
+    let (mut config, expected) = read_mcp_config_with_version(&location)?;
+
+    if enabled {
+        if !stash.enable_mcp_server(&mut config, &location, &scope, &name)? {
+            return Ok(location.path.to_string_lossy().to_string());
+        }
+
+        // Restore first: a failure in between leaves a duplicate rather than losing the entry
+        write_mcp_config_at(&location, &config, expected.as_ref())?;
+        write_disabled_stash(&stash_path, &stash, stash_expected.as_ref())?;
+        return Ok(location.path.to_string_lossy().to_string());
+    }
+
+    let previous_stash = stash.clone();
+    if !stash.disable_mcp_server(&mut config, &location, &scope, &name)? {
+        return Ok(location.path.to_string_lossy().to_string());
+    }
+
+    // Stash first, and undo the stash if the config can't be written
+    write_disabled_stash(&stash_path, &stash, stash_expected.as_ref())?;
+    if let Err(e) = write_mcp_config_at(&location, &config, expected.as_ref()) {
+        let _ = write_disabled_stash(&stash_path, &previous_stash, None);
+        return Err(e);
+    }
+    Ok(location.path.to_string_lossy().to_string())
 }
 
-/// Get environment variable values for the specified variable names
+/// Config files of other MCP clients found in their standard locations
+#[tauri::command]
+async fn list_mcp_import_sources(project_path: Option<String>) -> Result<Vec<ImportSource>, String> {
+    let home_dir = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+    let config_dir = dirs::config_dir().ok_or_else(|| "Failed to get config directory".to_string())?;
+    Ok(mcp_import::import_sources(
+        &home_dir,
+        &config_dir,
+        project_path.as_deref().map(Path::new),
+    ))
+}
+
+/// Resolve a client config path for import or export, with the folder `${workspaceFolder}`
+/// stands for. Standard locations may sit in directories `validate_user_file_path` blocks
+/// (~/.config), so only other, user-picked paths go through it.
+fn resolve_client_config_path(
+    client: McpClient,
+    path: &str,
+    project_path: Option<&str>,
+    must_exist: bool,
+) -> Result<(PathBuf, Option<PathBuf>), String> {
+    let home_dir = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+    let config_dir = dirs::config_dir().ok_or_else(|| "Failed to get config directory".to_string())?;
+    let known = mcp_import::import_sources(&home_dir, &config_dir, project_path.map(Path::new))
+        .into_iter()
+        .find(|source| source.client == client && source.path == path);
+
+    match known {
+        Some(source) => Ok((PathBuf::from(&source.path), mcp_import::workspace_of(&source))),
+        None => Ok((validate_user_file_path(Path::new(path), must_exist)?, None)),
+    }
+}
+
+fn read_client_config(path: &Path) -> Result<String, String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?;
+    if metadata.len() > MAX_IMPORT_FILE_SIZE {
+        return Err(format!(
+            "File too large ({} bytes). Maximum allowed is {} bytes",
+            metadata.len(),
+            MAX_IMPORT_FILE_SIZE
+        ));
+    }
+    fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))
+}
+
+/// What importing a client config into a scope would change
+#[derive(Debug, Serialize)]
+struct MCPImportPreview {
+    client: McpClient,
+    source_path: String,
+    scope: String,
+    servers: Vec<ImportCandidate>,
+    /// Entries that couldn't be mapped, as "name: reason"
+    skipped: Vec<String>,
+}
+
+/// Read another client's MCP config and compare its servers with `scope`, without writing.
+/// `path` is one of `list_mcp_import_sources` or a file the user picked.
+/// Servers are then written one by one with `add_mcp_server` and `overwrite: false`.
+#[tauri::command]
+async fn preview_mcp_import(
+    client: McpClient,
+    path: String,
+    scope: String,
+    project_path: Option<String>,
+) -> Result<MCPImportPreview, String> {
+    let (source_path, workspace) = resolve_client_config_path(client, &path, project_path.as_deref(), true)?;
+    let content = read_client_config(&source_path)?;
+    let parsed = mcp_import::parse_client_config(client, &content, workspace.as_deref())?;
+
+    // Servers disabled through Vinsly still own their name
+    let location = get_mcp_config_location(&scope, project_path)?;
+    let mut existing = read_mcp_config_at(&location)?.mcp_servers;
+    let stash = read_disabled_stash(&get_disabled_stash_path()?).unwrap_or_default();
+    for stashed in stash.mcp_servers_at(&location) {
+        existing
+            .entry(stashed.name.clone())
+            .or_insert_with(|| stashed.config.clone());
+    }
+
+    Ok(MCPImportPreview {
+        client,
+        source_path: source_path.to_string_lossy().to_string(),
+        scope,
+        servers: mcp_import::preview_import(parsed.servers, &existing),
+        skipped: parsed.skipped,
+    })
+}
+
+fn server_info_config(server: &MCPServerInfo) -> MCPServerConfig {
+    MCPServerConfig {
+        server_type: Some(server.server_type.clone()),
+        url: server.url.clone(),
+        command: server.command.clone(),
+        args: server.args.clone(),
+        headers: server.headers.clone(),
+        env: server.env.clone(),
+        extra: serde_json::Map::new(),
+    }
+}
+
+/// Write servers from `list_mcp_servers` into another client's config file, merging with
+/// what is already there. Of several servers with one name the last (narrowest scope) wins.
+#[tauri::command]
+async fn export_mcp_servers(
+    client: McpClient,
+    servers: Vec<MCPServerInfo>,
+    path: String,
+    project_path: Option<String>,
+) -> Result<ExportReport, WriteError> {
+    let (target, _) = resolve_client_config_path(client, &path, project_path.as_deref(), false)?;
+    let existing = if target.exists() {
+        Some(read_client_config(&target)?)
+    } else {
+        None
+    };
+    let expected = existing
+        .as_deref()
+        .map(|content| ExpectedVersion::of(content.as_bytes()));
+
+    let servers: IndexMap<String, MCPServerConfig> = servers
+        .iter()
+        .map(|server| (server.name.clone(), server_info_config(server)))
+        .collect();
+    let mut report = ExportReport {
+        path: target.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let content =
+        mcp_export::merge_into_client_config(client, existing.as_deref(), &servers, &mut report)?;
+    write_atomic(&target, content.as_bytes(), expected.as_ref())?;
+    Ok(report)
+}
+
+/// A `claude mcp add-json` script recreating servers from `list_mcp_servers` in their scopes
+#[tauri::command]
+async fn get_mcp_add_json_script(servers: Vec<MCPServerInfo>) -> Result<String, String> {
+    let servers: Vec<(String, String, MCPServerConfig)> = servers
+        .iter()
+        .map(|server| (server.scope.clone(), server.name.clone(), server_info_config(server)))
+        .collect();
+    mcp_export::add_json_script(&servers)
+}
+
+/// Get environment variable values for the specified variable names, as the user's
+/// login shell would see them
 #[tauri::command]
 async fn get_env_vars(var_names: Vec<String>) -> Result<std::collections::HashMap<String, Option<String>>, String> {
+    let env = tauri::async_runtime::spawn_blocking(mcp_env::login_shell_env)
+        .await
+        .map_err(|e| format!("Failed to read environment: {}", e))?;
     let mut result = std::collections::HashMap::new();
     for name in var_names {
-        result.insert(name.clone(), std::env::var(&name).ok());
+        let value = env.get(&name).cloned();
+        result.insert(name, value);
     }
     Ok(result)
 }
 
+/// Expand `${VAR}` and `${VAR:-default}` in a server config against the login-shell
+/// environment. The first call reads that environment, so it runs off the async runtime.
+async fn expand_mcp_config(config: MCPServerConfig) -> Result<ExpandedConfig, String> {
+    tauri::async_runtime::spawn_blocking(move || mcp_env::expand_with_login_env(&config))
+        .await
+        .map_err(|e| format!("Failed to expand environment variables: {}", e))
+}
+
+/// The scopes `list_mcp_servers` covers, with where each keeps its servers
+fn mcp_config_scopes(
+    project_path: Option<String>,
+) -> Result<Vec<(&'static str, MCPConfigLocation)>, String> {
+    let mut scopes = vec![("user", get_mcp_config_location("user", None)?)];
+    if let Some(proj_path) = project_path {
+        scopes.push(("project", get_mcp_config_location("project", Some(proj_path.clone()))?));
+        scopes.push(("local", get_mcp_config_location("local", Some(proj_path))?));
+    }
+    Ok(scopes)
+}
+
+/// The servers Claude Code would use in a project: one per name, from the narrowest
+/// scope that defines it, with the definitions it shadows and `.mcp.json` approval status
+#[tauri::command]
+async fn get_effective_mcp_servers(project_path: String) -> Result<Vec<EffectiveServer>, String> {
+    let approvals = McpjsonApprovals::read(&mcpjson_settings_locations(&project_path)?);
+    let scopes = mcp_config_scopes(Some(project_path))?
+        .into_iter()
+        .map(|(scope, location)| ScopeDefinitions {
+            scope: scope.to_string(),
+            source_path: location.path.to_string_lossy().to_string(),
+            servers: read_mcp_config_lenient(&location)
+                .map(|config| config.mcp_servers)
+                .unwrap_or_default(),
+        })
+        .collect();
+    Ok(mcp_resolve::resolve_effective_servers(scopes, &approvals))
+}
+
+/// How a project's `.mcp.json` servers have been answered
+#[derive(Debug, Serialize)]
+struct MCPProjectApprovals {
+    enable_all: bool,
+    servers: Vec<MCPServerApproval>,
+}
+
+#[derive(Debug, Serialize)]
+struct MCPServerApproval {
+    name: String,
+    approval: McpjsonApproval,
+}
+
+#[tauri::command]
+async fn get_mcpjson_approvals(project_path: String) -> Result<MCPProjectApprovals, String> {
+    let approvals = McpjsonApprovals::read(&mcpjson_settings_locations(&project_path)?);
+    let location = get_mcp_config_location("project", Some(project_path))?;
+    let servers = read_mcp_config_lenient(&location)?
+        .mcp_servers
+        .into_keys()
+        .map(|name| MCPServerApproval {
+            approval: approvals.status(&name),
+            name,
+        })
+        .collect();
+    Ok(MCPProjectApprovals {
+        enable_all: approvals.enable_all,
+        servers,
+    })
+}
+
+/// Settings Vinsly reads but never rewrites: the project's shared `.claude/settings.json`
+/// and the user settings
+fn mcpjson_read_only_locations(project_path: &str) -> Result<Vec<MCPConfigLocation>, String> {
+    Ok(mcpjson_settings_locations(project_path)?.split_off(2))
+}
+
+/// Approve or reject a `.mcp.json` server ahead of Claude Code's prompt, or reset it to pending
+#[tauri::command]
+async fn set_mcpjson_server_approval(
+    project_path: String,
+    name: String,
+    approval: McpjsonApproval,
+) -> Result<(), WriteError> {
+    let read_only = mcpjson_read_only_locations(&project_path)?;
+    mcp_config::set_mcpjson_server_approval(
+        &get_claude_json_path()?,
+        &project_path,
+        &name,
+        approval,
+        &read_only,
+    )
+}
+
+#[tauri::command]
+async fn set_enable_all_project_mcp_servers(
+    project_path: String,
+    enabled: bool,
+) -> Result<(), WriteError> {
+    let read_only = mcpjson_read_only_locations(&project_path)?;
+    mcp_config::set_enable_all_project_mcp_servers(
+        &get_claude_json_path()?,
+        &project_path,
+        enabled,
+        &read_only,
+    )
+}
+
+/// Variables a configured server references that resolve to nothing
+#[derive(Debug, Serialize)]
+struct MCPUnresolvedVariables {
+    scope: String,
+    name: String,
+    variables: Vec<String>,
+}
+
+/// Report, per server, the `${VAR}` references Claude Code would fail to expand.
+/// Covers the same scopes as `list_mcp_servers`; servers without problems are left out.
+#[tauri::command]
+async fn get_mcp_unresolved_variables(
+    project_path: Option<String>,
+) -> Result<Vec<MCPUnresolvedVariables>, String> {
+    let mut report = Vec::new();
+    for (scope, location) in mcp_config_scopes(project_path)? {
+        let Ok(config) = read_mcp_config_lenient(&location) else {
+            continue;
+        };
+        for (name, server) in config.mcp_servers {
+            let expanded = expand_mcp_config(server).await?;
+            if !expanded.unresolved.is_empty() {
+                report.push(MCPUnresolvedVariables {
+                    scope: scope.to_string(),
+                    name,
+                    variables: expanded.unresolved,
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn get_mcp_secrets_index_path() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join("com.vinsly.desktop").join("mcp-secrets.json"))
+        .ok_or_else(|| "Failed to get app data directory".to_string())
+}
+
+/// Secrets moved to the keychain, as environment variables for processes Vinsly starts
+fn mcp_secrets_env() -> std::collections::HashMap<String, String> {
+    get_mcp_secrets_index_path()
+        .map(|path| mcp_secrets::secrets_env(&path))
+        .unwrap_or_default()
+}
+
+/// A header or env value of a configured server that looks like a plaintext credential
+#[derive(Debug, Serialize)]
+struct MCPSecretCandidate {
+    scope: String,
+    name: String,
+    #[serde(flatten)]
+    candidate: SecretCandidate,
+}
+
+/// Check every server in the scopes `list_mcp_servers` covers, including entries too
+/// malformed to be listed. Servers without problems are left out.
+#[tauri::command]
+async fn validate_mcp_servers(project_path: Option<String>) -> Result<Vec<ServerDiagnostics>, String> {
+    let scopes: Vec<ScopeServers> = mcp_config_scopes(project_path)?
+        .into_iter()
+        .map(|(scope, location)| {
+            let raw = read_raw_mcp_servers(&location);
+            ScopeServers {
+                scope: scope.to_string(),
+                source_path: location.path.to_string_lossy().to_string(),
+                pointer: raw
+                    .as_ref()
+                    .map(|(pointer, _)| pointer.clone())
+                    .unwrap_or_default(),
+                servers: raw.map(|(_, servers)| servers),
+            }
+        })
+        .collect();
+    let env = tauri::async_runtime::spawn_blocking(mcp_env::login_shell_env)
+        .await
+        .map_err(|e| format!("Failed to read environment: {}", e))?;
+    let env = mcp_validate::SystemEnvironment(|name: &str| env.get(name).cloned());
+    Ok(mcp_validate::validate_scopes(&scopes, &env))
+}
+
+/// Find plaintext credentials in MCP configs: `Authorization` headers and env names
+/// ending in `_KEY` or `_TOKEN`. Values that are already `${VAR}` references are skipped.
+#[tauri::command]
+async fn detect_mcp_secrets(project_path: Option<String>) -> Result<Vec<MCPSecretCandidate>, String> {
+    let mut found = Vec::new();
+    for (scope, location) in mcp_config_scopes(project_path)? {
+        let Ok(config) = read_mcp_config_lenient(&location) else {
+            continue;
+        };
+        for (name, server) in &config.mcp_servers {
+            found.extend(mcp_secrets::detect_secrets(server).into_iter().map(|candidate| {
+                MCPSecretCandidate {
+                    scope: scope.to_string(),
+                    name: name.clone(),
+                    candidate,
+                }
+            }));
+        }
+    }
+    Ok(found)
+}
+
+/// Move a header or env value of a server into the OS keychain and leave a
+/// `${VINSLY_…}` reference in its place. Returns the reference name.
+#[tauri::command]
+async fn move_mcp_secret_to_keychain(
+    scope: String,
+    name: String,
+    field: SecretField,
+    key: String,
+    project_path: Option<String>,
+) -> Result<String, WriteError> {
+    let location = get_mcp_config_location(&scope, project_path)?;
+    let (mut config, expected) = read_mcp_config_with_version(&location)?;
+    let server = config
+        .mcp_servers
+        .get_mut(&name)
+        .ok_or_else(|| format!("Server '{}' not found in {} scope", name, scope))?;
+
+    let secret = mcp_secrets::extract_secret(server, field, &key, |value| {
+        mcp_secrets::unique_reference_name(
+            &mcp_secrets::reference_name(&name, &key),
+            value,
+            mcp_secrets::load_secret,
+        )
+    })?;
+
+    // Store first, so the config never points at a secret that isn't there
+    mcp_secrets::store_secret(&secret.reference, &secret.value)?;
+    set_name_listed(&get_mcp_secrets_index_path()?, &secret.reference, true)?;
+    write_mcp_config_at(&location, &config, expected.as_ref())?;
+    Ok(secret.reference)
+}
+
+/// Shell profile lines that export the keychain secrets, so Claude Code started from
+/// any terminal can expand the `${VINSLY_…}` references
+#[tauri::command]
+async fn get_mcp_secrets_launcher_script() -> Result<String, String> {
+    let names = read_name_list(&get_mcp_secrets_index_path()?);
+    mcp_secrets::launcher_script(&names)
+}
+
 /// Result of an MCP server health check
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct MCPHealthCheckResult {
@@ -2375,102 +2989,195 @@ struct MCPHealthCheckResult {
     status: String,
     latency_ms: Option<u64>,
     error_message: Option<String>,
+    /// Reported by the server when the initialize handshake succeeded
+    protocol_version: Option<String>,
+    server_info: Option<ServerInfo>,
+    capabilities: Option<serde_json::Value>,
+    /// `${VAR}` references without a value; the server isn't started when there are any
+    unresolved_variables: Vec<String>,
 }
 
-/// Check the health/connectivity of an MCP server
+/// Check an MCP server by performing the `initialize` handshake over its transport.
+/// `server_config` carries args, env and headers; without it the server is probed from
+/// `server_type`, `url` and `command` alone.
 #[tauri::command]
 async fn check_mcp_server_health(
+    app: tauri::AppHandle,
     server_type: String,
     server_name: String,
     url: Option<String>,
     command: Option<String>,
+    server_config: Option<MCPServerConfig>,
     timeout_ms: u64,
 ) -> Result<MCPHealthCheckResult, String> {
-    use std::time::{Duration, Instant};
-
-    match server_type.as_str() {
-        "http" | "sse" => {
-            let url = url.ok_or("URL required for HTTP/SSE server")?;
-            let client = reqwest::Client::builder()
-                .timeout(Duration::from_millis(timeout_ms))
-                .build()
-                .map_err(|e| e.to_string())?;
-
-            let start = Instant::now();
-            match client.head(&url).send().await {
-                Ok(response) => {
-                    let latency = start.elapsed().as_millis() as u64;
-                    // Accept success codes and 405 Method Not Allowed (server doesn't support HEAD)
-                    if response.status().is_success() || response.status() == 405 {
-                        Ok(MCPHealthCheckResult {
-                            server_name,
-                            status: "connected".to_string(),
-                            latency_ms: Some(latency),
-                            error_message: None,
-                        })
-                    } else {
-                        Ok(MCPHealthCheckResult {
-                            server_name,
-                            status: "error".to_string(),
-                            latency_ms: Some(latency),
-                            error_message: Some(format!("HTTP {}", response.status())),
-                        })
-                    }
-                }
-                Err(e) => {
-                    let error_msg = if e.is_timeout() {
-                        "Connection timeout".to_string()
-                    } else if e.is_connect() {
-                        "Connection refused".to_string()
-                    } else {
-                        e.to_string()
-                    };
-                    Ok(MCPHealthCheckResult {
-                        server_name,
-                        status: "disconnected".to_string(),
-                        latency_ms: None,
-                        error_message: Some(error_msg),
-                    })
-                }
+    let mut config = server_config.unwrap_or_default();
+    config.server_type = Some(server_type);
+    if config.url.is_none() {
+        config.url = url;
+    }
+    if config.command.is_none() {
+        config.command = command;
+    }
+    // Reject configs that can't be probed at all, e.g. an unknown type
+    TransportKind::of(&config).map_err(|e| e.to_string())?;
+
+    // Like Claude Code, don't start a server with references that expand to nothing
+    let ExpandedConfig { config, unresolved } = expand_mcp_config(config).await?;
+    if !unresolved.is_empty() {
+        return Ok(MCPHealthCheckResult {
+            server_name,
+            status: "error".to_string(),
+            latency_ms: None,
+            error_message: Some(unresolved_message(&unresolved)),
+            protocol_version: None,
+            server_info: None,
+            capabilities: None,
+            unresolved_variables: unresolved,
+        });
+    }
+
+    let token = oauth_access_token(&app, &server_name, &config).await;
+    let start = Instant::now();
+    let result =
+        mcp_client::probe(&config, token.as_deref(), Duration::from_millis(timeout_ms)).await;
+    let latency = start.elapsed().as_millis() as u64;
+
+    Ok(match result {
+        Ok(initialized) => MCPHealthCheckResult {
+            server_name,
+            status: "connected".to_string(),
+            latency_ms: Some(latency),
+            error_message: None,
+            protocol_version: Some(initialized.protocol_version),
+            server_info: Some(initialized.server_info),
+            capabilities: Some(initialized.capabilities),
+            unresolved_variables: Vec::new(),
+        },
+        Err(e) => {
+            // The server answered but refused us; anything else means we never got an MCP session
+            let status = match e {
+                McpError::Unauthorized { .. } | McpError::Rpc { .. } => "error",
+                McpError::Transport { .. } | McpError::Timeout { .. } => "disconnected",
+            };
+            MCPHealthCheckResult {
+                server_name,
+                status: status.to_string(),
+                latency_ms: None,
+                error_message: Some(e.to_string()),
+                protocol_version: None,
+                server_info: None,
+                capabilities: None,
+                unresolved_variables: Vec::new(),
             }
         }
-        "stdio" => {
-            let command = command.ok_or("Command required for stdio server")?;
-            let cmd_parts: Vec<&str> = command.split_whitespace().collect();
-            if cmd_parts.is_empty() {
-                return Err("Empty command".to_string());
-            }
+    })
+}
 
-            // Check if the command is available via `which`
-            let result = tokio::process::Command::new("which")
-                .arg(cmd_parts[0])
-                .output()
-                .await;
-
-            match result {
-                Ok(output) if output.status.success() => Ok(MCPHealthCheckResult {
-                    server_name,
-                    status: "connected".to_string(),
-                    latency_ms: None,
-                    error_message: None,
-                }),
-                _ => Ok(MCPHealthCheckResult {
-                    server_name,
-                    status: "disconnected".to_string(),
-                    latency_ms: None,
-                    error_message: Some(format!("Command '{}' not found in PATH", cmd_parts[0])),
-                }),
-            }
+const DEFAULT_INVENTORY_TIMEOUT_MS: u64 = 30000;
+
+/// Connect to a configured MCP server and list its tools, resources and prompts.
+/// Results are cached per server until `refresh` is set or the server's config changes.
+#[tauri::command]
+async fn get_mcp_server_inventory(
+    app: tauri::AppHandle,
+    scope: String,
+    name: String,
+    project_path: Option<String>,
+    refresh: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<McpInventory, McpError> {
+    let location = get_mcp_config_location(&scope, project_path.clone())?;
+    let config = read_mcp_config_lenient(&location)?
+        .mcp_servers
+        .shift_remove(&name)
+        .ok_or_else(|| format!("Server '{}' not found in {} scope", name, scope))?;
+
+    let server_id = format!("{}:{}:{}", scope, project_path.unwrap_or_default(), name);
+    let key = cache_key(&server_id, &config);
+    if !refresh.unwrap_or(false) {
+        if let Some(inventory) = cached_inventory(&key) {
+            return Ok(inventory);
         }
-        _ => Err(format!("Unknown server type: {}", server_type)),
     }
+
+    let ExpandedConfig { config, unresolved } = expand_mcp_config(config).await?;
+    if !unresolved.is_empty() {
+        return Err(unresolved_message(&unresolved).into());
+    }
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_INVENTORY_TIMEOUT_MS));
+    let token = oauth_access_token(&app, &name, &config).await;
+    let inventory = load_inventory(&config, token.as_deref(), timeout).await?;
+    store_inventory(key, inventory.clone());
+    Ok(inventory)
+}
+
+const DEFAULT_TOOL_CALL_TIMEOUT_MS: u64 = 60000;
+
+/// Call one tool of a configured MCP server with JSON `arguments` and return the raw
+/// result, any error and timings. Uses the server's configured env and headers with their
+/// `${VAR}` references expanded, plus the OAuth token stored for it, if any.
+#[tauri::command]
+async fn call_mcp_tool(
+    app: tauri::AppHandle,
+    scope: String,
+    name: String,
+    tool_name: String,
+    arguments: serde_json::Value,
+    project_path: Option<String>,
+    timeout_ms: Option<u64>,
+) -> Result<ToolCallOutcome, McpError> {
+    let location = get_mcp_config_location(&scope, project_path)?;
+    let config = read_mcp_config_lenient(&location)?
+        .mcp_servers
+        .shift_remove(&name)
+        .ok_or_else(|| format!("Server '{}' not found in {} scope", name, scope))?;
+    let ExpandedConfig { config, unresolved } = expand_mcp_config(config).await?;
+    if !unresolved.is_empty() {
+        return Err(unresolved_message(&unresolved).into());
+    }
+
+    let token = oauth_access_token(&app, &name, &config).await;
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TOOL_CALL_TIMEOUT_MS));
+    Ok(mcp_client::call_tool(&config, token.as_deref(), &tool_name, arguments, timeout).await)
 }
 
 // ============================================================================
 // OAuth Authentication Commands
 // ============================================================================
 
-const KEYRING_SERVICE: &str = "vinsly-mcp-oauth";
+/// Servers with a token in the keychain, for the background refresher
+fn get_oauth_servers_path() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join("com.vinsly.desktop").join("oauth-servers.json"))
+        .ok_or_else(|| "Failed to get app data directory".to_string())
+}
+
+/// Record whether `server_name` holds a token. Only affects background refreshing,
+/// so failures are logged rather than failing the command.
+fn record_authorized_server(server_name: &str, authorized: bool) {
+    let result = get_oauth_servers_path()
+        .and_then(|path| set_name_listed(&path, server_name, authorized));
+    if let Err(e) = result {
+        eprintln!("Failed to update OAuth server list: {}", e);
+    }
+}
+
+/// OAuth access token for a remote server, renewed first if it is about to expire.
+/// Refreshes and failures that need a new sign-in are emitted like the background ones.
+async fn oauth_access_token(
+    app: &tauri::AppHandle,
+    server_name: &str,
+    config: &MCPServerConfig,
+) -> Option<String> {
+    if matches!(TransportKind::of(config), Ok(TransportKind::Stdio)) {
+        return None;
+    }
+    let (token, event) = oauth_refresh::fresh_access_token(&KeyringTokenStore, server_name).await;
+    if let Some((event, payload)) = event {
+        let _ = app.emit(event, payload);
+    }
+    token
+}
 
 /// Get the authentication status for an MCP server
 #[tauri::command]
@@ -2527,6 +3234,7 @@ async fn store_mcp_oauth_token(
         .set_password(&token_data.to_string())
         .map_err(|e| format!("Failed to store token: {}", e))?;
 
+    record_authorized_server(&server_name, true);
     Ok(())
 }
 
@@ -2558,6 +3266,7 @@ async fn revoke_mcp_oauth(server_name: String) -> Result<(), String> {
 
     // Try to delete, but don't error if not found
     let _ = entry.delete_password();
+    record_authorized_server(&server_name, false);
     Ok(())
 }
 
@@ -2567,34 +3276,44 @@ async fn open_oauth_url(url: String) -> Result<(), String> {
     open::that(&url).map_err(|e| format!("Failed to open browser: {}", e))
 }
 
-// ============================================================================
-// Hooks Configuration Commands
-// ============================================================================
+/// How long the user has to finish signing in before the flow gives up
+const OAUTH_AUTHORIZE_TIMEOUT: Duration = Duration::from_secs(300);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct HookConfig {
-    #[serde(rename = "type")]
-    event_type: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    matcher: Option<String>,
-    command: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    timeout: Option<u64>,
+/// Run the OAuth 2.1 authorization code flow with PKCE for a remote MCP server: discover
+/// its authorization server, register a client, send the user to the browser and catch
+/// the redirect on a loopback listener, then store the tokens in the OS keychain.
+#[tauri::command]
+async fn authorize_mcp_server(
+    server_name: String,
+    server_url: String,
+    scope: Option<String>,
+) -> Result<(), String> {
+    let token = oauth::authorize(
+        &server_url,
+        scope,
+        |url| open::that(url).map_err(|e| format!("Failed to open browser: {}", e)),
+        OAUTH_AUTHORIZE_TIMEOUT,
+    )
+    .await?;
+
+    KeyringTokenStore.save(&server_name, &token)?;
+    record_authorized_server(&server_name, true);
+    Ok(())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-struct HooksConfigFile {
-    #[serde(default)]
-    hooks: std::collections::HashMap<String, Vec<HookConfig>>,
-}
+// ============================================================================
+// Hooks Configuration Commands
+// ============================================================================
 
 #[derive(Debug, Serialize, Deserialize)]
 struct HookInfo {
     id: String,
     name: String,
     event_type: String,
+    execution_type: String,
     matcher: Option<String>,
-    command: String,
+    command: Option<String>,
+    prompt: Option<String>,
     timeout: Option<u64>,
     scope: String,
     source_path: String,
@@ -2630,137 +3349,91 @@ fn get_hooks_config_path(scope: &str, project_path: Option<String>) -> Result<Pa
     }
 }
 
-fn read_hooks_from_settings_file(path: &Path) -> Result<HooksConfigFile, String> {
-    if !path.exists() {
-        return Ok(HooksConfigFile::default());
-    }
-
-    let content = fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read hooks config file: {}", e))?;
-
-    if content.trim().is_empty() {
-        return Ok(HooksConfigFile::default());
-    }
-
-    // Parse the full settings file and extract hooks
-    let full_settings: serde_json::Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse settings file: {}", e))?;
-
-    let hooks_value = full_settings.get("hooks").cloned().unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
-    let hooks: std::collections::HashMap<String, Vec<HookConfig>> = serde_json::from_value(hooks_value)
-        .unwrap_or_default();
-
-    Ok(HooksConfigFile { hooks })
-}
-
-fn write_hooks_to_settings_file(path: &Path, hooks_config: &HooksConfigFile) -> Result<(), String> {
-    // Create parent directory if it doesn't exist
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create directory: {}", e))?;
-    }
-
-    // Read existing settings to preserve other fields
-    let mut full_settings: serde_json::Value = if path.exists() {
-        let content = fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read existing settings: {}", e))?;
-        if content.trim().is_empty() {
-            serde_json::Value::Object(serde_json::Map::new())
-        } else {
-            serde_json::from_str(&content)
-                .map_err(|e| format!("Failed to parse existing settings: {}", e))?
-        }
-    } else {
-        serde_json::Value::Object(serde_json::Map::new())
+// One HookInfo per hook command; matcher groups are flattened in file order, followed by
+// the file's disabled hooks. Ids are derived from hook content so they survive reorders
+// and external edits elsewhere.
+fn hook_infos(
+    config: HooksConfigFile,
+    scope: &str,
+    source_path: &Path,
+    stash: &DisabledStash,
+    hook_index: &mut usize,
+) -> Vec<HookInfo> {
+    let mut hooks = Vec::new();
+    let mut push = |id: String,
+                    event_type: &str,
+                    matcher: Option<String>,
+                    hook: &HookCommand,
+                    enabled: bool| {
+        hooks.push(HookInfo {
+            id,
+            name: format!("{}-hook-{}", event_type.to_lowercase(), hook_index),
+            event_type: event_type.to_string(),
+            execution_type: hook.hook_type.clone(),
+            matcher,
+            command: hook.command.clone(),
+            prompt: hook.prompt.clone(),
+            timeout: hook.timeout,
+            scope: scope.to_string(),
+            source_path: source_path.to_string_lossy().to_string(),
+            enabled,
+        });
+        *hook_index += 1;
     };
 
-    // Update hooks field
-    let hooks_value = serde_json::to_value(&hooks_config.hooks)
-        .map_err(|e| format!("Failed to serialize hooks: {}", e))?;
-
-    if let serde_json::Value::Object(ref mut map) = full_settings {
-        map.insert("hooks".to_string(), hooks_value);
+    let source = HookSource::new(scope, source_path);
+    for location in config.locations(source) {
+        let group = &config.hooks[&location.event_type][location.group_index];
+        let hook = &group.hooks[location.hook_index];
+        push(location.id.clone(), &location.event_type, group.matcher.clone(), hook, true);
     }
-
-    let content = serde_json::to_string_pretty(&full_settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-
-    fs::write(path, content)
-        .map_err(|e| format!("Failed to write settings file: {}", e))?;
-
-    Ok(())
+    for stashed in stash.hooks_at(source_path) {
+        push(
+            stashed.id.clone(),
+            &stashed.event_type,
+            stashed.matcher.clone(),
+            &stashed.hook,
+            false,
+        );
+    }
+    hooks
 }
 
 #[tauri::command]
 async fn list_hooks(project_path: Option<String>) -> Result<Vec<HookInfo>, String> {
     let mut hooks = Vec::new();
     let mut hook_index = 0;
+    let stash = read_disabled_stash(&get_disabled_stash_path()?).unwrap_or_default();
 
     // Read user-level config (~/.claude/settings.json)
     let user_path = get_hooks_config_path("user", None)?;
     if let Ok(config) = read_hooks_from_settings_file(&user_path) {
-        for (event_type, event_hooks) in config.hooks {
-            for hook_config in event_hooks {
-                let name = format!("{}-hook-{}", event_type.to_lowercase(), hook_index);
-                hooks.push(HookInfo {
-                    id: format!("user:{}:{}", name, hook_index),
-                    name: name.clone(),
-                    event_type: event_type.clone(),
-                    matcher: hook_config.matcher,
-                    command: hook_config.command,
-                    timeout: hook_config.timeout,
-                    scope: "user".to_string(),
-                    source_path: user_path.to_string_lossy().to_string(),
-                    enabled: true,
-                });
-                hook_index += 1;
-            }
-        }
+        hooks.extend(hook_infos(config, "user", &user_path, &stash, &mut hook_index));
     }
 
     // Read project-level config (.claude/settings.json) if project_path provided
     if let Some(ref proj_path) = project_path {
         let project_config_path = get_hooks_config_path("project", Some(proj_path.clone()))?;
         if let Ok(config) = read_hooks_from_settings_file(&project_config_path) {
-            for (event_type, event_hooks) in config.hooks {
-                for hook_config in event_hooks {
-                    let name = format!("{}-hook-{}", event_type.to_lowercase(), hook_index);
-                    hooks.push(HookInfo {
-                        id: format!("project:{}:{}", name, hook_index),
-                        name: name.clone(),
-                        event_type: event_type.clone(),
-                        matcher: hook_config.matcher,
-                        command: hook_config.command,
-                        timeout: hook_config.timeout,
-                        scope: "project".to_string(),
-                        source_path: project_config_path.to_string_lossy().to_string(),
-                        enabled: true,
-                    });
-                    hook_index += 1;
-                }
-            }
+            hooks.extend(hook_infos(
+                config,
+                "project",
+                &project_config_path,
+                &stash,
+                &mut hook_index,
+            ));
         }
 
         // Also check local settings
         let local_config_path = get_hooks_config_path("local", Some(proj_path.clone()))?;
         if let Ok(config) = read_hooks_from_settings_file(&local_config_path) {
-            for (event_type, event_hooks) in config.hooks {
-                for hook_config in event_hooks {
-                    let name = format!("{}-hook-{}", event_type.to_lowercase(), hook_index);
-                    hooks.push(HookInfo {
-                        id: format!("local:{}:{}", name, hook_index),
-                        name: name.clone(),
-                        event_type: event_type.clone(),
-                        matcher: hook_config.matcher,
-                        command: hook_config.command,
-                        timeout: hook_config.timeout,
-                        scope: "local".to_string(),
-                        source_path: local_config_path.to_string_lossy().to_string(),
-                        enabled: true,
-                    });
-                    hook_index += 1;
-                }
-            }
+            hooks.extend(hook_infos(
+                config,
+                "local",
+                &local_config_path,
+                &stash,
+                &mut hook_index,
+            ));
         }
     }
 
@@ -2781,62 +3454,148 @@ async fn write_hooks_config(
     scope: String,
     config: HooksConfigFile,
     project_path: Option<String>,
-) -> Result<String, String> {
+    expected: Option<ExpectedVersion>,
+) -> Result<String, WriteError> {
     let config_path = get_hooks_config_path(&scope, project_path)?;
-    write_hooks_to_settings_file(&config_path, &config)?;
+    write_hooks_to_settings_file(&config_path, &config, expected.as_ref())?;
     Ok(config_path.to_string_lossy().to_string())
 }
 
+fn validate_hook_config(hook_config: &HookConfig) -> Result<(), String> {
+    match hook_config.hook_type.as_str() {
+        "command" if hook_config.command.as_deref().unwrap_or("").trim().is_empty() => {
+            Err("Hook command cannot be empty".to_string())
+        }
+        "prompt" if hook_config.prompt.as_deref().unwrap_or("").trim().is_empty() => {
+            Err("Hook prompt cannot be empty".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns the id of the new hook
 #[tauri::command]
 async fn add_hook(
     scope: String,
     event_type: String,
     hook_config: HookConfig,
     project_path: Option<String>,
-) -> Result<String, String> {
-    // Validate hook
-    if hook_config.command.trim().is_empty() {
-        return Err("Hook command cannot be empty".to_string());
-    }
+) -> Result<String, WriteError> {
+    validate_hook_config(&hook_config)?;
 
     let config_path = get_hooks_config_path(&scope, project_path)?;
-    let mut config = read_hooks_from_settings_file(&config_path)?;
+    let (mut config, expected) = read_hooks_with_version(&config_path)?;
+    let source = HookSource::new(&scope, &config_path);
 
-    // Add the hook to the appropriate event type array
-    config.hooks
-        .entry(event_type)
-        .or_insert_with(Vec::new)
-        .push(hook_config);
+    // Add the hook to the matching matcher group of the event
+    let hook_id = config.add_hook(source, &event_type, hook_config);
 
-    write_hooks_to_settings_file(&config_path, &config)?;
-    Ok(config_path.to_string_lossy().to_string())
+    write_hooks_to_settings_file(&config_path, &config, expected.as_ref())?;
+    Ok(hook_id)
 }
 
+/// Replace a hook in place and return its new id
 #[tauri::command]
-async fn remove_hook(
+async fn update_hook(
     scope: String,
+    hook_id: String,
     event_type: String,
-    hook_index: usize,
+    hook_config: HookConfig,
     project_path: Option<String>,
-) -> Result<String, String> {
+) -> Result<String, WriteError> {
+    validate_hook_config(&hook_config)?;
+
     let config_path = get_hooks_config_path(&scope, project_path)?;
-    let mut config = read_hooks_from_settings_file(&config_path)?;
-
-    if let Some(hooks) = config.hooks.get_mut(&event_type) {
-        if hook_index < hooks.len() {
-            hooks.remove(hook_index);
-            // Remove the event type key if no hooks remain
-            if hooks.is_empty() {
-                config.hooks.remove(&event_type);
-            }
-        } else {
-            return Err(format!("Hook index {} out of range", hook_index));
+    let (mut config, expected) = read_hooks_with_version(&config_path)?;
+    let source = HookSource::new(&scope, &config_path);
+
+    let new_id = config.update_hook(source, &hook_id, event_type, hook_config)?;
+
+    write_hooks_to_settings_file(&config_path, &config, expected.as_ref())?;
+    Ok(new_id)
+}
+
+#[tauri::command]
+async fn remove_hook(
+    scope: String,
+    hook_id: String,
+    project_path: Option<String>,
+) -> Result<String, WriteError> {
+    let config_path = get_hooks_config_path(&scope, project_path)?;
+    let (mut config, expected) = read_hooks_with_version(&config_path)?;
+    let source = HookSource::new(&scope, &config_path);
+
+    if let Err(e) = config.remove_hook(source, &hook_id) {
+        // A disabled hook only exists in the stash
+        let stash_path = get_disabled_stash_path()?;
+        let (mut stash, stash_expected) = read_disabled_stash_with_version(&stash_path)?;
+        if stash.take_hook(&config_path, &hook_id).is_none() {
+            return Err(e.into());
         }
-    } else {
-        return Err(format!("No hooks found for event type '{}'", event_type));
+        write_disabled_stash(&stash_path, &stash, stash_expected.as_ref())?;
+        return Ok(config_path.to_string_lossy().to_string());
+    }
+
+    write_hooks_to_settings_file(&config_path, &config, expected.as_ref())?;
+    Ok(config_path.to_string_lossy().to_string())
+}
+
+/// Turn a hook off without deleting it, or back on. Claude Code has no per-hook switch,
+/// so disabled hooks move into Vinsly's stash and are restored verbatim on enable.
+/// Resolves to the hook's id after the change.
+#[tauri::command]
+async fn set_hook_enabled(
+    scope: String,
+    hook_id: String,
+    enabled: bool,
+    project_path: Option<String>,
+) -> Result<String, WriteError> {
+    let config_path = get_hooks_config_path(&scope, project_path)?;
+    let stash_path = get_disabled_stash_path()?;
+    let (mut stash, stash_expected) = read_disabled_stash_with_version(&stash_path)?;
+    let (mut config, expected) = read_hooks_with_version(&config_path)?;
+    let source = HookSource::new(&scope, &config_path);
+
+    if enabled {
+        let Some(new_id) = stash.enable_hook(&mut config, source, &hook_id)? else {
+            return Ok(hook_id);
+        };
+
+        // Restore first: a failure in between leaves a duplicate rather than losing the hook
+        write_hooks_to_settings_file(&config_path, &config, expected.as_ref())?;
+        write_disabled_stash(&stash_path, &stash, stash_expected.as_ref())?;
+        return Ok(new_id);
     }
 
-    write_hooks_to_settings_file(&config_path, &config)?;
+    let previous_stash = stash.clone();
+    let Some(stashed_id) = stash.disable_hook(&mut config, source, &hook_id)? else {
+        return Ok(hook_id);
+    };
+
+    // Stash first, and undo the stash if the settings file can't be written
+    write_disabled_stash(&stash_path, &stash, stash_expected.as_ref())?;
+    if let Err(e) = write_hooks_to_settings_file(&config_path, &config, expected.as_ref()) {
+        let _ = write_disabled_stash(&stash_path, &previous_stash, None);
+        return Err(e);
+    }
+    Ok(stashed_id)
+}
+
+/// Reorder the hooks of one event; `hook_ids` must list all of them
+#[tauri::command]
+async fn reorder_hooks(
+    scope: String,
+    event_type: String,
+    hook_ids: Vec<String>,
+    project_path: Option<String>,
+) -> Result<String, WriteError> {
+    let config_path = get_hooks_config_path(&scope, project_path)?;
+    let (mut config, expected) = read_hooks_with_version(&config_path)?;
+    let source = HookSource::new(&scope, &config_path);
+
+    config.reorder_hooks(source, &event_type, &hook_ids)?;
+
+    write_hooks_to_settings_file(&config_path, &config, expected.as_ref())?;
     Ok(config_path.to_string_lossy().to_string())
 }
 
@@ -3018,84 +3777,212 @@ async fn kill_claude_session(app: tauri::AppHandle, pid: u32) -> Result<(), Stri
     }
 }
 
-/// Get token usage for a Claude session by reading JSONL files
+fn get_transcript_index_path() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join("com.vinsly.desktop").join("transcript-index.json"))
+        .ok_or_else(|| "Failed to get app data directory".to_string())
+}
+
+/// Index what was appended to the transcripts in `project_dirs` since the last call,
+/// then answer from the index. Loaded from disk on first use, saved when it changes.
+async fn with_transcript_index<T>(
+    project_dirs: &[PathBuf],
+    query: impl FnOnce(&transcript_index::TranscriptIndex) -> T,
+) -> Result<T, String> {
+    let path = get_transcript_index_path()?;
+    let mut cached = transcript_index().lock().await;
+    let index = cached.get_or_insert_with(|| transcript_index::TranscriptIndex::load(&path));
+    if index.refresh(project_dirs) {
+        index.save(&path)?;
+    }
+    Ok(query(index))
+}
+
+/// Get token usage for a Claude session from the transcript index
 /// Claude stores session data in ~/.claude/projects/[encoded-path]/[sessionId].jsonl
 /// Path encoding: /Users/foo/bar -> -Users-foo-bar
 #[tauri::command]
 async fn get_session_token_usage(working_directory: String, session_start_time: u64) -> Result<u64, String> {
-    use std::fs;
-    use std::io::{BufRead, BufReader};
-    use std::time::UNIX_EPOCH;
-
-    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
-    let projects_dir = home.join(".claude").join("projects");
-
-    if !projects_dir.exists() {
+    let projects_dir = transcript::projects_dir().ok_or("Could not determine home directory")?;
+    let project_dirs = transcript::project_dirs(&projects_dir, &working_directory);
+    if project_dirs.is_empty() {
         return Ok(0);
     }
 
-    // Claude encodes paths: /Users/foo/bar -> -Users-foo-bar
-    // Leading slash becomes leading dash, all slashes become dashes
-    // Use lowercase for case-insensitive matching (macOS is case-insensitive)
-    let encoded_path = format!("-{}", working_directory.trim_start_matches('/').replace('/', "-"));
-    let encoded_path_lower = encoded_path.to_lowercase();
+    // Only transcripts modified after the session started; cached context isn't counted
+    with_transcript_index(&project_dirs, |index| index.tokens_since(&project_dirs, session_start_time)).await
+}
 
-    let mut total_tokens: u64 = 0;
+/// List the Claude Code sessions recorded for a project, most recently active first
+#[tauri::command]
+async fn list_claude_sessions(project_path: String) -> Result<Vec<transcript::SessionSummary>, String> {
+    let projects_dir = transcript::projects_dir().ok_or("Could not determine home directory")?;
+    let project_dirs = transcript::project_dirs(&projects_dir, &project_path);
+    with_transcript_index(&project_dirs, |index| index.sessions(&project_dirs)).await
+}
 
-    // Iterate through project directories looking for matches
-    let entries = fs::read_dir(&projects_dir).map_err(|e| e.to_string())?;
+/// Page through one session's messages from index `offset` on. Subagent messages are left
+/// out unless `include_sidechains` is set, which also interleaves the subagents' own
+/// transcripts and so shifts indices; without it, a search hit's `message_index` is the
+/// offset whose page starts at that hit, unless the hit is a sidechain message.
+#[tauri::command]
+async fn get_claude_session_messages(
+    project_path: String,
+    session_id: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    include_sidechains: Option<bool>,
+) -> Result<transcript::SessionPage, String> {
+    let projects_dir = transcript::projects_dir().ok_or("Could not determine home directory")?;
+    let path = transcript::find_session(&projects_dir, &project_path, &session_id)?;
+    let include_sidechains = include_sidechains.unwrap_or(false);
+    let session = transcript::read_session(&path, include_sidechains)?;
+    Ok(transcript::page(&session_id, session, offset.unwrap_or(0), limit.unwrap_or(100), include_sidechains))
+}
+
+/// One message of a session by the index search hits carry, sidechain messages included
+#[tauri::command]
+async fn get_claude_session_message(
+    project_path: String,
+    session_id: String,
+    index: usize,
+) -> Result<transcript::TranscriptMessage, String> {
+    let projects_dir = transcript::projects_dir().ok_or("Could not determine home directory")?;
+    let path = transcript::find_session(&projects_dir, &project_path, &session_id)?;
+    transcript::read_session(&path, false)?
+        .messages
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| format!("Session {} has no message {}", session_id, index))
+}
+
+fn get_transcript_search_index_path() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join("com.vinsly.desktop").join("transcript-search.json"))
+        .ok_or_else(|| "Failed to get app data directory".to_string())
+}
+
+/// Full-text search over Claude Code session transcripts: message text, tool names and
+/// the paths tools touched. Every word of `query` must match the start of a word.
+/// Covers one project, or every project when `project_path` is omitted; `since` and
+/// `until` are inclusive YYYY-MM-DD days. Newest messages first.
+#[tauri::command]
+async fn search_claude_sessions(
+    query: String,
+    project_path: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    roles: Option<Vec<transcript::Role>>,
+    limit: Option<usize>,
+) -> Result<Vec<transcript_search::SearchHit>, String> {
+    let projects_dir = transcript::projects_dir().ok_or("Could not determine home directory")?;
+    let project_dirs = match &project_path {
+        Some(project_path) => transcript::project_dirs(&projects_dir, project_path),
+        None => transcript::all_project_dirs(&projects_dir),
+    };
+    let path = get_transcript_search_index_path()?;
+    let mut cached = transcript_search_index().lock().await;
+    let index = cached.get_or_insert_with(|| transcript_search::SearchIndex::load(&path));
+    if index.refresh(&project_dirs) {
+        index.save(&path)?;
+    }
+    let query = transcript_search::SearchQuery {
+        text: query,
+        since,
+        until,
+        roles: roles.unwrap_or_default(),
+        limit,
+    };
+    Ok(index.search(&project_dirs, &query))
+}
 
-    for entry in entries.flatten() {
-        let dir_name = entry.file_name().to_string_lossy().to_string();
-        let dir_name_lower = dir_name.to_lowercase();
-
-        // Check if this directory matches our encoded working directory (case-insensitive)
-        // Use starts_with to handle cases where Claude might add suffixes
-        if dir_name_lower == encoded_path_lower || dir_name_lower.starts_with(&format!("{}-", encoded_path_lower)) {
-            // Read only JSONL files modified after the session started
-            if let Ok(files) = fs::read_dir(entry.path()) {
-                for file in files.flatten() {
-                    let path = file.path();
-                    if path.extension().map_or(false, |ext| ext == "jsonl") {
-                        // Check if file was modified after session started
-                        if let Ok(metadata) = fs::metadata(&path) {
-                            if let Ok(modified) = metadata.modified() {
-                                let modified_secs = modified.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
-                                // Only read files modified after the session started
-                                if modified_secs < session_start_time {
-                                    continue;
-                                }
-                            }
-                        }
+fn get_model_prices_path() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join("com.vinsly.desktop").join("model-prices.json"))
+        .ok_or_else(|| "Failed to get app data directory".to_string())
+}
 
-                        if let Ok(f) = fs::File::open(&path) {
-                            let reader = BufReader::new(f);
-                            for line in reader.lines().flatten() {
-                                // Parse each line as JSON and extract token usage
-                                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
-                                    // Token usage is in message.usage for assistant messages
-                                    if let Some(message) = json.get("message") {
-                                        if let Some(usage) = message.get("usage") {
-                                            // Only count actual new tokens, not cached context
-                                            // cache_read_input_tokens are reused context and shouldn't be counted
-                                            if let Some(input) = usage.get("input_tokens").and_then(|v| v.as_u64()) {
-                                                total_tokens += input;
-                                            }
-                                            if let Some(output) = usage.get("output_tokens").and_then(|v| v.as_u64()) {
-                                                total_tokens += output;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+/// Token usage and estimated cost of Claude Code sessions, per model, session, project
+/// and day. Covers one project, or every project when `project_path` is omitted;
+/// `since` and `until` are inclusive YYYY-MM-DD days.
+#[tauri::command]
+async fn get_token_usage_report(
+    project_path: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+) -> Result<token_usage::UsageReport, String> {
+    let projects_dir = transcript::projects_dir().ok_or("Could not determine home directory")?;
+    let prices = token_usage::load_prices(&get_model_prices_path()?)?;
+    let project_dirs = match &project_path {
+        Some(project_path) => transcript::project_dirs(&projects_dir, project_path),
+        None => transcript::all_project_dirs(&projects_dir),
+    };
+    let sessions = with_transcript_index(&project_dirs, |index| index.session_usage(&project_dirs)).await?;
+    Ok(token_usage::build_report(&sessions, &prices, since.as_deref(), until.as_deref()))
+}
+
+/// Price table used for cost estimates, keyed by model ID prefix
+#[tauri::command]
+fn get_model_prices() -> Result<token_usage::PriceTable, String> {
+    token_usage::load_prices(&get_model_prices_path()?)
+}
+
+#[tauri::command]
+fn set_model_prices(prices: token_usage::PriceTable) -> Result<(), String> {
+    token_usage::validate_prices(&prices)?;
+    let content = serde_json::to_string_pretty(&prices)
+        .map_err(|e| format!("Failed to serialize price table: {}", e))?;
+    write_atomic(&get_model_prices_path()?, content.as_bytes(), None).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Drop the saved price table and go back to the built-in prices
+#[tauri::command]
+fn reset_model_prices() -> Result<token_usage::PriceTable, String> {
+    let path = get_model_prices_path()?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove price table: {}", e))?;
+    }
+    Ok(token_usage::default_prices())
+}
+
+/// Export one session to Markdown, self-contained HTML or normalized JSON at a
+/// user-selected path. Subagent messages are included unless `include_sidechains` is false.
+#[tauri::command]
+async fn export_claude_session(
+    project_path: String,
+    session_id: String,
+    format: session_export::ExportFormat,
+    path: String,
+    include_sidechains: Option<bool>,
+) -> Result<(), String> {
+    // Validate the path is safe (must_exist=false for export - file doesn't exist yet)
+    let canonical_path = validate_user_file_path(&PathBuf::from(&path), false)?;
+
+    let projects_dir = transcript::projects_dir().ok_or("Could not determine home directory")?;
+    let session_path = transcript::find_session(&projects_dir, &project_path, &session_id)?;
+    let session = transcript::read_session(&session_path, include_sidechains.unwrap_or(true))?;
+    let prices = token_usage::load_prices(&get_model_prices_path()?)?;
+    let tokens = session_export::token_summary(&session, &prices);
+    let exported_at = chrono::Utc::now().to_rfc3339();
+    let content = session_export::SessionExport {
+        session_id: &session_id,
+        transcript: &session,
+        tokens: &tokens,
+        exported_at: &exported_at,
+    }
+    .render(format)?;
+
+    if let Some(parent) = canonical_path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
         }
     }
+    fs::write(&canonical_path, content)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
 
-    Ok(total_tokens)
+    Ok(())
 }
 
 // ============================================================================
@@ -3150,6 +4037,7 @@ async fn invoke_claude_code(prompt: String) -> Result<ClaudeCodeInvocationResult
     let result = tauri::async_runtime::spawn_blocking(move || -> Result<ClaudeCodeInvocationResult, String> {
         let mut cmd = Command::new(&claude_path);
         cmd.args(["-p", &prompt, "--output-format", "stream-json", "--verbose"])
+            .envs(mcp_secrets_env())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
@@ -4151,7 +5039,8 @@ fn terminal_create(
     cols: u16,
     rows: u16,
 ) -> Result<String, String> {
-    terminal::create_terminal(app, working_dir, shell, cols, rows)
+    // Claude Code started in this terminal can expand references to keychain secrets
+    terminal::create_terminal(app, working_dir, shell, cols, rows, mcp_secrets_env())
 }
 
 #[tauri::command]
@@ -4174,6 +5063,22 @@ fn terminal_close_all() -> Result<Vec<String>, String> {
     terminal::close_all_terminals()
 }
 
+// ============================================================================
+// Resource Watcher Commands
+// ============================================================================
+
+/// Watch ~/.claude and the given project roots, emitting `resource:changed` events.
+/// Calling again replaces the set of watched projects.
+#[tauri::command]
+fn start_resource_watcher(app: tauri::AppHandle, project_paths: Vec<String>) -> Result<(), String> {
+    watcher::watch_resources(app, project_paths)
+}
+
+#[tauri::command]
+fn stop_resource_watcher() -> Result<(), String> {
+    watcher::stop_watching_resources()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -4286,6 +5191,17 @@ pub fn run() {
                 })
                 .build(app)?;
 
+            // Renew MCP OAuth tokens shortly before they expire
+            if let Ok(index_path) = get_oauth_servers_path() {
+                let handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    oauth_refresh::run_refresher(&KeyringTokenStore, &index_path, |event, payload| {
+                        let _ = handle.emit(event, payload);
+                    })
+                    .await;
+                });
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -4327,24 +5243,53 @@ pub fn run() {
             write_mcp_config,
             add_mcp_server,
             remove_mcp_server,
+            set_mcp_server_enabled,
+            list_mcp_import_sources,
+            preview_mcp_import,
+            export_mcp_servers,
+            get_mcp_add_json_script,
             get_env_vars,
+            get_mcp_unresolved_variables,
+            validate_mcp_servers,
+            get_effective_mcp_servers,
+            get_mcpjson_approvals,
+            set_mcpjson_server_approval,
+            set_enable_all_project_mcp_servers,
+            detect_mcp_secrets,
+            move_mcp_secret_to_keychain,
+            get_mcp_secrets_launcher_script,
             check_mcp_server_health,
+            get_mcp_server_inventory,
+            call_mcp_tool,
             // MCP OAuth commands
             get_mcp_auth_status,
             store_mcp_oauth_token,
             get_mcp_oauth_token,
             revoke_mcp_oauth,
             open_oauth_url,
+            authorize_mcp_server,
             // Hooks commands
             list_hooks,
             read_hooks_config,
             write_hooks_config,
             add_hook,
+            update_hook,
             remove_hook,
+            reorder_hooks,
+            set_hook_enabled,
             // Session detection and actions
             detect_claude_sessions,
             kill_claude_session,
             get_session_token_usage,
+            list_claude_sessions,
+            get_claude_session_messages,
+            get_claude_session_message,
+            get_token_usage_report,
+            search_claude_sessions,
+            get_model_prices,
+            set_model_prices,
+            reset_model_prices,
+            export_claude_session,
             // Claude Code CLI integration
             check_claude_cli_installed,
             invoke_claude_code,
@@ -4370,6 +5315,9 @@ pub fn run() {
             terminal_resize,
             terminal_close,
             terminal_close_all,
+            // Resource watcher
+            start_resource_watcher,
+            stop_resource_watcher,
         ])
         .run(tauri::generate_context!())
         .unwrap_or_else(|e| {
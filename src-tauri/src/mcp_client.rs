@@ -0,0 +1,1481 @@
+use crate::mcp_config::MCPServerConfig;
+use crate::mcp_env::login_shell_env;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+
+/// Protocol revision Vinsly asks for; servers may answer with an older one
+pub const LATEST_PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// How much of a stdio server's stderr to keep for error messages
+const STDERR_TAIL_BYTES: usize = 2000;
+
+/// `serverInfo` from the initialize result
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerInfo {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// What a server reported during `initialize`
+#[derive(Debug, Clone, Serialize)]
+pub struct InitializeResult {
+    pub protocol_version: String,
+    pub server_info: ServerInfo,
+    pub capabilities: Value,
+    pub instructions: Option<String>,
+}
+
+/// Why talking to an MCP server failed, serialized to the frontend as `{ kind, message, ... }`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum McpError {
+    /// The server could not be started or reached, or did not speak MCP
+    Transport { message: String },
+    /// No answer within the allotted time
+    Timeout { message: String },
+    /// The HTTP server wants credentials
+    Unauthorized {
+        message: String,
+        www_authenticate: Option<String>,
+    },
+    /// The server answered with a JSON-RPC error
+    Rpc {
+        message: String,
+        code: i64,
+        data: Option<Value>,
+    },
+}
+
+impl From<String> for McpError {
+    fn from(message: String) -> Self {
+        Self::Transport { message }
+    }
+}
+
+impl fmt::Display for McpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport { message }
+            | Self::Timeout { message }
+            | Self::Unauthorized { message, .. } => f.write_str(message),
+            Self::Rpc { message, code, .. } => write!(f, "{} (code {})", message, code),
+        }
+    }
+}
+
+/// Transport a server config selects; untyped entries with a command are stdio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+    Stdio,
+    /// Streamable HTTP
+    Http,
+    /// The older HTTP+SSE transport
+    Sse,
+}
+
+impl TransportKind {
+    pub fn of(config: &MCPServerConfig) -> Result<Self, McpError> {
+        match config.server_type.as_deref() {
+            Some("stdio") => Ok(Self::Stdio),
+            Some("http") | Some("streamable-http") => Ok(Self::Http),
+            Some("sse") => Ok(Self::Sse),
+            Some(other) => Err(format!("Unknown server type: {}", other).into()),
+            None if config.command.is_some() => Ok(Self::Stdio),
+            None => Ok(Self::Http),
+        }
+    }
+}
+
+/// What to do with a message read while waiting for the response to `id`
+enum Received {
+    Response(Value),
+    /// Answer to a request the server sent us
+    Reply(Value),
+    Ignore,
+}
+
+fn classify(message: Value, id: i64) -> Received {
+    let is_request = message.get("method").is_some();
+    match message.get("id") {
+        Some(message_id) if !is_request => {
+            if message_id.as_i64() == Some(id) {
+                Received::Response(message)
+            } else {
+                Received::Ignore
+            }
+        }
+        Some(request_id) => {
+            let request_id = request_id.clone();
+            let reply = if message.get("method").and_then(|m| m.as_str()) == Some("ping") {
+                json!({ "jsonrpc": "2.0", "id": request_id, "result": {} })
+            } else {
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": request_id,
+                    "error": { "code": -32601, "message": "Method not supported by Vinsly" }
+                })
+            };
+            Received::Reply(reply)
+        }
+        // Notifications (logging, progress, list_changed) are not needed here
+        None => Received::Ignore,
+    }
+}
+
+fn into_result(response: Value) -> Result<Value, McpError> {
+    if let Some(error) = response.get("error") {
+        return Err(McpError::Rpc {
+            message: error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .to_string(),
+            code: error.get("code").and_then(|c| c.as_i64()).unwrap_or(0),
+            data: error.get("data").cloned(),
+        });
+    }
+    response.get("result").cloned().ok_or_else(|| {
+        "Response has neither 'result' nor 'error'"
+            .to_string()
+            .into()
+    })
+}
+
+// ----------------------------------------------------------------------------
+// stdio
+// ----------------------------------------------------------------------------
+
+type BoxedReader = Box<dyn AsyncRead + Unpin + Send>;
+type BoxedWriter = Box<dyn AsyncWrite + Unpin + Send>;
+
+/// Newline-delimited JSON-RPC over a child process's stdin/stdout
+struct StdioTransport {
+    child: Option<tokio::process::Child>,
+    writer: BoxedWriter,
+    lines: Lines<BufReader<BoxedReader>>,
+    stderr: Arc<Mutex<String>>,
+}
+
+impl StdioTransport {
+    /// Start the server's command with `base_env` under the config's own `env`
+    fn spawn(
+        config: &MCPServerConfig,
+        base_env: &HashMap<String, String>,
+    ) -> Result<Self, McpError> {
+        let command = config
+            .command
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| "Command required for stdio server".to_string())?;
+
+        // Older entries put the whole command line in `command`
+        let (program, args) = match &config.args {
+            Some(args) if !args.is_empty() => (command.to_string(), args.clone()),
+            _ => {
+                let mut parts = command.split_whitespace().map(|s| s.to_string());
+                let program = parts.next().unwrap_or_default();
+                (program, parts.collect())
+            }
+        };
+
+        let mut cmd = tokio::process::Command::new(&program);
+        cmd.env_clear()
+            .envs(base_env)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(env) = &config.env {
+            cmd.envs(env);
+        }
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| format!("Failed to start '{}': {}", program, e))?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| "Failed to open server stdin".to_string())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "Failed to open server stdout".to_string())?;
+
+        let stderr = Arc::new(Mutex::new(String::new()));
+        if let Some(child_stderr) = child.stderr.take() {
+            let tail = stderr.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(child_stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let mut tail = tail.lock().unwrap_or_else(|e| e.into_inner());
+                    tail.push_str(&line);
+                    tail.push('\n');
+                    if tail.len() > STDERR_TAIL_BYTES {
+                        let mut cut = tail.len() - STDERR_TAIL_BYTES;
+                        while !tail.is_char_boundary(cut) {
+                            cut += 1;
+                        }
+                        tail.drain(..cut);
+                    }
+                }
+            });
+        }
+
+        let mut transport = Self::from_io(Box::new(stdout), Box::new(stdin));
+        transport.child = Some(child);
+        transport.stderr = stderr;
+        Ok(transport)
+    }
+
+    fn from_io(reader: BoxedReader, writer: BoxedWriter) -> Self {
+        Self {
+            child: None,
+            writer,
+            lines: BufReader::new(reader).lines(),
+            stderr: Arc::new(Mutex::new(String::new())),
+        }
+    }
+
+    async fn write(&mut self, message: &Value) -> Result<(), McpError> {
+        let mut line = message.to_string();
+        line.push('\n');
+        let written = match self.writer.write_all(line.as_bytes()).await {
+            Ok(()) => self.writer.flush().await,
+            Err(e) => Err(e),
+        };
+        match written {
+            Ok(()) => Ok(()),
+            // The server closed stdin, most likely by exiting during startup
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Err(self.exited_error().await),
+            Err(e) => Err(format!("Failed to write to server: {}", e).into()),
+        }
+    }
+
+    /// Next JSON message on stdout. Lines that aren't JSON (stray logging) are skipped.
+    async fn read(&mut self) -> Result<Value, McpError> {
+        loop {
+            let line = self
+                .lines
+                .next_line()
+                .await
+                .map_err(|e| format!("Failed to read from server: {}", e))?;
+            let Some(line) = line else {
+                return Err(self.exited_error().await);
+            };
+            if let Ok(message @ Value::Object(_)) = serde_json::from_str::<Value>(line.trim()) {
+                return Ok(message);
+            }
+        }
+    }
+
+    async fn exited_error(&mut self) -> McpError {
+        let status = match self.child.as_mut() {
+            Some(child) => tokio::time::timeout(Duration::from_millis(500), child.wait())
+                .await
+                .ok()
+                .and_then(|status| status.ok())
+                .map(|status| format!(" ({})", status)),
+            None => None,
+        };
+        // Give the stderr reader a moment to drain
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let stderr = self
+            .stderr
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .trim()
+            .to_string();
+        let mut message = format!(
+            "Server exited{} before answering",
+            status.unwrap_or_default()
+        );
+        if !stderr.is_empty() {
+            message.push_str(": ");
+            message.push_str(&stderr);
+        }
+        message.into()
+    }
+
+    async fn round_trip(&mut self, request: &Value, id: i64) -> Result<Value, McpError> {
+        self.write(request).await?;
+        loop {
+            match classify(self.read().await?, id) {
+                Received::Response(response) => return Ok(response),
+                Received::Reply(reply) => self.write(&reply).await?,
+                Received::Ignore => {}
+            }
+        }
+    }
+
+    async fn close(mut self) {
+        if let Some(child) = self.child.as_mut() {
+            let _ = child.kill().await;
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// HTTP
+// ----------------------------------------------------------------------------
+
+/// Incremental `text/event-stream` parser over a streaming response
+pub(crate) struct SseReader {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+pub(crate) struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+impl SseReader {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Next event with data, or `None` when the stream ends
+    pub(crate) async fn next_event(&mut self) -> Result<Option<SseEvent>, McpError> {
+        loop {
+            while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+                let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+                if let Some(event) = parse_sse_block(&String::from_utf8_lossy(&block)) {
+                    return Ok(Some(event));
+                }
+            }
+            match self.response.chunk().await {
+                Ok(Some(bytes)) => self.buffer.extend(bytes.iter().filter(|b| **b != b'\r')),
+                Ok(None) => {
+                    let rest = std::mem::take(&mut self.buffer);
+                    return Ok(parse_sse_block(&String::from_utf8_lossy(&rest)));
+                }
+                Err(e) => return Err(format!("Event stream failed: {}", e).into()),
+            }
+        }
+    }
+}
+
+fn parse_sse_block(block: &str) -> Option<SseEvent> {
+    let mut event = String::from("message");
+    let mut data: Vec<&str> = Vec::new();
+    for line in block.lines() {
+        if line.starts_with(':') {
+            continue;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event = value.to_string(),
+            "data" => data.push(value),
+            _ => {}
+        }
+    }
+    if data.is_empty() {
+        return None;
+    }
+    Some(SseEvent {
+        event,
+        data: data.join("\n"),
+    })
+}
+
+fn build_headers(
+    config: &MCPServerConfig,
+    bearer_token: Option<&str>,
+) -> Result<reqwest::header::HeaderMap, McpError> {
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in config.headers.iter().flatten() {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Invalid header name '{}': {}", name, e))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| format!("Invalid value for header '{}': {}", name, e))?;
+        headers.insert(name, value);
+    }
+    // A configured Authorization header wins over a stored token
+    if let Some(token) = bearer_token {
+        if !headers.contains_key(AUTHORIZATION) {
+            let value = HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|e| format!("Invalid access token: {}", e))?;
+            headers.insert(AUTHORIZATION, value);
+        }
+    }
+    Ok(headers)
+}
+
+fn server_url(config: &MCPServerConfig) -> Result<reqwest::Url, McpError> {
+    let url = config
+        .url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .ok_or_else(|| "URL required for HTTP/SSE server".to_string())?;
+    reqwest::Url::parse(url).map_err(|e| format!("Invalid server URL '{}': {}", url, e).into())
+}
+
+fn request_error(e: reqwest::Error) -> McpError {
+    if e.is_timeout() {
+        McpError::Timeout {
+            message: "Connection timeout".to_string(),
+        }
+    } else if e.is_connect() {
+        "Connection refused".to_string().into()
+    } else {
+        e.to_string().into()
+    }
+}
+
+/// Turn non-success statuses into errors, keeping the start of the body for context
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, McpError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        let www_authenticate = response
+            .headers()
+            .get(reqwest::header::WWW_AUTHENTICATE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        return Err(McpError::Unauthorized {
+            message: "Authentication required (HTTP 401)".to_string(),
+            www_authenticate,
+        });
+    }
+    let body = response.text().await.unwrap_or_default();
+    let snippet: String = body.trim().chars().take(200).collect();
+    let mut message = format!("HTTP {}", status);
+    if !snippet.is_empty() {
+        message.push_str(": ");
+        message.push_str(&snippet);
+    }
+    Err(message.into())
+}
+
+fn content_type(response: &reqwest::Response) -> String {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+/// Streamable HTTP: every message is a POST, answered with JSON or an event stream
+struct HttpTransport {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    headers: reqwest::header::HeaderMap,
+    session_id: Option<String>,
+    protocol_version: Option<String>,
+}
+
+impl HttpTransport {
+    async fn post(&mut self, message: &Value) -> Result<reqwest::Response, McpError> {
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .headers(self.headers.clone())
+            .header(
+                reqwest::header::ACCEPT,
+                "application/json, text/event-stream",
+            )
+            .json(message);
+        if let Some(session_id) = &self.session_id {
+            request = request.header("Mcp-Session-Id", session_id);
+        }
+        if let Some(version) = &self.protocol_version {
+            request = request.header("MCP-Protocol-Version", version);
+        }
+
+        let response = request.send().await.map_err(request_error)?;
+        if let Some(session_id) = response
+            .headers()
+            .get("mcp-session-id")
+            .and_then(|v| v.to_str().ok())
+        {
+            self.session_id = Some(session_id.to_string());
+        }
+        check_status(response).await
+    }
+
+    async fn round_trip(&mut self, request: &Value, id: i64) -> Result<Value, McpError> {
+        let response = self.post(request).await?;
+        let content_type = content_type(&response);
+
+        if content_type.starts_with("application/json") {
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| format!("Invalid JSON from server: {}", e))?;
+            let messages = match body {
+                Value::Array(messages) => messages,
+                message => vec![message],
+            };
+            for message in messages {
+                if let Received::Response(response) = classify(message, id) {
+                    return Ok(response);
+                }
+            }
+            return Err(format!("Server did not answer request {}", id).into());
+        }
+
+        if content_type.starts_with("text/event-stream") {
+            let mut events = SseReader::new(response);
+            while let Some(event) = events.next_event().await? {
+                let Ok(message) = serde_json::from_str::<Value>(&event.data) else {
+                    continue;
+                };
+                match classify(message, id) {
+                    Received::Response(response) => return Ok(response),
+                    Received::Reply(reply) => {
+                        self.post(&reply).await?;
+                    }
+                    Received::Ignore => {}
+                }
+            }
+            return Err("Event stream ended before the server answered"
+                .to_string()
+                .into());
+        }
+
+        Err(format!(
+            "Not an MCP endpoint: unexpected content type '{}'",
+            if content_type.is_empty() {
+                "none"
+            } else {
+                &content_type
+            }
+        )
+        .into())
+    }
+
+    async fn close(self) {
+        // Let the server drop the session; servers without sessions answer 405, which is fine
+        if let Some(session_id) = &self.session_id {
+            let _ = self
+                .client
+                .delete(self.url.clone())
+                .headers(self.headers.clone())
+                .header("Mcp-Session-Id", session_id)
+                .send()
+                .await;
+        }
+    }
+}
+
+/// Legacy HTTP+SSE: a long-lived GET stream carries responses, requests go to the
+/// endpoint announced in its first `endpoint` event
+struct SseTransport {
+    client: reqwest::Client,
+    endpoint: reqwest::Url,
+    headers: reqwest::header::HeaderMap,
+    events: SseReader,
+}
+
+impl SseTransport {
+    async fn connect(
+        client: reqwest::Client,
+        url: reqwest::Url,
+        headers: reqwest::header::HeaderMap,
+    ) -> Result<Self, McpError> {
+        let response = client
+            .get(url.clone())
+            .headers(headers.clone())
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .map_err(request_error)?;
+        let response = check_status(response).await?;
+        let content_type = content_type(&response);
+        if !content_type.starts_with("text/event-stream") {
+            return Err(format!(
+                "Not an MCP SSE endpoint: unexpected content type '{}'",
+                if content_type.is_empty() {
+                    "none"
+                } else {
+                    &content_type
+                }
+            )
+            .into());
+        }
+
+        let mut events = SseReader::new(response);
+        while let Some(event) = events.next_event().await? {
+            if event.event == "endpoint" {
+                let endpoint = url
+                    .join(event.data.trim())
+                    .map_err(|e| format!("Invalid endpoint '{}': {}", event.data, e))?;
+                return Ok(Self {
+                    client,
+                    endpoint,
+                    headers,
+                    events,
+                });
+            }
+        }
+        Err(
+            "Event stream ended before the server announced its endpoint"
+                .to_string()
+                .into(),
+        )
+    }
+
+    async fn post(&self, message: &Value) -> Result<(), McpError> {
+        let response = self
+            .client
+            .post(self.endpoint.clone())
+            .headers(self.headers.clone())
+            .json(message)
+            .send()
+            .await
+            .map_err(request_error)?;
+        check_status(response).await?;
+        Ok(())
+    }
+
+    async fn round_trip(&mut self, request: &Value, id: i64) -> Result<Value, McpError> {
+        self.post(request).await?;
+        while let Some(event) = self.events.next_event().await? {
+            if event.event != "message" {
+                continue;
+            }
+            let Ok(message) = serde_json::from_str::<Value>(&event.data) else {
+                continue;
+            };
+            match classify(message, id) {
+                Received::Response(response) => return Ok(response),
+                Received::Reply(reply) => self.post(&reply).await?,
+                Received::Ignore => {}
+            }
+        }
+        Err("Event stream ended before the server answered"
+            .to_string()
+            .into())
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Client
+// ----------------------------------------------------------------------------
+
+enum Transport {
+    Stdio(StdioTransport),
+    Http(HttpTransport),
+    Sse(SseTransport),
+}
+
+/// A connection to one MCP server. Requests are sent one at a time.
+pub struct McpClient {
+    transport: Transport,
+    next_id: i64,
+}
+
+impl McpClient {
+    /// Start or connect to the server described by `config`.
+    /// `bearer_token` is sent to HTTP servers unless the config sets its own Authorization header.
+    pub async fn connect(
+        config: &MCPServerConfig,
+        bearer_token: Option<&str>,
+    ) -> Result<Self, McpError> {
+        let transport = match TransportKind::of(config)? {
+            TransportKind::Stdio => {
+                // Start from what a terminal would see so npx, uvx and friends are on PATH.
+                // The first read runs the login shell, so it stays off the async runtime.
+                let env = tokio::task::spawn_blocking(login_shell_env)
+                    .await
+                    .map_err(|e| format!("Failed to read environment: {}", e))?;
+                Transport::Stdio(StdioTransport::spawn(config, env)?)
+            }
+            kind => {
+                let url = server_url(config)?;
+                let headers = build_headers(config, bearer_token)?;
+                let client = reqwest::Client::builder()
+                    .build()
+                    .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+                if kind == TransportKind::Sse {
+                    Transport::Sse(SseTransport::connect(client, url, headers).await?)
+                } else {
+                    Transport::Http(HttpTransport {
+                        client,
+                        url,
+                        headers,
+                        session_id: None,
+                        protocol_version: None,
+                    })
+                }
+            }
+        };
+        Ok(Self {
+            transport,
+            next_id: 1,
+        })
+    }
+
+    /// Send a request and wait for its result
+    pub async fn request(&mut self, method: &str, params: Value) -> Result<Value, McpError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        let response = match &mut self.transport {
+            Transport::Stdio(t) => t.round_trip(&request, id).await?,
+            Transport::Http(t) => t.round_trip(&request, id).await?,
+            Transport::Sse(t) => t.round_trip(&request, id).await?,
+        };
+        into_result(response)
+    }
+
+    pub async fn notify(&mut self, method: &str, params: Value) -> Result<(), McpError> {
+        let notification = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        match &mut self.transport {
+            Transport::Stdio(t) => t.write(&notification).await,
+            Transport::Http(t) => t.post(&notification).await.map(|_| ()),
+            Transport::Sse(t) => t.post(&notification).await,
+        }
+    }
+
+    /// Perform the `initialize` handshake and send `notifications/initialized`
+    pub async fn initialize(&mut self) -> Result<InitializeResult, McpError> {
+        let result = self
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": LATEST_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "vinsly", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await?;
+
+        let protocol_version = result
+            .get("protocolVersion")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "Initialize result is missing 'protocolVersion'".to_string())?
+            .to_string();
+        let server_info = result
+            .get("serverInfo")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| format!("Invalid 'serverInfo': {}", e))?
+            .unwrap_or_default();
+        let initialized = InitializeResult {
+            protocol_version,
+            server_info,
+            capabilities: result.get("capabilities").cloned().unwrap_or(json!({})),
+            instructions: result
+                .get("instructions")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+        };
+
+        if let Transport::Http(t) = &mut self.transport {
+            t.protocol_version = Some(initialized.protocol_version.clone());
+        }
+        self.notify("notifications/initialized", json!({})).await?;
+        Ok(initialized)
+    }
+
+    /// Shut the connection down: stdio servers are killed, HTTP sessions are ended
+    pub async fn close(self) {
+        match self.transport {
+            Transport::Stdio(t) => t.close().await,
+            Transport::Http(t) => t.close().await,
+            Transport::Sse(_) => {}
+        }
+    }
+}
+
+/// Connect, initialize and disconnect, all within `timeout`
+pub async fn probe(
+    config: &MCPServerConfig,
+    bearer_token: Option<&str>,
+    timeout: Duration,
+) -> Result<InitializeResult, McpError> {
+    let handshake = async {
+        let mut client = McpClient::connect(config, bearer_token).await?;
+        let result = client.initialize().await;
+        client.close().await;
+        result
+    };
+    tokio::time::timeout(timeout, handshake)
+        .await
+        .map_err(|_| McpError::Timeout {
+            message: format!("No initialize response within {} ms", timeout.as_millis()),
+        })?
+}
+
+/// Outcome of a single `tools/call`, with how long each phase took
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ToolCallOutcome {
+    /// The raw `tools/call` result when the server produced one
+    pub result: Option<Value>,
+    /// Why the call failed before producing a result
+    pub error: Option<McpError>,
+    /// `result.isError`: the tool ran but reported a failure
+    pub is_tool_error: bool,
+    pub connect_ms: u64,
+    pub initialize_ms: Option<u64>,
+    pub call_ms: Option<u64>,
+    pub total_ms: u64,
+}
+
+fn elapsed_ms(since: Instant) -> u64 {
+    since.elapsed().as_millis() as u64
+}
+
+fn deadline_error(phase: &str, timeout: Duration) -> McpError {
+    McpError::Timeout {
+        message: format!(
+            "Timed out during {} after {} ms",
+            phase,
+            timeout.as_millis()
+        ),
+    }
+}
+
+/// Initialize and call `tool_name` on a connected client, recording timings in `outcome`
+async fn run_tool_call(
+    client: &mut McpClient,
+    outcome: &mut ToolCallOutcome,
+    tool_name: &str,
+    arguments: Value,
+    deadline: tokio::time::Instant,
+    timeout: Duration,
+) -> Result<(), McpError> {
+    let started = Instant::now();
+    tokio::time::timeout_at(deadline, client.initialize())
+        .await
+        .map_err(|_| deadline_error("initialize", timeout))??;
+    outcome.initialize_ms = Some(elapsed_ms(started));
+
+    let started = Instant::now();
+    let params = json!({ "name": tool_name, "arguments": arguments });
+    let result = tokio::time::timeout_at(deadline, client.request("tools/call", params))
+        .await
+        .map_err(|_| deadline_error("the tool call", timeout));
+    outcome.call_ms = Some(elapsed_ms(started));
+
+    let result = result??;
+    outcome.is_tool_error = result
+        .get("isError")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    outcome.result = Some(result);
+    Ok(())
+}
+
+/// Connect, initialize and call one tool with JSON `arguments`, all within `timeout`.
+/// Failures are reported in the outcome so the timings up to that point are kept.
+pub async fn call_tool(
+    config: &MCPServerConfig,
+    bearer_token: Option<&str>,
+    tool_name: &str,
+    arguments: Value,
+    timeout: Duration,
+) -> ToolCallOutcome {
+    let started = Instant::now();
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut outcome = ToolCallOutcome::default();
+
+    let arguments = match arguments {
+        Value::Null => json!({}),
+        arguments @ Value::Object(_) => arguments,
+        _ => {
+            outcome.error = Some("Tool arguments must be a JSON object".to_string().into());
+            return outcome;
+        }
+    };
+
+    let connected = tokio::time::timeout_at(deadline, McpClient::connect(config, bearer_token))
+        .await
+        .map_err(|_| deadline_error("connect", timeout));
+    outcome.connect_ms = elapsed_ms(started);
+
+    match connected {
+        Ok(Ok(mut client)) => {
+            let called = run_tool_call(
+                &mut client,
+                &mut outcome,
+                tool_name,
+                arguments,
+                deadline,
+                timeout,
+            )
+            .await;
+            outcome.error = called.err();
+            client.close().await;
+        }
+        Ok(Err(e)) | Err(e) => outcome.error = Some(e),
+    }
+
+    outcome.total_ms = elapsed_ms(started);
+    outcome
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use indexmap::IndexMap;
+    use tokio::io::{AsyncReadExt, DuplexStream};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc;
+
+    /// Answers a JSON-RPC message the way a small MCP server would
+    pub(crate) fn mock_response(message: &Value) -> Option<Value> {
+        let id = message.get("id")?.clone();
+        let method = message.get("method")?.as_str()?;
+        let result = match method {
+            "initialize" => json!({
+                "protocolVersion": "2025-03-26",
+                "capabilities": { "tools": { "listChanged": true }, "prompts": {} },
+                "serverInfo": { "name": "mock-server", "version": "1.2.3" },
+                "instructions": "Use the echo tool"
+            }),
+            "tools/list" => json!({
+                "tools": [{
+                    "name": "echo",
+                    "description": "Echo the text back",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": { "text": { "type": "string" } },
+                        "required": ["text"]
+                    }
+                }]
+            }),
+            "resources/list" => json!({
+                "resources": [{ "uri": "mock://readme", "name": "readme", "mimeType": "text/plain" }]
+            }),
+            "prompts/list" => json!({
+                "prompts": [{ "name": "greet", "arguments": [{ "name": "who", "required": true }] }]
+            }),
+            "tools/call" => {
+                let params = message.get("params").cloned().unwrap_or(json!({}));
+                if params.get("name").and_then(|n| n.as_str()) != Some("echo") {
+                    return Some(json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32602, "message": "Unknown tool" }
+                    }));
+                }
+                let text = params
+                    .pointer("/arguments/text")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("");
+                json!({ "content": [{ "type": "text", "text": text }], "isError": false })
+            }
+            _ => {
+                return Some(json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32601, "message": "Method not found" }
+                }))
+            }
+        };
+        Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    /// A stdio mock over an in-memory pipe; it pings the client once before answering initialize
+    pub(crate) fn mock_stdio_server() -> McpClient {
+        let (client_side, server_side): (DuplexStream, DuplexStream) = tokio::io::duplex(64 * 1024);
+        let (server_read, mut server_write) = tokio::io::split(server_side);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(server_read).lines();
+            let mut pinged = false;
+            while let Ok(Some(line)) = lines.next_line().await {
+                let message: Value = serde_json::from_str(&line).unwrap();
+                if !pinged {
+                    pinged = true;
+                    let ping = json!({ "jsonrpc": "2.0", "id": "s1", "method": "ping" });
+                    server_write
+                        .write_all(format!("log line on stdout\n{}\n", ping).as_bytes())
+                        .await
+                        .unwrap();
+                    let pong = lines.next_line().await.unwrap().unwrap();
+                    assert_eq!(serde_json::from_str::<Value>(&pong).unwrap()["id"], "s1");
+                }
+                if let Some(response) = mock_response(&message) {
+                    server_write
+                        .write_all(format!("{}\n", response).as_bytes())
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+        let (client_read, client_write) = tokio::io::split(client_side);
+        McpClient {
+            transport: Transport::Stdio(StdioTransport::from_io(
+                Box::new(client_read),
+                Box::new(client_write),
+            )),
+            next_id: 1,
+        }
+    }
+
+    pub(crate) struct HttpRequest {
+        pub(crate) method: String,
+        pub(crate) path: String,
+        pub(crate) headers: Vec<(String, String)>,
+        pub(crate) body: Vec<u8>,
+    }
+
+    impl HttpRequest {
+        pub(crate) fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    pub(crate) async fn read_request(stream: &mut TcpStream) -> Option<HttpRequest> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let header_end = loop {
+            if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                break end;
+            }
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            buffer.extend_from_slice(&chunk[..n]);
+        };
+        let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_string();
+        let path = request_line.next()?.to_string();
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|l| l.split_once(':'))
+            .map(|(n, v)| (n.trim().to_string(), v.trim().to_string()))
+            .collect();
+        let length: usize = headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, v)| v.parse().ok())
+            .unwrap_or(0);
+        let mut body = buffer[header_end + 4..].to_vec();
+        while body.len() < length {
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..n]);
+        }
+        Some(HttpRequest {
+            method,
+            path,
+            headers,
+            body,
+        })
+    }
+
+    pub(crate) async fn respond(
+        stream: &mut TcpStream,
+        status: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) {
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            status,
+            body.len()
+        );
+        for (name, value) in headers {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        response.push_str("\r\n");
+        response.push_str(body);
+        let _ = stream.write_all(response.as_bytes()).await;
+    }
+
+    /// Local HTTP mock serving streamable HTTP on `/mcp` (JSON answers), `/mcp-stream`
+    /// (event-stream answers), legacy SSE on `/sse`, a non-MCP page on `/html` and a
+    /// protected endpoint on `/private`. Returns the base URL.
+    pub(crate) async fn mock_http_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let sse_sender: Arc<Mutex<Option<mpsc::UnboundedSender<String>>>> =
+            Arc::new(Mutex::new(None));
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let sse_sender = sse_sender.clone();
+                tokio::spawn(async move {
+                    let Some(request) = read_request(&mut stream).await else {
+                        return;
+                    };
+                    let message: Value =
+                        serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+                    match (request.method.as_str(), request.path.as_str()) {
+                        ("POST", "/mcp") | ("POST", "/mcp-stream") => {
+                            let is_initialize = message.get("method") == Some(&json!("initialize"));
+                            if !is_initialize
+                                && request.header("mcp-session-id") != Some("session-1")
+                            {
+                                respond(&mut stream, "400 Bad Request", &[], "missing session")
+                                    .await;
+                                return;
+                            }
+                            if !is_initialize
+                                && request.header("mcp-protocol-version") != Some("2025-03-26")
+                            {
+                                respond(
+                                    &mut stream,
+                                    "400 Bad Request",
+                                    &[],
+                                    "missing protocol version",
+                                )
+                                .await;
+                                return;
+                            }
+                            let Some(response) = mock_response(&message) else {
+                                respond(&mut stream, "202 Accepted", &[], "").await;
+                                return;
+                            };
+                            if request.path == "/mcp" {
+                                respond(
+                                    &mut stream,
+                                    "200 OK",
+                                    &[
+                                        ("Content-Type", "application/json"),
+                                        ("Mcp-Session-Id", "session-1"),
+                                    ],
+                                    &response.to_string(),
+                                )
+                                .await;
+                            } else {
+                                let body = format!(
+                                    ": keep-alive\n\nevent: message\ndata: {}\n\nevent: message\ndata: {}\n\n",
+                                    json!({ "jsonrpc": "2.0", "method": "notifications/message", "params": { "level": "info", "data": "working" } }),
+                                    response
+                                );
+                                respond(
+                                    &mut stream,
+                                    "200 OK",
+                                    &[
+                                        ("Content-Type", "text/event-stream"),
+                                        ("Mcp-Session-Id", "session-1"),
+                                    ],
+                                    &body,
+                                )
+                                .await;
+                            }
+                        }
+                        ("DELETE", _) => respond(&mut stream, "204 No Content", &[], "").await,
+                        ("GET", "/sse") => {
+                            let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+                            *sse_sender.lock().unwrap() = Some(sender);
+                            let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+                            let _ = stream.write_all(head.as_bytes()).await;
+                            let _ = stream
+                                .write_all(b"event: endpoint\ndata: /messages?session=abc\n\n")
+                                .await;
+                            while let Some(message) = receiver.recv().await {
+                                let event = format!("event: message\ndata: {}\n\n", message);
+                                if stream.write_all(event.as_bytes()).await.is_err() {
+                                    return;
+                                }
+                            }
+                        }
+                        ("POST", "/messages?session=abc") => {
+                            if let Some(response) = mock_response(&message) {
+                                if let Some(sender) = sse_sender.lock().unwrap().as_ref() {
+                                    let _ = sender.send(response.to_string());
+                                }
+                            }
+                            respond(&mut stream, "202 Accepted", &[], "").await;
+                        }
+                        ("POST", "/private") => {
+                            respond(
+                                &mut stream,
+                                "401 Unauthorized",
+                                &[("WWW-Authenticate", "Bearer resource_metadata=\"/.well-known/oauth-protected-resource\"")],
+                                "",
+                            )
+                            .await;
+                        }
+                        _ => {
+                            respond(
+                                &mut stream,
+                                "200 OK",
+                                &[("Content-Type", "text/html")],
+                                "<html>hello</html>",
+                            )
+                            .await;
+                        }
+                    }
+                });
+            }
+        });
+
+        base
+    }
+
+    pub(crate) fn http_config(server_type: &str, url: String) -> MCPServerConfig {
+        MCPServerConfig {
+            server_type: Some(server_type.to_string()),
+            url: Some(url),
+            ..Default::default()
+        }
+    }
+
+    fn assert_mock_initialize(result: &InitializeResult) {
+        assert_eq!(result.protocol_version, "2025-03-26");
+        assert_eq!(result.server_info.name, "mock-server");
+        assert_eq!(result.server_info.version, "1.2.3");
+        assert!(result.capabilities.get("tools").is_some());
+        assert_eq!(result.instructions.as_deref(), Some("Use the echo tool"));
+    }
+
+    #[tokio::test]
+    async fn stdio_handshake_answers_pings_and_skips_stray_output() {
+        let mut client = mock_stdio_server();
+        let result = client.initialize().await.unwrap();
+        assert_mock_initialize(&result);
+
+        let tools = client.request("tools/list", json!({})).await.unwrap();
+        assert_eq!(tools["tools"][0]["name"], "echo");
+    }
+
+    #[tokio::test]
+    async fn stdio_rpc_errors_keep_code_and_message() {
+        let mut client = mock_stdio_server();
+        client.initialize().await.unwrap();
+        match client.request("does/not/exist", json!({})).await {
+            Err(McpError::Rpc { code, message, .. }) => {
+                assert_eq!(code, -32601);
+                assert_eq!(message, "Method not found");
+            }
+            other => panic!("expected an RPC error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stdio_spawns_the_configured_command_with_args_and_env() {
+        let script = r#"read line; printf '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-06-18","capabilities":{},"serverInfo":{"name":"%s","version":"0.1.0"}}}\n' "$MOCK_NAME"; read line; sleep 5"#;
+        let config = MCPServerConfig {
+            command: Some("sh".to_string()),
+            args: Some(vec!["-c".to_string(), script.to_string()]),
+            env: Some(IndexMap::from([(
+                "MOCK_NAME".to_string(),
+                "from-env".to_string(),
+            )])),
+            ..Default::default()
+        };
+        let result = probe(&config, None, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(result.server_info.name, "from-env");
+        assert_eq!(result.protocol_version, "2025-06-18");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stdio_server_that_crashes_is_not_connected() {
+        let config = MCPServerConfig {
+            command: Some("sh".to_string()),
+            args: Some(vec![
+                "-c".to_string(),
+                "echo 'missing API key' >&2; exit 3".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let error = probe(&config, None, Duration::from_secs(5))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("exited"), "{}", error);
+        assert!(error.contains('3'), "{}", error);
+        assert!(error.contains("missing API key"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stdio_server_that_never_answers_times_out() {
+        let config = MCPServerConfig {
+            command: Some("sleep".to_string()),
+            args: Some(vec!["10".to_string()]),
+            ..Default::default()
+        };
+        let error = probe(&config, None, Duration::from_millis(300))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, McpError::Timeout { .. }));
+    }
+
+    #[tokio::test]
+    async fn missing_stdio_command_is_reported() {
+        let config = MCPServerConfig {
+            command: Some("vinsly-definitely-not-a-command".to_string()),
+            ..Default::default()
+        };
+        let error = probe(&config, None, Duration::from_secs(5))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Failed to start"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn streamable_http_with_json_answers() {
+        let base = mock_http_server().await;
+        let mut client = McpClient::connect(&http_config("http", format!("{}/mcp", base)), None)
+            .await
+            .unwrap();
+        assert_mock_initialize(&client.initialize().await.unwrap());
+        // Session id and protocol version headers are required by the mock after initialize
+        let tools = client.request("tools/list", json!({})).await.unwrap();
+        assert_eq!(tools["tools"][0]["name"], "echo");
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn streamable_http_with_event_stream_answers() {
+        let base = mock_http_server().await;
+        let result = probe(
+            &http_config("http", format!("{}/mcp-stream", base)),
+            None,
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        assert_mock_initialize(&result);
+    }
+
+    #[tokio::test]
+    async fn legacy_sse_transport() {
+        let base = mock_http_server().await;
+        let mut client = McpClient::connect(&http_config("sse", format!("{}/sse", base)), None)
+            .await
+            .unwrap();
+        assert_mock_initialize(&client.initialize().await.unwrap());
+        let prompts = client.request("prompts/list", json!({})).await.unwrap();
+        assert_eq!(prompts["prompts"][0]["name"], "greet");
+    }
+
+    #[tokio::test]
+    async fn http_endpoint_that_is_not_mcp_is_rejected() {
+        let base = mock_http_server().await;
+        let error = probe(
+            &http_config("http", format!("{}/html", base)),
+            None,
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("Not an MCP endpoint"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn unauthorized_http_server_reports_the_challenge() {
+        let base = mock_http_server().await;
+        let error = probe(
+            &http_config("http", format!("{}/private", base)),
+            None,
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap_err();
+        match error {
+            McpError::Unauthorized {
+                www_authenticate, ..
+            } => assert!(www_authenticate.unwrap().contains("resource_metadata")),
+            other => panic!("expected Unauthorized, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_http_server_is_reported() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        drop(listener);
+        let error = probe(&http_config("http", url), None, Duration::from_secs(5))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Connection refused"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn tool_call_returns_the_raw_result_and_timings() {
+        let base = mock_http_server().await;
+        let config = http_config("http", format!("{}/mcp", base));
+        let outcome = call_tool(
+            &config,
+            Some("token"),
+            "echo",
+            json!({ "text": "hello" }),
+            Duration::from_secs(5),
+        )
+        .await;
+
+        assert!(outcome.error.is_none(), "{:?}", outcome.error);
+        assert!(!outcome.is_tool_error);
+        assert_eq!(outcome.result.unwrap()["content"][0]["text"], "hello");
+        assert!(outcome.initialize_ms.is_some());
+        assert!(outcome.call_ms.is_some());
+        assert!(outcome.total_ms >= outcome.connect_ms);
+    }
+
+    #[tokio::test]
+    async fn tool_call_reports_rpc_errors_and_bad_arguments() {
+        let base = mock_http_server().await;
+        let config = http_config("http", format!("{}/mcp", base));
+
+        let outcome = call_tool(&config, None, "missing", json!({}), Duration::from_secs(5)).await;
+        assert!(matches!(
+            outcome.error,
+            Some(McpError::Rpc { code: -32602, .. })
+        ));
+        assert!(outcome.result.is_none());
+        assert!(outcome.call_ms.is_some());
+
+        let outcome = call_tool(&config, None, "echo", json!([1]), Duration::from_secs(5)).await;
+        assert!(outcome.error.unwrap().to_string().contains("JSON object"));
+        assert!(outcome.initialize_ms.is_none());
+    }
+
+    #[test]
+    fn stored_token_is_sent_unless_a_header_is_configured() {
+        let mut config = http_config("http", "http://localhost/mcp".to_string());
+        let headers = build_headers(&config, Some("abc")).unwrap();
+        assert_eq!(headers["authorization"], "Bearer abc");
+
+        config.headers = Some(IndexMap::from([(
+            "Authorization".to_string(),
+            "Bearer configured".to_string(),
+        )]));
+        let headers = build_headers(&config, Some("abc")).unwrap();
+        assert_eq!(headers["authorization"], "Bearer configured");
+    }
+
+    #[test]
+    fn sse_blocks_join_data_lines_and_skip_comments() {
+        let event = parse_sse_block(": comment\nevent: endpoint\ndata: /a\ndata: b\n").unwrap();
+        assert_eq!(event.event, "endpoint");
+        assert_eq!(event.data, "/a\nb");
+        assert!(parse_sse_block(": only a comment\n").is_none());
+    }
+}
@@ -0,0 +1,786 @@
+use crate::fs_write::{read_with_version, write_atomic, ExpectedVersion, WriteError};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One entry under `mcpServers`.
+/// Keys Vinsly doesn't model (e.g. `alwaysAllow`, `oauth`) are kept in `extra`
+/// so editing a server never drops them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MCPServerConfig {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub server_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<IndexMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<IndexMap<String, String>>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl MCPServerConfig {
+    /// Carry over unknown keys from the entry this one replaces.
+    /// The frontend only round-trips the modelled fields, so an edit would otherwise drop them.
+    pub fn inherit_unknown_fields(&mut self, previous: &MCPServerConfig) {
+        for (key, value) in &previous.extra {
+            if !self.extra.contains_key(key) {
+                self.extra.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// An MCP config file: `~/.claude.json` (user) or `.mcp.json` (project).
+/// For `~/.claude.json` everything besides `mcpServers` belongs to Claude Code
+/// (projects, onboarding state, caches) and lands in `extra`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MCPConfigFile {
+    #[serde(rename = "mcpServers", default)]
+    pub mcp_servers: IndexMap<String, MCPServerConfig>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Where a scope's `mcpServers` live
+#[derive(Debug, Clone)]
+pub struct MCPConfigLocation {
+    pub path: PathBuf,
+    /// Local scope only: the project entry under `projects` in ~/.claude.json
+    pub project: Option<String>,
+}
+
+impl MCPConfigLocation {
+    /// A whole file whose top level holds `mcpServers` (user and project scope)
+    pub fn file(path: PathBuf) -> Self {
+        Self {
+            path,
+            project: None,
+        }
+    }
+
+    /// `projects["<project_path>"]` inside ~/.claude.json (local scope)
+    pub fn project_entry(claude_json: PathBuf, project_path: &str) -> Self {
+        Self {
+            path: claude_json,
+            project: Some(project_path.to_string()),
+        }
+    }
+}
+
+fn normalize_project_key(path: &str) -> String {
+    let normalized = path.replace('\\', "/");
+    let trimmed = normalized.trim_end_matches('/');
+    if trimmed.is_empty() {
+        normalized
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Claude Code keys `projects` by absolute path. Reuse an existing key that
+/// differs only by separators or a trailing slash instead of creating a duplicate entry.
+fn resolve_project_key(projects: &serde_json::Map<String, serde_json::Value>, project_path: &str) -> String {
+    let wanted = normalize_project_key(project_path);
+    projects
+        .keys()
+        .find(|key| normalize_project_key(key) == wanted)
+        .cloned()
+        .unwrap_or_else(|| {
+            let trimmed = project_path.trim_end_matches(['/', '\\']);
+            if trimmed.is_empty() { project_path } else { trimmed }.to_string()
+        })
+}
+
+type VersionedObject = (
+    Option<serde_json::Map<String, serde_json::Value>>,
+    Option<ExpectedVersion>,
+);
+
+fn read_json_object(path: &Path) -> Result<Option<serde_json::Map<String, serde_json::Value>>, String> {
+    Ok(read_json_object_with_version(path)?.0)
+}
+
+/// The JSON object in a file plus the version of the bytes it was parsed from,
+/// which is `None` only when the file does not exist
+fn read_json_object_with_version(
+    path: &Path,
+) -> Result<VersionedObject, String> {
+    let Some((content, version)) = read_with_version(path)? else {
+        return Ok((None, None));
+    };
+
+    if content.trim().is_empty() {
+        return Ok((None, Some(version)));
+    }
+
+    match serde_json::from_str(&content) {
+        Ok(serde_json::Value::Object(map)) => Ok((Some(map), Some(version))),
+        Ok(_) => Err("Failed to parse MCP config: expected a JSON object".to_string()),
+        Err(e) => Err(format!("Failed to parse MCP config: {}", e)),
+    }
+}
+
+/// The object a location points at: the whole document, or its project entry
+fn read_location_object(
+    location: &MCPConfigLocation,
+) -> Result<Option<serde_json::Map<String, serde_json::Value>>, String> {
+    Ok(read_location_object_with_version(location)?.0)
+}
+
+fn read_location_object_with_version(
+    location: &MCPConfigLocation,
+) -> Result<VersionedObject, String> {
+    let (Some(mut document), version) = read_json_object_with_version(&location.path)? else {
+        return Ok((None, None));
+    };
+
+    let object = match &location.project {
+        None => Some(document),
+        Some(project_path) => match document.remove("projects") {
+            Some(serde_json::Value::Object(mut projects)) => {
+                let key = resolve_project_key(&projects, project_path);
+                match projects.remove(&key) {
+                    Some(serde_json::Value::Object(entry)) => Some(entry),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+    };
+    Ok((object, version))
+}
+
+pub fn read_mcp_config_at(location: &MCPConfigLocation) -> Result<MCPConfigFile, String> {
+    Ok(read_mcp_config_with_version(location)?.0)
+}
+
+/// Like `read_mcp_config_at`, plus the version of the file it was parsed from,
+/// to pass to `write_mcp_config_at` when writing the edited config back
+pub fn read_mcp_config_with_version(
+    location: &MCPConfigLocation,
+) -> Result<(MCPConfigFile, Option<ExpectedVersion>), String> {
+    let (object, version) = read_location_object_with_version(location)?;
+    let Some(object) = object else {
+        return Ok((MCPConfigFile::default(), version));
+    };
+
+    let config = serde_json::from_value(serde_json::Value::Object(object))
+        .map_err(|e| format!("Failed to parse MCP config: {}", e))?;
+    Ok((config, version))
+}
+
+/// Like `read_mcp_config_at`, but servers whose entry doesn't deserialize are left out
+/// instead of failing the whole file. For listing only: writing the result back would
+/// drop those entries. `validate_mcp_servers` reports what is wrong with them.
+pub fn read_mcp_config_lenient(location: &MCPConfigLocation) -> Result<MCPConfigFile, String> {
+    let Some(mut object) = read_location_object(location)? else {
+        return Ok(MCPConfigFile::default());
+    };
+
+    let entries = object.remove("mcpServers");
+    let mut config: MCPConfigFile = serde_json::from_value(serde_json::Value::Object(object))
+        .map_err(|e| format!("Failed to parse MCP config: {}", e))?;
+    if let Some(serde_json::Value::Object(entries)) = entries {
+        for (name, entry) in entries {
+            if let Ok(server) = serde_json::from_value(entry) {
+                config.mcp_servers.insert(name, server);
+            }
+        }
+    }
+    Ok(config)
+}
+
+/// Escape one reference token of a JSON pointer (RFC 6901)
+pub fn json_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// The raw `mcpServers` value at `location`, if any, with its JSON pointer in the file.
+/// Lets validation look at entries that don't deserialize.
+pub fn read_raw_mcp_servers(
+    location: &MCPConfigLocation,
+) -> Result<(String, Option<serde_json::Value>), String> {
+    let document = read_json_object(&location.path)?;
+    let Some(project_path) = &location.project else {
+        let servers = document.and_then(|mut document| document.remove("mcpServers"));
+        return Ok(("/mcpServers".to_string(), servers));
+    };
+
+    let mut projects = match document.and_then(|mut document| document.remove("projects")) {
+        Some(serde_json::Value::Object(projects)) => projects,
+        _ => serde_json::Map::new(),
+    };
+    let key = resolve_project_key(&projects, project_path);
+    let pointer = format!("/projects/{}/mcpServers", json_pointer_token(&key));
+    let servers = match projects.remove(&key) {
+        Some(serde_json::Value::Object(mut entry)) => entry.remove("mcpServers"),
+        _ => None,
+    };
+    Ok((pointer, servers))
+}
+
+/// Write `config` back to its location.
+/// The serialized config is merged key by key into the JSON already on disk, so
+/// keys keep their position and keys missing from `config` are left untouched.
+pub fn write_mcp_config_at(
+    location: &MCPConfigLocation,
+    config: &MCPConfigFile,
+    expected: Option<&ExpectedVersion>,
+) -> Result<(), WriteError> {
+    let serde_json::Value::Object(updates) = serde_json::to_value(config)
+        .map_err(|e| format!("Failed to serialize MCP config: {}", e))?
+    else {
+        return Err("Failed to serialize MCP config: expected a JSON object".to_string().into());
+    };
+
+    update_location_object(location, expected, |target| {
+        for (key, value) in updates {
+            target.insert(key, value);
+        }
+    })
+}
+
+/// Read the JSON at `location`, apply `update` to the object the location points at,
+/// and write the whole document back atomically.
+fn update_location_object(
+    location: &MCPConfigLocation,
+    expected: Option<&ExpectedVersion>,
+    update: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>),
+) -> Result<(), WriteError> {
+    let mut document = read_json_object(&location.path)?.unwrap_or_default();
+    let target = match &location.project {
+        None => &mut document,
+        Some(project_path) => {
+            let projects = document
+                .entry("projects")
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+                .as_object_mut()
+                .ok_or_else(|| "Failed to update MCP config: 'projects' is not an object".to_string())?;
+            let key = resolve_project_key(projects, project_path);
+            projects
+                .entry(key)
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+                .as_object_mut()
+                .ok_or_else(|| format!("Failed to update MCP config: project entry for {} is not an object", project_path))?
+        }
+    };
+    update(target);
+
+    let content = serde_json::to_string_pretty(&document)
+        .map_err(|e| format!("Failed to serialize MCP config: {}", e))?;
+
+    write_atomic(&location.path, content.as_bytes(), expected)?;
+
+    Ok(())
+}
+
+/// Settings key listing `.mcp.json` servers the user approved
+pub const ENABLED_MCPJSON_SERVERS: &str = "enabledMcpjsonServers";
+/// Settings key listing `.mcp.json` servers the user turned off
+pub const DISABLED_MCPJSON_SERVERS: &str = "disabledMcpjsonServers";
+
+/// Settings key that approves every `.mcp.json` server of a project
+pub const ENABLE_ALL_PROJECT_MCP_SERVERS: &str = "enableAllProjectMcpServers";
+
+/// Where Claude Code records `.mcp.json` approvals for a project, private locations first:
+/// the project entry in ~/.claude.json, `.claude/settings.local.json`, then the shared
+/// `.claude/settings.json`.
+pub fn mcpjson_approval_locations(claude_json: &Path, project_path: &str) -> Vec<MCPConfigLocation> {
+    let settings_dir = Path::new(project_path).join(".claude");
+    vec![
+        MCPConfigLocation::project_entry(claude_json.to_path_buf(), project_path),
+        MCPConfigLocation::file(settings_dir.join("settings.local.json")),
+        MCPConfigLocation::file(settings_dir.join("settings.json")),
+    ]
+}
+
+/// String array stored under `key` at a location; missing or malformed lists read as empty
+pub fn read_string_list(location: &MCPConfigLocation, key: &str) -> Result<Vec<String>, String> {
+    Ok(string_list(read_location_object(location)?.as_ref(), key))
+}
+
+fn string_list(object: Option<&serde_json::Map<String, serde_json::Value>>, key: &str) -> Vec<String> {
+    object
+        .and_then(|object| object.get(key))
+        .and_then(|value| value.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Add or remove `name` in the string array under `key`. Writes only when the list changes.
+fn set_list_membership(
+    location: &MCPConfigLocation,
+    key: &str,
+    name: &str,
+    member: bool,
+) -> Result<(), WriteError> {
+    let (object, expected) = read_location_object_with_version(location)?;
+    if string_list(object.as_ref(), key).iter().any(|n| n == name) == member {
+        return Ok(());
+    }
+
+    update_location_object(location, expected.as_ref(), |target| {
+        let entry = target
+            .entry(key)
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+        if !entry.is_array() {
+            *entry = serde_json::Value::Array(Vec::new());
+        }
+        if let Some(items) = entry.as_array_mut() {
+            items.retain(|item| item.as_str() != Some(name));
+            if member {
+                items.push(serde_json::Value::String(name.to_string()));
+            }
+        }
+    })
+}
+
+/// Names of `.mcp.json` servers turned off in any of `locations`
+pub fn disabled_mcpjson_servers(locations: &[MCPConfigLocation]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for location in locations {
+        for name in read_string_list(location, DISABLED_MCPJSON_SERVERS).unwrap_or_default() {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// Whether the user allowed a `.mcp.json` server to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpjsonApproval {
+    Approved,
+    /// Claude Code will ask at the start of the next session
+    Pending,
+    Rejected,
+}
+
+/// `.mcp.json` approval settings merged across locations
+#[derive(Debug, Clone, Default)]
+pub struct McpjsonApprovals {
+    pub enabled: Vec<String>,
+    pub disabled: Vec<String>,
+    pub enable_all: bool,
+}
+
+impl McpjsonApprovals {
+    /// Read every location; a missing or unreadable one contributes nothing
+    pub fn read(locations: &[MCPConfigLocation]) -> Self {
+        let mut approvals = Self::default();
+        for location in locations {
+            for (key, names) in [
+                (ENABLED_MCPJSON_SERVERS, &mut approvals.enabled),
+                (DISABLED_MCPJSON_SERVERS, &mut approvals.disabled),
+            ] {
+                for name in read_string_list(location, key).unwrap_or_default() {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+            let enable_all = read_location_object(location)
+                .ok()
+                .flatten()
+                .and_then(|object| object.get(ENABLE_ALL_PROJECT_MCP_SERVERS)?.as_bool());
+            approvals.enable_all |= enable_all == Some(true);
+        }
+        approvals
+    }
+
+    /// A rejection anywhere wins over approvals
+    pub fn status(&self, name: &str) -> McpjsonApproval {
+        if self.disabled.iter().any(|n| n == name) {
+            McpjsonApproval::Rejected
+        } else if self.enable_all || self.enabled.iter().any(|n| n == name) {
+            McpjsonApproval::Approved
+        } else {
+            McpjsonApproval::Pending
+        }
+    }
+}
+
+/// Turn a `.mcp.json` server on or off the way Claude Code does, through
+/// `enabledMcpjsonServers`/`disabledMcpjsonServers` in the project entry of ~/.claude.json.
+/// Stale entries in `.claude/settings.local.json` are cleaned up; the shared
+/// `.claude/settings.json` is never rewritten, so a server disabled there cannot be enabled here.
+pub fn set_mcpjson_server_enabled(
+    claude_json: &Path,
+    project_path: &str,
+    name: &str,
+    enabled: bool,
+) -> Result<(), WriteError> {
+    let locations = mcpjson_approval_locations(claude_json, project_path);
+    let (project_entry, local_settings, shared_settings) = (&locations[0], &locations[1], &locations[2]);
+
+    if enabled
+        && read_string_list(shared_settings, DISABLED_MCPJSON_SERVERS)?
+            .iter()
+            .any(|n| n == name)
+    {
+        return Err(format!(
+            "Server '{}' is disabled in {}, which is shared with the project; remove it there to enable it",
+            name,
+            shared_settings.path.display()
+        )
+        .into());
+    }
+
+    set_list_membership(project_entry, DISABLED_MCPJSON_SERVERS, name, !enabled)?;
+    set_list_membership(project_entry, ENABLED_MCPJSON_SERVERS, name, enabled)?;
+
+    // Drop a contradicting entry from the local settings file
+    if local_settings.path.exists() {
+        let stale_key = if enabled {
+            DISABLED_MCPJSON_SERVERS
+        } else {
+            ENABLED_MCPJSON_SERVERS
+        };
+        set_list_membership(local_settings, stale_key, name, false)?;
+    }
+    Ok(())
+}
+
+/// Set or remove the boolean under `key`. Writes only when the value changes.
+fn set_bool_setting(
+    location: &MCPConfigLocation,
+    key: &str,
+    value: Option<bool>,
+) -> Result<(), WriteError> {
+    let (object, expected) = read_location_object_with_version(location)?;
+    let current = object.and_then(|object| object.get(key).cloned());
+    if current == value.map(serde_json::Value::Bool) {
+        return Ok(());
+    }
+
+    update_location_object(location, expected.as_ref(), |target| match value {
+        Some(value) => {
+            target.insert(key.to_string(), serde_json::Value::Bool(value));
+        }
+        None => {
+            target.shift_remove(key);
+        }
+    })
+}
+
+/// Record the answer to Claude Code's `.mcp.json` prompt for one server, or take it back
+/// (`Pending`) so Claude Code asks again. `read_only` are settings Vinsly never rewrites,
+/// such as the shared `.claude/settings.json` and the user settings; an answer they
+/// contradict is refused instead of being silently ignored.
+pub fn set_mcpjson_server_approval(
+    claude_json: &Path,
+    project_path: &str,
+    name: &str,
+    approval: McpjsonApproval,
+    read_only: &[MCPConfigLocation],
+) -> Result<(), WriteError> {
+    let fixed = McpjsonApprovals::read(read_only);
+    let conflict = match approval {
+        McpjsonApproval::Approved => fixed.disabled.iter().any(|n| n == name).then_some("rejected"),
+        McpjsonApproval::Rejected => None,
+        McpjsonApproval::Pending => {
+            (fixed.status(name) != McpjsonApproval::Pending).then_some("already answered")
+        }
+    };
+    if let Some(conflict) = conflict {
+        return Err(format!(
+            "Server '{}' is {} in shared or user settings; change it there",
+            name, conflict
+        )
+        .into());
+    }
+
+    match approval {
+        McpjsonApproval::Approved => set_mcpjson_server_enabled(claude_json, project_path, name, true),
+        McpjsonApproval::Rejected => set_mcpjson_server_enabled(claude_json, project_path, name, false),
+        McpjsonApproval::Pending => {
+            let locations = mcpjson_approval_locations(claude_json, project_path);
+            for location in &locations[..2] {
+                if location.project.is_none() && !location.path.exists() {
+                    continue;
+                }
+                set_list_membership(location, ENABLED_MCPJSON_SERVERS, name, false)?;
+                set_list_membership(location, DISABLED_MCPJSON_SERVERS, name, false)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Turn `enableAllProjectMcpServers` on or off for a project in its ~/.claude.json entry.
+/// Turning it off also drops it from `.claude/settings.local.json`; it can't be turned
+/// off while one of the `read_only` settings enables it.
+pub fn set_enable_all_project_mcp_servers(
+    claude_json: &Path,
+    project_path: &str,
+    enabled: bool,
+    read_only: &[MCPConfigLocation],
+) -> Result<(), WriteError> {
+    if !enabled && McpjsonApprovals::read(read_only).enable_all {
+        return Err(format!(
+            "{} is set in shared or user settings; change it there",
+            ENABLE_ALL_PROJECT_MCP_SERVERS
+        )
+        .into());
+    }
+
+    let locations = mcpjson_approval_locations(claude_json, project_path);
+    let (project_entry, local_settings) = (&locations[0], &locations[1]);
+    set_bool_setting(project_entry, ENABLE_ALL_PROJECT_MCP_SERVERS, Some(enabled))?;
+    if !enabled && local_settings.path.exists() {
+        set_bool_setting(local_settings, ENABLE_ALL_PROJECT_MCP_SERVERS, None)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const CLAUDE_JSON_FIXTURE: &str = include_str!("../tests/fixtures/claude.json");
+
+    fn fixture_path() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vinsly-mcp-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".claude.json");
+        fs::write(&path, CLAUDE_JSON_FIXTURE).unwrap();
+        path
+    }
+
+    fn read_value(path: &Path) -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn read_mcp_config_from_file(path: &Path) -> Result<MCPConfigFile, String> {
+        read_mcp_config_at(&MCPConfigLocation::file(path.to_path_buf()))
+    }
+
+    fn write_mcp_config_to_file(path: &Path, config: &MCPConfigFile) -> Result<(), WriteError> {
+        write_mcp_config_at(&MCPConfigLocation::file(path.to_path_buf()), config, None)
+    }
+
+    fn top_level_keys(value: &serde_json::Value) -> Vec<String> {
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn unchanged_round_trip_is_lossless() {
+        let path = fixture_path();
+        let original = read_value(&path);
+
+        let config = read_mcp_config_from_file(&path).unwrap();
+        write_mcp_config_to_file(&path, &config).unwrap();
+
+        let written = read_value(&path);
+        assert_eq!(written, original);
+        assert_eq!(top_level_keys(&written), top_level_keys(&original));
+    }
+
+    #[test]
+    fn adding_a_server_keeps_claude_code_state() {
+        let path = fixture_path();
+        let original = read_value(&path);
+
+        let mut config = read_mcp_config_from_file(&path).unwrap();
+        config.mcp_servers.insert(
+            "sentry".to_string(),
+            MCPServerConfig {
+                server_type: Some("http".to_string()),
+                url: Some("https://mcp.sentry.dev/mcp".to_string()),
+                ..Default::default()
+            },
+        );
+        write_mcp_config_to_file(&path, &config).unwrap();
+
+        let written = read_value(&path);
+        for key in ["numStartups", "projects", "oauthAccount", "tipsHistory", "hasCompletedOnboarding"] {
+            assert_eq!(written[key], original[key], "top-level key {} changed", key);
+        }
+        assert_eq!(top_level_keys(&written), top_level_keys(&original));
+        assert_eq!(written["mcpServers"]["sentry"]["url"], "https://mcp.sentry.dev/mcp");
+        assert_eq!(written["mcpServers"]["github"], original["mcpServers"]["github"]);
+    }
+
+    #[test]
+    fn unknown_server_fields_survive_an_edit() {
+        let path = fixture_path();
+
+        let mut config = read_mcp_config_from_file(&path).unwrap();
+        let previous = config.mcp_servers["filesystem"].clone();
+        assert!(previous.extra.contains_key("alwaysAllow"));
+
+        // What the frontend sends back: only the modelled fields
+        let mut edited = MCPServerConfig {
+            server_type: previous.server_type.clone(),
+            command: previous.command.clone(),
+            args: Some(vec!["-y".to_string(), "@modelcontextprotocol/server-filesystem".to_string(), "/tmp".to_string()]),
+            ..Default::default()
+        };
+        edited.inherit_unknown_fields(&previous);
+        config.mcp_servers.insert("filesystem".to_string(), edited);
+        write_mcp_config_to_file(&path, &config).unwrap();
+
+        let written = read_value(&path);
+        let server = &written["mcpServers"]["filesystem"];
+        assert_eq!(server["alwaysAllow"], serde_json::json!(["read_file", "list_directory"]));
+        assert_eq!(server["timeout"], 30000);
+        assert_eq!(server["args"][2], "/tmp");
+    }
+
+    #[test]
+    fn removing_a_server_keeps_order_of_the_rest() {
+        let path = fixture_path();
+
+        let mut config = read_mcp_config_from_file(&path).unwrap();
+        let names_before: Vec<String> = config.mcp_servers.keys().cloned().collect();
+        config.mcp_servers.shift_remove("github");
+        write_mcp_config_to_file(&path, &config).unwrap();
+
+        let written = read_mcp_config_from_file(&path).unwrap();
+        let expected: Vec<String> = names_before.into_iter().filter(|n| n != "github").collect();
+        assert_eq!(written.mcp_servers.keys().cloned().collect::<Vec<_>>(), expected);
+        assert!(written.extra.contains_key("projects"));
+    }
+
+    #[test]
+    fn partial_config_does_not_erase_other_keys() {
+        let path = fixture_path();
+        let original = read_value(&path);
+
+        // `write_mcp_config` called with only `mcpServers`, as older frontends do
+        let config: MCPConfigFile =
+            serde_json::from_value(serde_json::json!({ "mcpServers": {} })).unwrap();
+        write_mcp_config_to_file(&path, &config).unwrap();
+
+        let written = read_value(&path);
+        assert_eq!(written["mcpServers"], serde_json::json!({}));
+        assert_eq!(written["projects"], original["projects"]);
+        assert_eq!(written["userID"], original["userID"]);
+    }
+
+    const STOREFRONT: &str = "/Users/alex/code/storefront";
+
+    #[test]
+    fn local_scope_reads_the_project_entry() {
+        let path = fixture_path();
+
+        let local = read_mcp_config_at(&MCPConfigLocation::project_entry(path.clone(), STOREFRONT)).unwrap();
+        assert_eq!(local.mcp_servers.keys().collect::<Vec<_>>(), vec!["playwright"]);
+        assert_eq!(local.extra["lastSessionId"], "0b8e5c1d-4f2a-4d7b-9e3c-6a1f8b2d5e70");
+
+        // A trailing slash still finds the same entry
+        let trailing = format!("{}/", STOREFRONT);
+        let again = read_mcp_config_at(&MCPConfigLocation::project_entry(path.clone(), &trailing)).unwrap();
+        assert!(again.mcp_servers.contains_key("playwright"));
+
+        let unknown = read_mcp_config_at(&MCPConfigLocation::project_entry(path, "/Users/alex/code/other")).unwrap();
+        assert!(unknown.mcp_servers.is_empty());
+    }
+
+    #[test]
+    fn local_scope_writes_only_the_project_entry() {
+        let path = fixture_path();
+        let original = read_value(&path);
+        let location = MCPConfigLocation::project_entry(path.clone(), STOREFRONT);
+
+        let mut local = read_mcp_config_at(&location).unwrap();
+        local.mcp_servers.insert(
+            "linear".to_string(),
+            MCPServerConfig {
+                server_type: Some("sse".to_string()),
+                url: Some("https://mcp.linear.app/sse".to_string()),
+                ..Default::default()
+            },
+        );
+        write_mcp_config_at(&location, &local, None).unwrap();
+
+        let written = read_value(&path);
+        let project = &written["projects"][STOREFRONT];
+        assert_eq!(project["mcpServers"]["linear"]["type"], "sse");
+        assert_eq!(project["mcpServers"]["playwright"], original["projects"][STOREFRONT]["mcpServers"]["playwright"]);
+        assert_eq!(project["history"], original["projects"][STOREFRONT]["history"]);
+        assert_eq!(written["mcpServers"], original["mcpServers"]);
+        assert_eq!(written["projects"]["/Users/alex/code/dotfiles"], original["projects"]["/Users/alex/code/dotfiles"]);
+        assert_eq!(top_level_keys(&written), top_level_keys(&original));
+    }
+
+    #[test]
+    fn local_scope_creates_a_missing_project_entry() {
+        let path = fixture_path();
+        let location = MCPConfigLocation::project_entry(path.clone(), "/Users/alex/code/new-app");
+
+        let mut local = read_mcp_config_at(&location).unwrap();
+        local.mcp_servers.insert(
+            "fetch".to_string(),
+            MCPServerConfig {
+                command: Some("uvx".to_string()),
+                args: Some(vec!["mcp-server-fetch".to_string()]),
+                ..Default::default()
+            },
+        );
+        write_mcp_config_at(&location, &local, None).unwrap();
+
+        let written = read_value(&path);
+        assert_eq!(written["projects"]["/Users/alex/code/new-app"]["mcpServers"]["fetch"]["command"], "uvx");
+        assert!(written["projects"][STOREFRONT].is_object());
+    }
+
+    #[test]
+    fn a_malformed_entry_only_hides_itself() {
+        let path = fixture_path();
+        let mut document = read_value(&path);
+        document["mcpServers"]["broken"] = serde_json::json!({ "command": "npx", "args": "-y" });
+        fs::write(&path, document.to_string()).unwrap();
+
+        assert!(read_mcp_config_from_file(&path).is_err());
+        let config = read_mcp_config_lenient(&MCPConfigLocation::file(path.clone())).unwrap();
+        assert!(!config.mcp_servers.is_empty());
+        assert!(!config.mcp_servers.contains_key("broken"));
+
+        let (pointer, servers) = read_raw_mcp_servers(&MCPConfigLocation::file(path.clone())).unwrap();
+        assert_eq!(pointer, "/mcpServers");
+        assert!(servers.unwrap()["broken"].is_object());
+        let (pointer, _) = read_raw_mcp_servers(&MCPConfigLocation::project_entry(path, STOREFRONT)).unwrap();
+        assert_eq!(pointer, format!("/projects/{}/mcpServers", json_pointer_token(STOREFRONT)));
+    }
+
+    #[test]
+    fn approvals_can_be_answered_and_taken_back() {
+        let path = fixture_path();
+        let project = path.parent().unwrap().join("app");
+        let project_path = project.to_string_lossy().to_string();
+        let shared = project.join(".claude").join("settings.json");
+        fs::create_dir_all(shared.parent().unwrap()).unwrap();
+        fs::write(&shared, r#"{"disabledMcpjsonServers": ["tracker"]}"#).unwrap();
+        let read_only = vec![MCPConfigLocation::file(shared)];
+        let approvals = || McpjsonApprovals::read(&mcpjson_approval_locations(&path, &project_path));
+
+        set_mcpjson_server_approval(&path, &project_path, "github", McpjsonApproval::Approved, &read_only).unwrap();
+        assert_eq!(approvals().status("github"), McpjsonApproval::Approved);
+        set_mcpjson_server_approval(&path, &project_path, "github", McpjsonApproval::Pending, &read_only).unwrap();
+        assert_eq!(approvals().status("github"), McpjsonApproval::Pending);
+
+        assert!(set_mcpjson_server_approval(&path, &project_path, "tracker", McpjsonApproval::Approved, &read_only).is_err());
+        assert!(set_mcpjson_server_approval(&path, &project_path, "tracker", McpjsonApproval::Pending, &read_only).is_err());
+
+        set_enable_all_project_mcp_servers(&path, &project_path, true, &read_only).unwrap();
+        assert_eq!(approvals().status("github"), McpjsonApproval::Approved);
+        assert_eq!(approvals().status("tracker"), McpjsonApproval::Rejected);
+        set_enable_all_project_mcp_servers(&path, &project_path, false, &read_only).unwrap();
+        assert_eq!(approvals().status("github"), McpjsonApproval::Pending);
+    }
+}
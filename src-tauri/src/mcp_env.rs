@@ -0,0 +1,281 @@
+use crate::mcp_config::MCPServerConfig;
+use crate::mcp_secrets::{load_secret, REFERENCE_PREFIX};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Upper bound on how long a slow shell profile can delay the first lookup
+#[cfg(unix)]
+const LOGIN_SHELL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Printed before the environment dump, so anything shell profiles write to stdout is skipped
+#[cfg(unix)]
+const ENV_MARKER: &str = "__VINSLY_ENV_START__";
+
+/// The environment Claude Code sees when started from a terminal: the app's own
+/// environment overlaid with what the user's login shell exports. Apps launched from
+/// the Dock or a desktop launcher miss everything set in shell profiles.
+/// Read once per run.
+pub fn login_shell_env() -> &'static HashMap<String, String> {
+    static ENV: OnceLock<HashMap<String, String>> = OnceLock::new();
+    ENV.get_or_init(|| {
+        let mut env: HashMap<String, String> = std::env::vars().collect();
+        #[cfg(unix)]
+        if let Some(shell_env) = read_login_shell_env() {
+            env.extend(shell_env);
+        }
+        env
+    })
+}
+
+#[cfg(unix)]
+fn read_login_shell_env() -> Option<HashMap<String, String>> {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+    use std::time::Instant;
+
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
+    let mut child = Command::new(&shell)
+        .args(["-l", "-c", &format!("printf '{}\\0'; env -0", ENV_MARKER)])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let mut stdout = child.stdout.take()?;
+    let reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        output
+    });
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => break,
+            Ok(None) if started.elapsed() < LOGIN_SHELL_TIMEOUT => {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+
+    let output = reader.join().ok()?;
+    Some(parse_env_dump(&String::from_utf8_lossy(&output)))
+}
+
+/// Parse `env -0` output that follows the marker
+#[cfg(unix)]
+fn parse_env_dump(output: &str) -> HashMap<String, String> {
+    let marker = format!("{}\0", ENV_MARKER);
+    let dump = match output.find(&marker) {
+        Some(start) => &output[start + marker.len()..],
+        None => return HashMap::new(),
+    };
+    dump.split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` the way Claude Code does. References that
+/// resolve to nothing and have no default are left as written and their names pushed
+/// onto `unresolved`.
+pub fn expand_references(
+    input: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    unresolved: &mut Vec<String>,
+) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+        let reference = &after[..end];
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+
+        if !is_variable_name(name) {
+            output.push_str(&rest[start..start + 2 + end + 1]);
+        } else if let Some(value) = lookup(name) {
+            output.push_str(&value);
+        } else if let Some(default) = default {
+            output.push_str(default);
+        } else {
+            if !unresolved.iter().any(|n| n == name) {
+                unresolved.push(name.to_string());
+            }
+            output.push_str(&rest[start..start + 2 + end + 1]);
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A server config with its references expanded, and the variables that didn't resolve
+#[derive(Debug, Clone)]
+pub struct ExpandedConfig {
+    pub config: MCPServerConfig,
+    pub unresolved: Vec<String>,
+}
+
+/// Expand references in `url`, `command`, `args`, `headers` and `env` values
+pub fn expand_config(
+    config: &MCPServerConfig,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> ExpandedConfig {
+    let mut unresolved = Vec::new();
+    let mut expanded = config.clone();
+    let mut expand = |value: &mut String| {
+        *value = expand_references(value, &lookup, &mut unresolved);
+    };
+
+    if let Some(url) = expanded.url.as_mut() {
+        expand(url);
+    }
+    if let Some(command) = expanded.command.as_mut() {
+        expand(command);
+    }
+    for arg in expanded.args.iter_mut().flatten() {
+        expand(arg);
+    }
+    for value in expanded.headers.iter_mut().flat_map(|h| h.values_mut()) {
+        expand(value);
+    }
+    for value in expanded.env.iter_mut().flat_map(|e| e.values_mut()) {
+        expand(value);
+    }
+
+    ExpandedConfig {
+        config: expanded,
+        unresolved,
+    }
+}
+
+/// Expand `config` against the login-shell environment. `${VINSLY_…}` references the
+/// environment doesn't define come from the secrets Vinsly keeps in the keychain.
+pub fn expand_with_login_env(config: &MCPServerConfig) -> ExpandedConfig {
+    let env = login_shell_env();
+    expand_config(config, |name| {
+        env.get(name).cloned().or_else(|| {
+            name.starts_with(REFERENCE_PREFIX)
+                .then(|| load_secret(name))
+                .flatten()
+        })
+    })
+}
+
+/// Error message for a config that can't be started because of `unresolved` variables
+pub fn unresolved_message(unresolved: &[String]) -> String {
+    format!("Missing environment variables: {}", unresolved.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::IndexMap;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "API_KEY" => Some("secret".to_string()),
+            "HOST" => Some("example.com".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expands_references_and_defaults() {
+        let mut unresolved = Vec::new();
+        assert_eq!(
+            expand_references(
+                "https://${HOST}/mcp?port=${PORT:-8080}",
+                lookup,
+                &mut unresolved
+            ),
+            "https://example.com/mcp?port=8080"
+        );
+        assert_eq!(
+            expand_references("${HOST:-fallback}", lookup, &mut unresolved),
+            "example.com"
+        );
+        assert_eq!(
+            expand_references("${EMPTY_DEFAULT:-}", lookup, &mut unresolved),
+            ""
+        );
+        assert!(unresolved.is_empty());
+    }
+
+    #[test]
+    fn unresolved_references_are_kept_and_reported_once() {
+        let mut unresolved = Vec::new();
+        let expanded = expand_references(
+            "Bearer ${TOKEN} ${TOKEN} $HOME ${not valid} ${UNCLOSED",
+            lookup,
+            &mut unresolved,
+        );
+        assert_eq!(
+            expanded,
+            "Bearer ${TOKEN} ${TOKEN} $HOME ${not valid} ${UNCLOSED"
+        );
+        assert_eq!(unresolved, vec!["TOKEN"]);
+    }
+
+    #[test]
+    fn expands_every_string_field_of_a_config() {
+        let config = MCPServerConfig {
+            url: Some("https://${HOST}/mcp".to_string()),
+            args: Some(vec!["--key=${API_KEY}".to_string()]),
+            headers: Some(IndexMap::from([(
+                "Authorization".to_string(),
+                "Bearer ${API_KEY}".to_string(),
+            )])),
+            env: Some(IndexMap::from([(
+                "REGION".to_string(),
+                "${REGION}".to_string(),
+            )])),
+            ..Default::default()
+        };
+        let expanded = expand_config(&config, lookup);
+        assert_eq!(
+            expanded.config.url.as_deref(),
+            Some("https://example.com/mcp")
+        );
+        assert_eq!(expanded.config.args.unwrap()[0], "--key=secret");
+        assert_eq!(
+            expanded.config.headers.unwrap()["Authorization"],
+            "Bearer secret"
+        );
+        assert_eq!(expanded.unresolved, vec!["REGION"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn env_dump_skips_profile_output() {
+        let output = format!("Welcome!\n{}\0A=1\0B=x=y\0MULTI=line1\nline2\0", ENV_MARKER);
+        let env = parse_env_dump(&output);
+        assert_eq!(env["A"], "1");
+        assert_eq!(env["B"], "x=y");
+        assert_eq!(env["MULTI"], "line1\nline2");
+        assert!(!env.contains_key("Welcome!\n"));
+    }
+}
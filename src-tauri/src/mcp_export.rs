@@ -0,0 +1,318 @@
+use crate::mcp_config::MCPServerConfig;
+use crate::mcp_import::{strip_jsonc, McpClient};
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Keys an exported entry sets; everything else in an entry being replaced is kept
+const MANAGED_KEYS: &[&str] = &[
+    "type",
+    "command",
+    "args",
+    "env",
+    "url",
+    "serverUrl",
+    "headers",
+];
+
+/// What an export wrote and what it had to leave out
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportReport {
+    pub path: String,
+    pub written: Vec<String>,
+    /// Written over an entry of the same name that was already in the file
+    pub replaced: Vec<String>,
+    /// Servers the client can't run, as "name: reason"
+    pub skipped: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Key of the object holding the servers in `client`'s file
+fn servers_key(client: McpClient) -> &'static str {
+    match client {
+        McpClient::Vscode => "servers",
+        _ => "mcpServers",
+    }
+}
+
+/// Rewrite Claude Code's `${VAR}` / `${VAR:-default}` for `client`: editors spell
+/// environment references `${env:VAR}` and have no defaults; Claude Desktop expands nothing.
+pub fn translate_references(
+    client: McpClient,
+    name: &str,
+    value: &str,
+    warnings: &mut Vec<String>,
+) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+        let reference = &after[..end];
+        let original = &rest[start..start + 2 + end + 1];
+        let (variable, default) = match reference.split_once(":-") {
+            Some((variable, default)) => (variable, Some(default)),
+            None => (reference, None),
+        };
+
+        let mut warn = |warning: String| {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        };
+        if client == McpClient::ClaudeDesktop {
+            warn(format!(
+                "{}: Claude Desktop doesn't expand '{}'",
+                name, original
+            ));
+            output.push_str(original);
+        } else {
+            if let Some(default) = default {
+                warn(format!(
+                    "{}: {} has no defaults; '{}' loses its default '{}'",
+                    name,
+                    client.label(),
+                    original,
+                    default
+                ));
+            }
+            output.push_str(&format!("${{env:{}}}", variable));
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// `config` in `client`'s entry format, or why the client can't run it
+pub fn client_entry(
+    client: McpClient,
+    name: &str,
+    config: &MCPServerConfig,
+    warnings: &mut Vec<String>,
+) -> Result<Map<String, Value>, String> {
+    let server_type = config
+        .server_type
+        .as_deref()
+        .unwrap_or(if config.command.is_some() {
+            "stdio"
+        } else {
+            "http"
+        });
+    let mut translate = |value: &str| translate_references(client, name, value, warnings);
+    let mut entry = Map::new();
+
+    if server_type == "stdio" {
+        let command = config
+            .command
+            .as_deref()
+            .ok_or_else(|| "stdio server has no command".to_string())?;
+        if client == McpClient::Vscode {
+            entry.insert("type".to_string(), Value::from("stdio"));
+        }
+        entry.insert("command".to_string(), Value::from(translate(command)));
+        if let Some(args) = &config.args {
+            let args: Vec<String> = args.iter().map(|arg| translate(arg)).collect();
+            entry.insert("args".to_string(), Value::from(args));
+        }
+        if let Some(env) = &config.env {
+            let env: Map<String, Value> = env
+                .iter()
+                .map(|(key, value)| (key.clone(), Value::from(translate(value))))
+                .collect();
+            entry.insert("env".to_string(), Value::Object(env));
+        }
+        return Ok(entry);
+    }
+
+    if client == McpClient::ClaudeDesktop {
+        return Err("Claude Desktop's config file only runs stdio servers".to_string());
+    }
+    let url = config
+        .url
+        .as_deref()
+        .ok_or_else(|| format!("{} server has no url", server_type))?;
+    let url_key = match client {
+        McpClient::Windsurf => "serverUrl",
+        _ => "url",
+    };
+    if client == McpClient::Vscode {
+        entry.insert("type".to_string(), Value::from(server_type));
+    }
+    entry.insert(url_key.to_string(), Value::from(translate(url)));
+    if let Some(headers) = &config.headers {
+        let headers: Map<String, Value> = headers
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::from(translate(value))))
+            .collect();
+        entry.insert("headers".to_string(), Value::Object(headers));
+    }
+    Ok(entry)
+}
+
+/// Merge `servers` into the content of `client`'s config file (`None` when it doesn't
+/// exist yet). Top-level keys, other servers, and keys Vinsly doesn't set inside a
+/// replaced entry (`disabled`, `alwaysAllow`, …) are preserved. Comments are not.
+pub fn merge_into_client_config(
+    client: McpClient,
+    existing: Option<&str>,
+    servers: &IndexMap<String, MCPServerConfig>,
+    report: &mut ExportReport,
+) -> Result<String, String> {
+    let mut root = match existing.filter(|content| !content.trim().is_empty()) {
+        Some(content) => serde_json::from_str(&strip_jsonc(content))
+            .map_err(|e| format!("Failed to parse {} config: {}", client.label(), e))?,
+        None => Value::Object(Map::new()),
+    };
+    let root = root
+        .as_object_mut()
+        .ok_or_else(|| format!("{} config is not a JSON object", client.label()))?;
+    let entries = root
+        .entry(servers_key(client))
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| format!("'{}' is not an object", servers_key(client)))?;
+
+    for (name, config) in servers {
+        let entry = match client_entry(client, name, config, &mut report.warnings) {
+            Ok(entry) => entry,
+            Err(reason) => {
+                report.skipped.push(format!("{}: {}", name, reason));
+                continue;
+            }
+        };
+        let merged = match entries.get(name).and_then(Value::as_object) {
+            Some(previous) => {
+                report.replaced.push(name.clone());
+                let mut merged: Map<String, Value> = previous
+                    .iter()
+                    .filter(|(key, _)| !MANAGED_KEYS.contains(&key.as_str()))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                merged.extend(entry);
+                merged
+            }
+            None => entry,
+        };
+        entries.insert(name.clone(), Value::Object(merged));
+        report.written.push(name.clone());
+    }
+
+    let mut content = serde_json::to_string_pretty(&root)
+        .map_err(|e| format!("Failed to serialize {} config: {}", client.label(), e))?;
+    content.push('\n');
+    Ok(content)
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// A shell script that recreates `servers` with `claude mcp add-json`, each in the
+/// scope it came from. `${VAR}` references stay unexpanded for Claude Code to resolve.
+pub fn add_json_script(servers: &[(String, String, MCPServerConfig)]) -> Result<String, String> {
+    let mut script = String::from("#!/bin/sh\n# MCP servers exported by Vinsly\nset -e\n\n");
+    for (scope, name, config) in servers {
+        let json = serde_json::to_string(config)
+            .map_err(|e| format!("Failed to serialize server '{}': {}", name, e))?;
+        script.push_str(&format!(
+            "claude mcp add-json --scope {} {} {}\n",
+            scope,
+            shell_quote(name),
+            shell_quote(&json)
+        ));
+    }
+    Ok(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn servers() -> IndexMap<String, MCPServerConfig> {
+        IndexMap::from([
+            (
+                "fs".to_string(),
+                MCPServerConfig {
+                    server_type: Some("stdio".to_string()),
+                    command: Some("npx".to_string()),
+                    args: Some(vec!["server-fs".to_string(), "${ROOT:-/tmp}".to_string()]),
+                    ..Default::default()
+                },
+            ),
+            (
+                "linear".to_string(),
+                MCPServerConfig {
+                    server_type: Some("http".to_string()),
+                    url: Some("https://mcp.linear.test/mcp".to_string()),
+                    headers: Some(IndexMap::from([(
+                        "Authorization".to_string(),
+                        "Bearer ${LINEAR_TOKEN}".to_string(),
+                    )])),
+                    ..Default::default()
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn merging_keeps_unknown_fields_and_other_servers() {
+        let existing = r#"{
+            // user settings
+            "inputs": [{"id": "token", "type": "promptString"}],
+            "servers": {
+                "other": {"type": "stdio", "command": "x"},
+                "linear": {"type": "sse", "url": "https://old.test", "envFile": ".env"},
+            }
+        }"#;
+        let mut report = ExportReport::default();
+        let content =
+            merge_into_client_config(McpClient::Vscode, Some(existing), &servers(), &mut report)
+                .unwrap();
+        let root: Value = serde_json::from_str(&content).unwrap();
+
+        assert_eq!(root["inputs"][0]["id"], "token");
+        assert_eq!(root["servers"]["other"]["command"], "x");
+        let linear = &root["servers"]["linear"];
+        assert_eq!(linear["type"], "http");
+        assert_eq!(linear["url"], "https://mcp.linear.test/mcp");
+        assert_eq!(linear["envFile"], ".env");
+        assert_eq!(
+            linear["headers"]["Authorization"],
+            "Bearer ${env:LINEAR_TOKEN}"
+        );
+        assert_eq!(root["servers"]["fs"]["args"][1], "${env:ROOT}");
+        assert_eq!(report.replaced, vec!["linear"]);
+        assert!(report.warnings[0].contains("loses its default '/tmp'"));
+    }
+
+    #[test]
+    fn claude_desktop_skips_remote_servers() {
+        let mut report = ExportReport::default();
+        let content =
+            merge_into_client_config(McpClient::ClaudeDesktop, None, &servers(), &mut report)
+                .unwrap();
+        let root: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(root["mcpServers"]["fs"]["command"], "npx");
+        assert!(root["mcpServers"].get("linear").is_none());
+        assert_eq!(report.written, vec!["fs"]);
+        assert!(report.skipped[0].starts_with("linear: "));
+    }
+
+    #[test]
+    fn script_quotes_names_and_json() {
+        let script = add_json_script(&[(
+            "user".to_string(),
+            "it's".to_string(),
+            servers()["linear"].clone(),
+        )])
+        .unwrap();
+        assert!(script.contains(
+            r#"claude mcp add-json --scope user 'it'\''s' '{"type":"http","url":"https://mcp.linear.test/mcp","headers":{"Authorization":"Bearer ${LINEAR_TOKEN}"}}'"#
+        ));
+    }
+}
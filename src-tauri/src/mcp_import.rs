@@ -0,0 +1,535 @@
+use crate::mcp_config::MCPServerConfig;
+use crate::text_diff::{is_unchanged, line_diff, DiffLine};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Other MCP clients whose config files Vinsly imports from and exports to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpClient {
+    ClaudeDesktop,
+    Cursor,
+    Vscode,
+    Windsurf,
+}
+
+impl McpClient {
+    pub fn label(self) -> &'static str {
+        match self {
+            McpClient::ClaudeDesktop => "Claude Desktop",
+            McpClient::Cursor => "Cursor",
+            McpClient::Vscode => "VS Code",
+            McpClient::Windsurf => "Windsurf",
+        }
+    }
+}
+
+/// A config file a client reads its MCP servers from
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSource {
+    pub client: McpClient,
+    pub path: String,
+    /// Project-level file (`.cursor/mcp.json`, `.vscode/mcp.json`)
+    pub project: bool,
+    pub exists: bool,
+}
+
+/// Standard locations of each client's MCP config. `config_dir` is the platform config
+/// directory (`~/.config` on Linux). Project files are included when `project_path` is set.
+pub fn import_sources(
+    home: &Path,
+    config_dir: &Path,
+    project_path: Option<&Path>,
+) -> Vec<ImportSource> {
+    let mut sources = vec![
+        (
+            McpClient::ClaudeDesktop,
+            config_dir.join("Claude").join("claude_desktop_config.json"),
+            false,
+        ),
+        (
+            McpClient::Cursor,
+            home.join(".cursor").join("mcp.json"),
+            false,
+        ),
+        (
+            McpClient::Vscode,
+            config_dir.join("Code").join("User").join("mcp.json"),
+            false,
+        ),
+        (
+            McpClient::Vscode,
+            config_dir.join("Code").join("User").join("settings.json"),
+            false,
+        ),
+        (
+            McpClient::Windsurf,
+            home.join(".codeium")
+                .join("windsurf")
+                .join("mcp_config.json"),
+            false,
+        ),
+    ];
+    if let Some(project) = project_path {
+        sources.push((
+            McpClient::Cursor,
+            project.join(".cursor").join("mcp.json"),
+            true,
+        ));
+        sources.push((
+            McpClient::Vscode,
+            project.join(".vscode").join("mcp.json"),
+            true,
+        ));
+    }
+
+    sources
+        .into_iter()
+        .map(|(client, path, project)| ImportSource {
+            client,
+            exists: path.is_file(),
+            path: path.to_string_lossy().to_string(),
+            project,
+        })
+        .collect()
+}
+
+/// Folder `${workspaceFolder}` stands for in a project-level file such as
+/// `<project>/.vscode/mcp.json`
+pub fn workspace_of(source: &ImportSource) -> Option<PathBuf> {
+    if !source.project {
+        return None;
+    }
+    Path::new(&source.path)
+        .parent()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+}
+
+/// A server mapped to Claude Code's format, with what didn't carry over
+#[derive(Debug, Clone)]
+pub struct ImportedServer {
+    pub name: String,
+    pub config: MCPServerConfig,
+    pub warnings: Vec<String>,
+}
+
+/// Servers read from a client config. Entries that can't be mapped are reported in
+/// `skipped` instead of failing the whole file.
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    pub servers: Vec<ImportedServer>,
+    pub skipped: Vec<String>,
+}
+
+/// Drop `//` and `/* */` comments and trailing commas, which VS Code and Cursor accept
+pub fn strip_jsonc(input: &str) -> String {
+    strip_trailing_commas(&strip_comments(input))
+}
+
+fn strip_comments(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        output.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => {
+                in_string = c == '"';
+                output.push(c);
+            }
+        }
+    }
+    output
+}
+
+fn strip_trailing_commas(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = String::with_capacity(input.len());
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' if i < chars.len() => {
+                    output.push(chars[i]);
+                    i += 1;
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if c == ',' {
+            let next = chars[i..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        }
+        in_string = c == '"';
+        output.push(c);
+    }
+    output
+}
+
+/// The object holding the servers: `mcpServers` for most clients, `servers` in VS Code's
+/// `mcp.json`, `mcp.servers` in its `settings.json`
+fn server_entries(root: &Value) -> Option<&serde_json::Map<String, Value>> {
+    root.get("mcpServers")
+        .or_else(|| root.get("servers"))
+        .or_else(|| root.get("mcp").and_then(|mcp| mcp.get("servers")))
+        .and_then(Value::as_object)
+}
+
+/// Parse a client's config file into Claude Code server configs.
+/// `workspace` replaces `${workspaceFolder}` in project-level files.
+pub fn parse_client_config(
+    client: McpClient,
+    content: &str,
+    workspace: Option<&Path>,
+) -> Result<ClientConfig, String> {
+    let root: Value = serde_json::from_str(&strip_jsonc(content))
+        .map_err(|e| format!("Failed to parse {} config: {}", client.label(), e))?;
+    let mut parsed = ClientConfig::default();
+    let Some(entries) = server_entries(&root) else {
+        return Ok(parsed);
+    };
+
+    for (name, entry) in entries {
+        match map_server(client, entry, workspace) {
+            Ok((config, warnings)) => parsed.servers.push(ImportedServer {
+                name: name.clone(),
+                config,
+                warnings,
+            }),
+            Err(e) => parsed.skipped.push(format!("{}: {}", name, e)),
+        }
+    }
+    Ok(parsed)
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn string_map(
+    value: &Value,
+    field: &str,
+    translate: &mut impl FnMut(&str) -> String,
+) -> Result<IndexMap<String, String>, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("'{}' must be an object", field))?;
+    Ok(object
+        .iter()
+        .map(|(key, value)| (key.clone(), translate(&value_to_string(value))))
+        .collect())
+}
+
+fn map_server(
+    client: McpClient,
+    entry: &Value,
+    workspace: Option<&Path>,
+) -> Result<(MCPServerConfig, Vec<String>), String> {
+    let object = entry
+        .as_object()
+        .ok_or_else(|| "entry is not an object".to_string())?;
+    let mut warnings = Vec::new();
+    let mut translate = |value: &str| translate_variables(value, workspace, &mut warnings);
+    let mut config = MCPServerConfig::default();
+    let mut ignored = Vec::new();
+    let mut disabled = false;
+
+    for (key, value) in object {
+        match key.as_str() {
+            "type" => config.server_type = value.as_str().map(str::to_string),
+            "command" => config.command = Some(translate(&value_to_string(value))),
+            // Windsurf calls the URL of remote servers `serverUrl`
+            "url" | "serverUrl" => config.url = Some(translate(&value_to_string(value))),
+            "args" => {
+                let args = value
+                    .as_array()
+                    .ok_or_else(|| "'args' must be an array".to_string())?;
+                config.args = Some(
+                    args.iter()
+                        .map(|arg| translate(&value_to_string(arg)))
+                        .collect(),
+                );
+            }
+            "env" => config.env = Some(string_map(value, key, &mut translate)?),
+            "headers" => config.headers = Some(string_map(value, key, &mut translate)?),
+            "disabled" => disabled = value.as_bool().unwrap_or(false),
+            _ => ignored.push(key.clone()),
+        }
+    }
+
+    let server_type = match config.server_type.as_deref() {
+        Some(known @ ("stdio" | "http" | "sse")) => known.to_string(),
+        Some("streamable-http" | "streamableHttp") => "http".to_string(),
+        Some(other) => return Err(format!("unsupported server type '{}'", other)),
+        None if config.command.is_some() => "stdio".to_string(),
+        None => match config.url.as_deref() {
+            Some(url) if url.trim_end_matches('/').ends_with("/sse") => "sse".to_string(),
+            Some(_) => "http".to_string(),
+            None => return Err("has neither a command nor a url".to_string()),
+        },
+    };
+    config.server_type = Some(server_type);
+
+    if disabled {
+        warnings.push(format!(
+            "Disabled in {}; it will be enabled in Claude Code",
+            client.label()
+        ));
+    }
+    for key in ignored {
+        warnings.push(format!("Ignored {} setting '{}'", client.label(), key));
+    }
+    Ok((config, warnings))
+}
+
+/// Rewrite editor variables into what Claude Code expands: `${env:NAME}` becomes
+/// `${NAME}`, `${workspaceFolder}` the project folder. Anything else (`${input:…}`
+/// prompts, other editor variables) is kept and reported.
+pub fn translate_variables(
+    value: &str,
+    workspace: Option<&Path>,
+    warnings: &mut Vec<String>,
+) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+        let variable = &after[..end];
+        let original = &rest[start..start + 2 + end + 1];
+
+        let (text, warning) = match (variable.split_once(':'), workspace) {
+            (Some(("env", name)), _) => (format!("${{{}}}", name), None),
+            (None, Some(folder)) if variable == "workspaceFolder" => {
+                (folder.to_string_lossy().to_string(), None)
+            }
+            (Some(("input", input)), _) => (
+                original.to_string(),
+                Some(format!(
+                    "'{}' asks for input '{}'; fill it in after importing",
+                    original, input
+                )),
+            ),
+            _ if variable.contains(':') || variable == "workspaceFolder" => (
+                original.to_string(),
+                Some(format!("Claude Code can't expand '{}'", original)),
+            ),
+            // A plain `${NAME}` already means the same to Claude Code
+            _ => (original.to_string(), None),
+        };
+        output.push_str(&text);
+        if let Some(warning) = warning {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    /// No server with this name in the target scope
+    New,
+    /// The target scope already has exactly this server
+    Unchanged,
+    /// The target scope has a different server with this name
+    Conflict,
+}
+
+/// How one imported server compares with the target scope
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportCandidate {
+    pub name: String,
+    pub config: MCPServerConfig,
+    pub status: ImportStatus,
+    /// The existing entry (if any) against the imported one, as pretty-printed JSON
+    pub diff: Vec<DiffLine>,
+    pub warnings: Vec<String>,
+}
+
+fn pretty(config: &MCPServerConfig) -> String {
+    serde_json::to_string_pretty(config).unwrap_or_default()
+}
+
+/// Compare `servers` with the target scope's current `existing` servers
+pub fn preview_import(
+    servers: Vec<ImportedServer>,
+    existing: &IndexMap<String, MCPServerConfig>,
+) -> Vec<ImportCandidate> {
+    servers
+        .into_iter()
+        .map(|server| {
+            let (status, diff) = match existing.get(&server.name) {
+                Some(current) => {
+                    let diff = line_diff(&pretty(current), &pretty(&server.config));
+                    let status = if is_unchanged(&diff) {
+                        ImportStatus::Unchanged
+                    } else {
+                        ImportStatus::Conflict
+                    };
+                    (status, diff)
+                }
+                None => (ImportStatus::New, line_diff("", &pretty(&server.config))),
+            };
+            ImportCandidate {
+                name: server.name,
+                config: server.config,
+                status,
+                diff,
+                warnings: server.warnings,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_comments_and_trailing_commas_outside_strings() {
+        let input = r#"{
+            // servers
+            "servers": { "a": { "url": "https://x.test/a//b", /* inline */ "args": ["/*", "x",], }, },
+        }"#;
+        let value: Value = serde_json::from_str(&strip_jsonc(input)).unwrap();
+        assert_eq!(value["servers"]["a"]["url"], "https://x.test/a//b");
+        assert_eq!(value["servers"]["a"]["args"][0], "/*");
+    }
+
+    #[test]
+    fn maps_each_client_format() {
+        let desktop = r#"{"mcpServers": {"fs": {"command": "npx", "args": ["-y", "server-fs"], "env": {"DEBUG": 1}}}}"#;
+        let parsed = parse_client_config(McpClient::ClaudeDesktop, desktop, None).unwrap();
+        let fs = &parsed.servers[0].config;
+        assert_eq!(fs.server_type.as_deref(), Some("stdio"));
+        assert_eq!(fs.env.as_ref().unwrap()["DEBUG"], "1");
+
+        let windsurf =
+            r#"{"mcpServers": {"remote": {"serverUrl": "https://x.test/sse", "disabled": true}}}"#;
+        let parsed = parse_client_config(McpClient::Windsurf, windsurf, None).unwrap();
+        let remote = &parsed.servers[0];
+        assert_eq!(remote.config.url.as_deref(), Some("https://x.test/sse"));
+        assert_eq!(remote.config.server_type.as_deref(), Some("sse"));
+        assert!(remote.warnings[0].contains("Disabled in Windsurf"));
+
+        let vscode = r#"{
+            "mcp": { "servers": {
+                "gh": { "type": "http", "url": "https://api.test/mcp",
+                        "headers": { "Authorization": "Bearer ${input:token}" } },
+                "local": { "command": "${workspaceFolder}/bin/server", "env": { "KEY": "${env:API_KEY}" }, "envFile": ".env" },
+                "broken": { "type": "websocket", "url": "ws://x" },
+            } }
+        }"#;
+        let workspace = Path::new("/work/app");
+        let parsed = parse_client_config(McpClient::Vscode, vscode, Some(workspace)).unwrap();
+        assert_eq!(parsed.servers.len(), 2);
+        assert!(parsed.skipped[0].starts_with("broken: unsupported server type"));
+        let gh = &parsed.servers[0];
+        assert!(gh.warnings[0].contains("asks for input 'token'"));
+        let local = &parsed.servers[1];
+        assert_eq!(
+            local.config.command.as_deref(),
+            Some("/work/app/bin/server")
+        );
+        assert_eq!(local.config.env.as_ref().unwrap()["KEY"], "${API_KEY}");
+        assert_eq!(local.warnings, vec!["Ignored VS Code setting 'envFile'"]);
+    }
+
+    #[test]
+    fn preview_reports_conflicts_with_a_diff() {
+        let server = |url: &str| MCPServerConfig {
+            server_type: Some("http".to_string()),
+            url: Some(url.to_string()),
+            ..Default::default()
+        };
+        let existing = IndexMap::from([
+            ("same".to_string(), server("https://a.test")),
+            ("changed".to_string(), server("https://old.test")),
+        ]);
+        let imported = ["same", "changed", "added"]
+            .into_iter()
+            .map(|name| ImportedServer {
+                name: name.to_string(),
+                config: server(if name == "changed" {
+                    "https://new.test"
+                } else {
+                    "https://a.test"
+                }),
+                warnings: Vec::new(),
+            })
+            .collect();
+
+        let preview = preview_import(imported, &existing);
+        let statuses: Vec<_> = preview.iter().map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ImportStatus::Unchanged,
+                ImportStatus::Conflict,
+                ImportStatus::New
+            ]
+        );
+        let changed: Vec<_> = preview[1]
+            .diff
+            .iter()
+            .filter(|line| line.kind != crate::text_diff::DiffKind::Context)
+            .map(|line| line.text.trim())
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                "\"url\": \"https://old.test\"",
+                "\"url\": \"https://new.test\""
+            ]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use crate::mcp_client::{InitializeResult, McpClient, McpError};
+use crate::mcp_config::MCPServerConfig;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Upper bound on `nextCursor` pages followed per list, in case a server loops
+const MAX_PAGES: usize = 50;
+
+/// Everything a server exposes, as returned by its `*/list` methods
+#[derive(Debug, Clone, Serialize)]
+pub struct McpInventory {
+    pub server: InitializeResult,
+    /// Tool definitions with `name`, `description`, `inputSchema`, ...
+    pub tools: Vec<Value>,
+    pub resources: Vec<Value>,
+    pub resource_templates: Vec<Value>,
+    pub prompts: Vec<Value>,
+    /// Lists the server advertises but failed to return, keyed by method
+    pub errors: HashMap<String, String>,
+    pub fetched_at_ms: u64,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Call a paginated list method and concatenate the `field` arrays of every page
+async fn list_all(
+    client: &mut McpClient,
+    method: &str,
+    field: &str,
+) -> Result<Vec<Value>, McpError> {
+    let mut items = Vec::new();
+    let mut cursor: Option<String> = None;
+    for _ in 0..MAX_PAGES {
+        let params = match &cursor {
+            Some(cursor) => json!({ "cursor": cursor }),
+            None => json!({}),
+        };
+        let page = client.request(method, params).await?;
+        if let Some(page_items) = page.get(field).and_then(|v| v.as_array()) {
+            items.extend(page_items.iter().cloned());
+        }
+        cursor = page
+            .get("nextCursor")
+            .and_then(|c| c.as_str())
+            .map(|c| c.to_string());
+        if cursor.is_none() {
+            break;
+        }
+    }
+    Ok(items)
+}
+
+/// List tools, resources and prompts of an initialized connection.
+/// Only lists the server advertises in its capabilities are requested.
+pub async fn fetch_inventory(
+    client: &mut McpClient,
+    server: InitializeResult,
+) -> McpInventory {
+    let mut inventory = McpInventory {
+        server,
+        tools: Vec::new(),
+        resources: Vec::new(),
+        resource_templates: Vec::new(),
+        prompts: Vec::new(),
+        errors: HashMap::new(),
+        fetched_at_ms: 0,
+    };
+    let capabilities = inventory.server.capabilities.clone();
+
+    if capabilities.get("tools").is_some() {
+        match list_all(client, "tools/list", "tools").await {
+            Ok(tools) => inventory.tools = tools,
+            Err(e) => {
+                inventory.errors.insert("tools/list".to_string(), e.to_string());
+            }
+        }
+    }
+    if capabilities.get("resources").is_some() {
+        match list_all(client, "resources/list", "resources").await {
+            Ok(resources) => inventory.resources = resources,
+            Err(e) => {
+                inventory
+                    .errors
+                    .insert("resources/list".to_string(), e.to_string());
+            }
+        }
+        // Templates are optional even for servers with resources
+        if let Ok(templates) =
+            list_all(client, "resources/templates/list", "resourceTemplates").await
+        {
+            inventory.resource_templates = templates;
+        }
+    }
+    if capabilities.get("prompts").is_some() {
+        match list_all(client, "prompts/list", "prompts").await {
+            Ok(prompts) => inventory.prompts = prompts,
+            Err(e) => {
+                inventory
+                    .errors
+                    .insert("prompts/list".to_string(), e.to_string());
+            }
+        }
+    }
+
+    inventory.fetched_at_ms = now_ms();
+    inventory
+}
+
+/// Connect, initialize and list everything within `timeout`
+pub async fn load_inventory(
+    config: &MCPServerConfig,
+    bearer_token: Option<&str>,
+    timeout: Duration,
+) -> Result<McpInventory, McpError> {
+    let load = async {
+        let mut client = McpClient::connect(config, bearer_token).await?;
+        let inventory = match client.initialize().await {
+            Ok(server) => Ok(fetch_inventory(&mut client, server).await),
+            Err(e) => Err(e),
+        };
+        client.close().await;
+        inventory
+    };
+    tokio::time::timeout(timeout, load)
+        .await
+        .map_err(|_| McpError::Timeout {
+            message: format!(
+                "Server did not list its inventory within {} ms",
+                timeout.as_millis()
+            ),
+        })?
+}
+
+/// Cached inventories keyed by server identity. The key includes the serialized config,
+/// so editing a server invalidates its entry.
+fn cache() -> &'static Mutex<HashMap<String, McpInventory>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, McpInventory>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn cache_key(server_id: &str, config: &MCPServerConfig) -> String {
+    format!(
+        "{}\n{}",
+        server_id,
+        serde_json::to_string(config).unwrap_or_default()
+    )
+}
+
+pub fn cached_inventory(key: &str) -> Option<McpInventory> {
+    cache()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(key)
+        .cloned()
+}
+
+pub fn store_inventory(key: String, inventory: McpInventory) {
+    let mut cache = cache().lock().unwrap_or_else(|e| e.into_inner());
+    // Drop stale entries for the same server left behind by config edits
+    let server_id = key.split('\n').next().unwrap_or_default().to_string();
+    cache.retain(|existing, _| existing.split('\n').next() != Some(server_id.as_str()));
+    cache.insert(key, inventory);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_client::tests::{http_config, mock_http_server, mock_stdio_server};
+
+    #[tokio::test]
+    async fn lists_only_what_the_server_advertises() {
+        let mut client = mock_stdio_server();
+        let server = client.initialize().await.unwrap();
+        let inventory = fetch_inventory(&mut client, server).await;
+
+        assert_eq!(inventory.tools.len(), 1);
+        assert_eq!(inventory.tools[0]["name"], "echo");
+        assert_eq!(inventory.tools[0]["inputSchema"]["required"][0], "text");
+        assert_eq!(inventory.prompts[0]["name"], "greet");
+        // The mock doesn't advertise resources, so they are never requested
+        assert!(inventory.resources.is_empty());
+        assert!(inventory.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn loads_over_http() {
+        let base = mock_http_server().await;
+        let inventory = load_inventory(
+            &http_config("http", format!("{}/mcp", base)),
+            None,
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        assert_eq!(inventory.server.server_info.name, "mock-server");
+        assert_eq!(inventory.tools[0]["name"], "echo");
+    }
+
+    #[test]
+    fn config_edits_replace_the_cached_entry() {
+        let mut config = http_config("http", "http://localhost/a".to_string());
+        let first = cache_key("user::cache-test", &config);
+        config.url = Some("http://localhost/b".to_string());
+        let second = cache_key("user::cache-test", &config);
+        assert_ne!(first, second);
+
+        let inventory = McpInventory {
+            server: InitializeResult {
+                protocol_version: "2025-06-18".to_string(),
+                server_info: Default::default(),
+                capabilities: json!({}),
+                instructions: None,
+            },
+            tools: Vec::new(),
+            resources: Vec::new(),
+            resource_templates: Vec::new(),
+            prompts: Vec::new(),
+            errors: HashMap::new(),
+            fetched_at_ms: 1,
+        };
+        store_inventory(first.clone(), inventory.clone());
+        store_inventory(second.clone(), inventory);
+        assert!(cached_inventory(&first).is_none());
+        assert!(cached_inventory(&second).is_some());
+    }
+}
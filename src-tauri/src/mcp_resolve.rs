@@ -0,0 +1,156 @@
+use crate::mcp_config::{MCPServerConfig, McpjsonApproval, McpjsonApprovals};
+use indexmap::IndexMap;
+use serde::Serialize;
+
+/// The servers one scope defines
+pub struct ScopeDefinitions {
+    pub scope: String,
+    pub source_path: String,
+    pub servers: IndexMap<String, MCPServerConfig>,
+}
+
+/// A definition of a server name that Claude Code does not use
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowedDefinition {
+    pub scope: String,
+    pub source_path: String,
+    /// Project scope only
+    pub approval: Option<McpjsonApproval>,
+}
+
+/// The definition Claude Code uses for a server name in a project
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveServer {
+    pub name: String,
+    pub scope: String,
+    pub source_path: String,
+    pub config: MCPServerConfig,
+    /// Project scope only
+    pub approval: Option<McpjsonApproval>,
+    /// False for a project server that isn't approved and has no other definition to fall back to
+    pub active: bool,
+    /// Other definitions of the name, broadest scope first
+    pub shadowed: Vec<ShadowedDefinition>,
+}
+
+/// Merge `scopes`, given from broadest to narrowest (user, project, local), the way
+/// Claude Code does: a narrower definition replaces a broader one, and project servers
+/// only take part once approved. A name only defined by an unapproved project server is
+/// still listed, inactive, so it can be approved.
+pub fn resolve_effective_servers(
+    scopes: Vec<ScopeDefinitions>,
+    approvals: &McpjsonApprovals,
+) -> Vec<EffectiveServer> {
+    let mut definitions: IndexMap<String, Vec<EffectiveServer>> = IndexMap::new();
+    for scope in scopes {
+        for (name, config) in scope.servers {
+            let approval = (scope.scope == "project").then(|| approvals.status(&name));
+            definitions
+                .entry(name.clone())
+                .or_default()
+                .push(EffectiveServer {
+                    name,
+                    scope: scope.scope.clone(),
+                    source_path: scope.source_path.clone(),
+                    config,
+                    approval,
+                    active: matches!(approval, None | Some(McpjsonApproval::Approved)),
+                    shadowed: Vec::new(),
+                });
+        }
+    }
+
+    definitions
+        .into_values()
+        .map(|mut candidates| {
+            let winner = candidates
+                .iter()
+                .rposition(|candidate| candidate.active)
+                .unwrap_or(candidates.len() - 1);
+            let mut effective = candidates.remove(winner);
+            effective.shadowed = candidates
+                .into_iter()
+                .map(|candidate| ShadowedDefinition {
+                    scope: candidate.scope,
+                    source_path: candidate.source_path,
+                    approval: candidate.approval,
+                })
+                .collect();
+            effective
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(scope: &str, names: &[&str]) -> ScopeDefinitions {
+        ScopeDefinitions {
+            scope: scope.to_string(),
+            source_path: format!("/{}.json", scope),
+            servers: names
+                .iter()
+                .map(|name| {
+                    let config = MCPServerConfig {
+                        command: Some(format!("{}-{}", scope, name)),
+                        ..Default::default()
+                    };
+                    (name.to_string(), config)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn narrower_scopes_win_and_unapproved_project_servers_do_not() {
+        let approvals = McpjsonApprovals {
+            enabled: vec!["approved".to_string()],
+            disabled: vec!["rejected".to_string()],
+            enable_all: false,
+        };
+        let servers = resolve_effective_servers(
+            vec![
+                scope("user", &["approved", "pending", "everywhere"]),
+                scope(
+                    "project",
+                    &["approved", "pending", "rejected", "everywhere"],
+                ),
+                scope("local", &["everywhere"]),
+            ],
+            &approvals,
+        );
+
+        let summary: Vec<_> = servers
+            .iter()
+            .map(|s| {
+                let shadowed: Vec<_> = s.shadowed.iter().map(|d| d.scope.as_str()).collect();
+                (
+                    s.name.as_str(),
+                    s.config.command.as_deref().unwrap(),
+                    s.active,
+                    shadowed,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("approved", "project-approved", true, vec!["user"]),
+                ("pending", "user-pending", true, vec!["project"]),
+                (
+                    "everywhere",
+                    "local-everywhere",
+                    true,
+                    vec!["user", "project"]
+                ),
+                ("rejected", "project-rejected", false, vec![]),
+            ]
+        );
+        assert_eq!(
+            servers[1].shadowed[0].approval,
+            Some(McpjsonApproval::Pending)
+        );
+        assert_eq!(servers[3].approval, Some(McpjsonApproval::Rejected));
+    }
+}
@@ -0,0 +1,334 @@
+use crate::keychain_index::read_name_list;
+use crate::mcp_config::MCPServerConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Keychain service holding one entry per `VINSLY_…` reference
+pub const SECRETS_KEYRING_SERVICE: &str = "vinsly-mcp-secrets";
+
+/// Prefix of the variables that stand in for secrets moved to the keychain
+pub const REFERENCE_PREFIX: &str = "VINSLY_";
+
+/// Where in a server config a secret lives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretField {
+    Header,
+    Env,
+}
+
+/// A plaintext value that looks like a credential
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretCandidate {
+    pub field: SecretField,
+    pub key: String,
+}
+
+/// Built-in detection: `Authorization` headers and `*_KEY` / `*_TOKEN` env names
+pub fn is_likely_secret(field: SecretField, key: &str) -> bool {
+    match field {
+        SecretField::Header => key.eq_ignore_ascii_case("authorization"),
+        SecretField::Env => {
+            let key = key.to_ascii_uppercase();
+            key.ends_with("_KEY") || key.ends_with("_TOKEN")
+        }
+    }
+}
+
+/// Values already written as references (or empty) have nothing to move
+fn is_plaintext(value: &str) -> bool {
+    !value.trim().is_empty() && !value.contains("${")
+}
+
+/// Headers and env entries of `config` that look like plaintext credentials
+pub fn detect_secrets(config: &MCPServerConfig) -> Vec<SecretCandidate> {
+    let headers = config
+        .headers
+        .iter()
+        .flatten()
+        .map(|(key, value)| (SecretField::Header, key, value));
+    let env = config
+        .env
+        .iter()
+        .flatten()
+        .map(|(key, value)| (SecretField::Env, key, value));
+    headers
+        .chain(env)
+        .filter(|(field, key, value)| is_likely_secret(*field, key) && is_plaintext(value))
+        .map(|(field, key, _)| SecretCandidate {
+            field,
+            key: key.clone(),
+        })
+        .collect()
+}
+
+/// `VINSLY_<SERVER>_<KEY>`, upper-cased with everything but letters and digits as `_`
+pub fn reference_name(server_name: &str, key: &str) -> String {
+    let mut name = String::from(REFERENCE_PREFIX);
+    for part in [server_name, key] {
+        for c in part.chars() {
+            name.push(if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            });
+        }
+        name.push('_');
+    }
+    name.pop();
+    name
+}
+
+/// A reference name that is free in the keychain or already holds `value`, so two
+/// servers with the same name in different scopes don't overwrite each other's secret
+pub fn unique_reference_name(
+    base: &str,
+    value: &str,
+    stored: impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut name = base.to_string();
+    let mut n = 2;
+    while let Some(existing) = stored(&name) {
+        if existing == value {
+            break;
+        }
+        name = format!("{}_{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+/// Split an `Authorization` value into the scheme to keep in plaintext and the credential
+fn split_scheme(value: &str) -> (&str, &str) {
+    match value.split_once(' ') {
+        Some((scheme, credential))
+            if !credential.trim().is_empty()
+                && scheme.chars().all(|c| c.is_ascii_alphabetic())
+                && ["bearer", "basic", "token"].contains(&scheme.to_ascii_lowercase().as_str()) =>
+        {
+            (&value[..scheme.len() + 1], credential.trim())
+        }
+        _ => ("", value),
+    }
+}
+
+/// A secret taken out of a config: the credential to store under `reference`
+#[derive(Debug, Clone)]
+pub struct ExtractedSecret {
+    pub reference: String,
+    pub value: String,
+}
+
+/// Replace the value at `field`/`key` with a `${VINSLY_…}` reference. An `Authorization`
+/// scheme such as `Bearer ` stays in the config. `name_for` picks the reference name for
+/// the credential.
+pub fn extract_secret(
+    config: &mut MCPServerConfig,
+    field: SecretField,
+    key: &str,
+    name_for: impl FnOnce(&str) -> String,
+) -> Result<ExtractedSecret, String> {
+    let (map, label) = match field {
+        SecretField::Header => (config.headers.as_mut(), "Header"),
+        SecretField::Env => (config.env.as_mut(), "Environment variable"),
+    };
+    let value = map
+        .and_then(|map| map.get_mut(key))
+        .ok_or_else(|| format!("{} '{}' not found", label, key))?;
+    if !is_plaintext(value) {
+        return Err(format!(
+            "{} '{}' has no plaintext value to move",
+            label, key
+        ));
+    }
+
+    let (prefix, credential) = match field {
+        SecretField::Header if key.eq_ignore_ascii_case("authorization") => split_scheme(value),
+        _ => ("", value.as_str()),
+    };
+    let (prefix, credential) = (prefix.to_string(), credential.to_string());
+    let reference = name_for(&credential);
+    *value = format!("{}${{{}}}", prefix, reference);
+    Ok(ExtractedSecret {
+        reference,
+        value: credential,
+    })
+}
+
+pub fn load_secret(name: &str) -> Option<String> {
+    keyring::Entry::new(SECRETS_KEYRING_SERVICE, name)
+        .ok()?
+        .get_password()
+        .ok()
+}
+
+pub fn store_secret(name: &str, value: &str) -> Result<(), String> {
+    keyring::Entry::new(SECRETS_KEYRING_SERVICE, name)
+        .map_err(|e| format!("Failed to access keyring: {}", e))?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store secret: {}", e))
+}
+
+/// Every stored secret as environment variables, for processes Vinsly starts itself
+pub fn secrets_env(index_path: &Path) -> HashMap<String, String> {
+    read_name_list(index_path)
+        .into_iter()
+        .filter_map(|name| load_secret(&name).map(|value| (name, value)))
+        .collect()
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Shell lines that export every stored secret by reading it from the keychain at shell
+/// startup, for a profile such as `~/.zshrc`. Claude Code started from that shell can then
+/// expand the `${VINSLY_…}` references itself.
+pub fn launcher_script(names: &[String]) -> Result<String, String> {
+    let read_command = |name: &str| -> Result<String, String> {
+        if cfg!(target_os = "macos") {
+            Ok(format!(
+                "security find-generic-password -s {} -a {} -w",
+                shell_quote(SECRETS_KEYRING_SERVICE),
+                shell_quote(name)
+            ))
+        } else if cfg!(target_os = "linux") {
+            Ok(format!(
+                "secret-tool lookup service {} username {}",
+                shell_quote(SECRETS_KEYRING_SERVICE),
+                shell_quote(name)
+            ))
+        } else {
+            Err(
+                "Reading keychain secrets from a shell isn't supported on this platform; \
+                 start Claude Code from the Vinsly terminal instead"
+                    .to_string(),
+            )
+        }
+    };
+
+    let mut script = String::from("# MCP secrets stored by Vinsly\n");
+    for name in names {
+        script.push_str(&format!(
+            "export {}=\"$({} 2>/dev/null)\"\n",
+            name,
+            read_command(name)?
+        ));
+    }
+    Ok(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::IndexMap;
+
+    fn config() -> MCPServerConfig {
+        MCPServerConfig {
+            headers: Some(IndexMap::from([
+                (
+                    "Authorization".to_string(),
+                    "Bearer sk-live-123".to_string(),
+                ),
+                ("X-Trace".to_string(), "on".to_string()),
+            ])),
+            env: Some(IndexMap::from([
+                ("GITHUB_TOKEN".to_string(), "ghp_abc".to_string()),
+                (
+                    "OPENAI_API_KEY".to_string(),
+                    "${OPENAI_API_KEY}".to_string(),
+                ),
+                ("LOG_LEVEL".to_string(), "debug".to_string()),
+            ])),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_authorization_headers_and_key_or_token_env_names() {
+        let found: Vec<_> = detect_secrets(&config())
+            .into_iter()
+            .map(|c| (c.field, c.key))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (SecretField::Header, "Authorization".to_string()),
+                (SecretField::Env, "GITHUB_TOKEN".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn moving_a_header_keeps_the_scheme() {
+        let mut config = config();
+        let secret = extract_secret(&mut config, SecretField::Header, "Authorization", |_| {
+            reference_name("linear.app", "Authorization")
+        })
+        .unwrap();
+        assert_eq!(secret.reference, "VINSLY_LINEAR_APP_AUTHORIZATION");
+        assert_eq!(secret.value, "sk-live-123");
+        assert_eq!(
+            config.headers.unwrap()["Authorization"],
+            "Bearer ${VINSLY_LINEAR_APP_AUTHORIZATION}"
+        );
+    }
+
+    #[test]
+    fn moving_an_env_value_replaces_it_whole() {
+        let mut config = config();
+        let secret = extract_secret(&mut config, SecretField::Env, "GITHUB_TOKEN", |_| {
+            reference_name("github", "GITHUB_TOKEN")
+        })
+        .unwrap();
+        assert_eq!(secret.value, "ghp_abc");
+        assert_eq!(
+            config.env.as_ref().unwrap()["GITHUB_TOKEN"],
+            "${VINSLY_GITHUB_GITHUB_TOKEN}"
+        );
+
+        // Already a reference now
+        let again = extract_secret(
+            &mut config,
+            SecretField::Env,
+            "GITHUB_TOKEN",
+            |_| unreachable!(),
+        );
+        assert!(again.is_err());
+    }
+
+    #[test]
+    fn reference_names_avoid_other_secrets() {
+        let stored = |name: &str| match name {
+            "VINSLY_A_KEY" => Some("other".to_string()),
+            "VINSLY_A_KEY_2" => Some("mine".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            unique_reference_name("VINSLY_A_KEY", "mine", stored),
+            "VINSLY_A_KEY_2"
+        );
+        assert_eq!(
+            unique_reference_name("VINSLY_A_KEY", "new", stored),
+            "VINSLY_A_KEY_3"
+        );
+        assert_eq!(
+            unique_reference_name("VINSLY_B_KEY", "x", stored),
+            "VINSLY_B_KEY"
+        );
+    }
+
+    #[test]
+    fn launcher_script_exports_each_reference() {
+        let script = launcher_script(&["VINSLY_A_KEY".to_string()]);
+        if cfg!(any(target_os = "macos", target_os = "linux")) {
+            let script = script.unwrap();
+            assert!(script.contains("export VINSLY_A_KEY=\"$("));
+            assert!(script.contains("'vinsly-mcp-secrets'"));
+        } else {
+            // No keychain command line to read the secret with
+            assert!(script.is_err());
+        }
+    }
+}
@@ -0,0 +1,564 @@
+use crate::fs_write::{WriteConflict, WriteError};
+use crate::mcp_config::json_pointer_token;
+use crate::mcp_env::expand_references;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+const SERVER_TYPES: &[&str] = &["stdio", "http", "sse"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Claude Code can't use the entry as written
+    Error,
+    /// Usable, but probably not what was meant, or broken on this machine
+    Warning,
+}
+
+/// A problem with one value of an MCP config file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    /// JSON pointer (RFC 6901) into the config file
+    pub pointer: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(pointer: String, message: impl Into<String>) -> Self {
+        Self {
+            pointer,
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(pointer: String, message: impl Into<String>) -> Self {
+        Self {
+            pointer,
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+/// Error returned by MCP config writers, serialized to the frontend as `{ kind, message, ... }`
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MCPWriteError {
+    Invalid {
+        message: String,
+        diagnostics: Vec<Diagnostic>,
+    },
+    Conflict(WriteConflict),
+    Io {
+        message: String,
+    },
+}
+
+impl From<String> for MCPWriteError {
+    fn from(message: String) -> Self {
+        Self::Io { message }
+    }
+}
+
+impl From<WriteError> for MCPWriteError {
+    fn from(error: WriteError) -> Self {
+        match error {
+            WriteError::Conflict(conflict) => Self::Conflict(conflict),
+            WriteError::Io { message } => Self::Io { message },
+        }
+    }
+}
+
+impl From<Vec<Diagnostic>> for MCPWriteError {
+    fn from(diagnostics: Vec<Diagnostic>) -> Self {
+        let message = diagnostics
+            .iter()
+            .map(|d| d.message.clone())
+            .collect::<Vec<_>>()
+            .join("; ");
+        Self::Invalid {
+            message,
+            diagnostics,
+        }
+    }
+}
+
+/// Reject a server entry about to be written if it has errors; warnings don't block
+pub fn ensure_valid(diagnostics: Vec<Diagnostic>) -> Result<(), MCPWriteError> {
+    let errors: Vec<Diagnostic> = diagnostics
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.into())
+    }
+}
+
+/// What the checks may look up in the environment Claude Code runs in
+pub trait Environment {
+    /// Value of an environment variable, used for `${VAR}` references and `PATH`
+    fn var(&self, name: &str) -> Option<String>;
+    fn is_file(&self, path: &Path) -> bool;
+}
+
+/// The real filesystem, with variables from `lookup`
+pub struct SystemEnvironment<F: Fn(&str) -> Option<String>>(pub F);
+
+impl<F: Fn(&str) -> Option<String>> Environment for SystemEnvironment<F> {
+    fn var(&self, name: &str) -> Option<String> {
+        (self.0)(name)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+}
+
+/// Where `command` resolves on `path_var`, the way a shell looks it up
+pub fn find_on_path(command: &str, path_var: &str, env: &dyn Environment) -> Option<PathBuf> {
+    let extensions: &[&str] = if cfg!(windows) {
+        &["", ".exe", ".cmd", ".bat"]
+    } else {
+        &[""]
+    };
+    std::env::split_paths(path_var)
+        .flat_map(|dir| {
+            extensions
+                .iter()
+                .map(move |ext| dir.join(format!("{}{}", command, ext)))
+        })
+        .find(|candidate| env.is_file(candidate))
+}
+
+/// The value with `${VAR}` references expanded, or `None` if any doesn't resolve
+/// (already reported by `get_mcp_unresolved_variables`)
+fn expanded(value: &str, env: &dyn Environment) -> Option<String> {
+    let mut unresolved = Vec::new();
+    let value = expand_references(value, |name| env.var(name), &mut unresolved);
+    unresolved.is_empty().then_some(value)
+}
+
+fn check_string(
+    entry: &serde_json::Map<String, Value>,
+    key: &str,
+    pointer: &str,
+) -> Vec<Diagnostic> {
+    match entry.get(key) {
+        Some(value) if !value.is_string() => vec![Diagnostic::error(
+            format!("{}/{}", pointer, key),
+            format!("'{}' must be a string", key),
+        )],
+        _ => Vec::new(),
+    }
+}
+
+fn check_string_array(
+    entry: &serde_json::Map<String, Value>,
+    key: &str,
+    pointer: &str,
+) -> Vec<Diagnostic> {
+    let pointer = format!("{}/{}", pointer, key);
+    match entry.get(key) {
+        None => Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| !item.is_string())
+            .map(|(i, _)| {
+                Diagnostic::error(
+                    format!("{}/{}", pointer, i),
+                    format!("'{}' entries must be strings", key),
+                )
+            })
+            .collect(),
+        Some(_) => vec![Diagnostic::error(
+            pointer,
+            format!("'{}' must be an array of strings", key),
+        )],
+    }
+}
+
+fn check_string_map(
+    entry: &serde_json::Map<String, Value>,
+    key: &str,
+    pointer: &str,
+) -> Vec<Diagnostic> {
+    let pointer = format!("{}/{}", pointer, key);
+    match entry.get(key) {
+        None => Vec::new(),
+        Some(Value::Object(map)) => map
+            .iter()
+            .filter(|(_, value)| !value.is_string())
+            .map(|(name, _)| {
+                Diagnostic::error(
+                    format!("{}/{}", pointer, json_pointer_token(name)),
+                    format!("'{}' values must be strings", key),
+                )
+            })
+            .collect(),
+        Some(_) => vec![Diagnostic::error(
+            pointer,
+            format!("'{}' must be an object of strings", key),
+        )],
+    }
+}
+
+fn check_command(command: &str, pointer: String, env: &dyn Environment) -> Option<Diagnostic> {
+    let command = expanded(command, env)?;
+    if command.trim().is_empty() {
+        return Some(Diagnostic::error(pointer, "Command is empty"));
+    }
+    let path = Path::new(&command);
+    if path.is_absolute() {
+        return (!env.is_file(path))
+            .then(|| Diagnostic::warning(pointer, format!("'{}' does not exist", command)));
+    }
+    if command.contains('/') || command.contains('\\') {
+        return Some(Diagnostic::warning(
+            pointer,
+            format!(
+                "'{}' is relative to the directory Claude Code is started in",
+                command
+            ),
+        ));
+    }
+    let path_var = env.var("PATH").unwrap_or_default();
+    find_on_path(&command, &path_var, env)
+        .is_none()
+        .then(|| Diagnostic::warning(pointer, format!("'{}' was not found on PATH", command)))
+}
+
+fn check_url(url: &str, pointer: String, env: &dyn Environment) -> Option<Diagnostic> {
+    let url = expanded(url, env)?;
+    match reqwest::Url::parse(&url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => None,
+        Ok(parsed) => Some(Diagnostic::error(
+            pointer,
+            format!(
+                "URL scheme must be http or https, not '{}'",
+                parsed.scheme()
+            ),
+        )),
+        Err(e) => Some(Diagnostic::error(
+            pointer,
+            format!("Invalid URL '{}': {}", url, e),
+        )),
+    }
+}
+
+/// Check one `mcpServers` entry. `pointer` is the entry's JSON pointer in its file.
+pub fn validate_server(entry: &Value, pointer: &str, env: &dyn Environment) -> Vec<Diagnostic> {
+    let Some(entry) = entry.as_object() else {
+        return vec![Diagnostic::error(
+            pointer.to_string(),
+            "Server entry must be an object",
+        )];
+    };
+    let at = |key: &str| format!("{}/{}", pointer, key);
+
+    let mut diagnostics = Vec::new();
+    for key in ["type", "command", "url"] {
+        diagnostics.extend(check_string(entry, key, pointer));
+    }
+    diagnostics.extend(check_string_array(entry, "args", pointer));
+    diagnostics.extend(check_string_map(entry, "env", pointer));
+    diagnostics.extend(check_string_map(entry, "headers", pointer));
+
+    let command = entry.get("command").and_then(Value::as_str);
+    let url = entry.get("url").and_then(Value::as_str);
+    let server_type = match entry.get("type").and_then(Value::as_str) {
+        Some(server_type) if SERVER_TYPES.contains(&server_type) => server_type,
+        Some(other) => {
+            diagnostics.push(Diagnostic::error(
+                at("type"),
+                format!(
+                    "Unknown server type '{}' (expected stdio, http or sse)",
+                    other
+                ),
+            ));
+            return diagnostics;
+        }
+        None if command.is_none() && url.is_some() => {
+            diagnostics.push(Diagnostic::warning(
+                pointer.to_string(),
+                "Remote servers should set \"type\" to \"http\" or \"sse\"",
+            ));
+            "http"
+        }
+        None => "stdio",
+    };
+
+    if server_type == "stdio" {
+        match command {
+            Some(command) => diagnostics.extend(check_command(command, at("command"), env)),
+            None if !entry.contains_key("command") => diagnostics.push(Diagnostic::error(
+                pointer.to_string(),
+                "stdio servers need a \"command\"",
+            )),
+            None => {}
+        }
+        for key in ["url", "headers"] {
+            if entry.contains_key(key) {
+                diagnostics.push(Diagnostic::warning(
+                    at(key),
+                    format!("'{}' is ignored for stdio servers", key),
+                ));
+            }
+        }
+    } else {
+        match url {
+            Some(url) => diagnostics.extend(check_url(url, at("url"), env)),
+            None if !entry.contains_key("url") => diagnostics.push(Diagnostic::error(
+                pointer.to_string(),
+                format!("{} servers need a \"url\"", server_type),
+            )),
+            None => {}
+        }
+        for key in ["command", "args", "env"] {
+            if entry.contains_key(key) {
+                diagnostics.push(Diagnostic::warning(
+                    at(key),
+                    format!("'{}' is ignored for {} servers", key, server_type),
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// The raw servers of one scope, as read for validation
+pub struct ScopeServers {
+    pub scope: String,
+    pub source_path: String,
+    /// Pointer of the `mcpServers` object in the file
+    pub pointer: String,
+    /// The `mcpServers` value, or why the file couldn't be read
+    pub servers: Result<Option<Value>, String>,
+}
+
+/// Diagnostics for one server, or for a whole file when `name` is `None`
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerDiagnostics {
+    pub scope: String,
+    pub name: Option<String>,
+    pub source_path: String,
+    /// Pointer of the server entry, or of what was wrong with the file
+    pub pointer: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Validate every server of `scopes`, given from broadest to narrowest (user, project,
+/// local), and flag names defined in more than one: the narrowest definition wins.
+/// Servers without problems are left out.
+pub fn validate_scopes(scopes: &[ScopeServers], env: &dyn Environment) -> Vec<ServerDiagnostics> {
+    let mut report = Vec::new();
+    let mut defined: Vec<(&str, &str)> = Vec::new();
+    for scope in scopes {
+        let entries = match &scope.servers {
+            Ok(None) => continue,
+            Ok(Some(Value::Object(entries))) => entries,
+            Ok(Some(_)) => {
+                report.push(ServerDiagnostics {
+                    scope: scope.scope.clone(),
+                    name: None,
+                    source_path: scope.source_path.clone(),
+                    pointer: scope.pointer.clone(),
+                    diagnostics: vec![Diagnostic::error(
+                        scope.pointer.clone(),
+                        "\"mcpServers\" must be an object",
+                    )],
+                });
+                continue;
+            }
+            Err(message) => {
+                report.push(ServerDiagnostics {
+                    scope: scope.scope.clone(),
+                    name: None,
+                    source_path: scope.source_path.clone(),
+                    pointer: String::new(),
+                    diagnostics: vec![Diagnostic::error(String::new(), message.clone())],
+                });
+                continue;
+            }
+        };
+        for (name, entry) in entries {
+            defined.push((&scope.scope, name));
+            let pointer = format!("{}/{}", scope.pointer, json_pointer_token(name));
+            report.push(ServerDiagnostics {
+                scope: scope.scope.clone(),
+                name: Some(name.clone()),
+                source_path: scope.source_path.clone(),
+                diagnostics: validate_server(entry, &pointer, env),
+                pointer,
+            });
+        }
+    }
+
+    for server in &mut report {
+        let Some(name) = &server.name else {
+            continue;
+        };
+        let scopes: Vec<&str> = defined
+            .iter()
+            .filter(|(_, n)| n == name)
+            .map(|(scope, _)| *scope)
+            .collect();
+        if scopes.len() < 2 {
+            continue;
+        }
+        let winner = scopes[scopes.len() - 1];
+        let others: Vec<&str> = scopes
+            .iter()
+            .copied()
+            .filter(|scope| *scope != server.scope)
+            .collect();
+        let message = if server.scope == winner {
+            format!(
+                "Also defined in {} scope; this definition takes precedence",
+                others.join(" and ")
+            )
+        } else {
+            format!("Shadowed by the definition in {} scope", winner)
+        };
+        server
+            .diagnostics
+            .push(Diagnostic::warning(server.pointer.clone(), message));
+    }
+
+    report.retain(|server| !server.diagnostics.is_empty());
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct FakeEnvironment;
+
+    impl Environment for FakeEnvironment {
+        fn var(&self, name: &str) -> Option<String> {
+            match name {
+                "PATH" => Some("/usr/bin:/opt/tools/bin".to_string()),
+                "HOST" => Some("example.com".to_string()),
+                _ => None,
+            }
+        }
+
+        fn is_file(&self, path: &Path) -> bool {
+            path == Path::new("/usr/bin/npx") || path == Path::new("/opt/tools/bin/uvx")
+        }
+    }
+
+    fn summary(diagnostics: &[Diagnostic]) -> Vec<(String, Severity)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.pointer.clone(), d.severity))
+            .collect()
+    }
+
+    #[test]
+    fn flags_fields_that_do_not_fit_the_type() {
+        let stdio = json!({"command": "npx", "args": ["-y", 3], "url": "https://x.test"});
+        assert_eq!(
+            summary(&validate_server(&stdio, "/mcpServers/a", &FakeEnvironment)),
+            vec![
+                ("/mcpServers/a/args/1".to_string(), Severity::Error),
+                ("/mcpServers/a/url".to_string(), Severity::Warning),
+            ]
+        );
+
+        let http = json!({"type": "http", "env": {"A": "1"}});
+        assert_eq!(
+            summary(&validate_server(&http, "/mcpServers/b", &FakeEnvironment)),
+            vec![
+                ("/mcpServers/b".to_string(), Severity::Error),
+                ("/mcpServers/b/env".to_string(), Severity::Warning),
+            ]
+        );
+
+        let unknown = json!({"type": "websocket", "url": "ws://x"});
+        let diagnostics = validate_server(&unknown, "/mcpServers/c", &FakeEnvironment);
+        assert_eq!(diagnostics[0].pointer, "/mcpServers/c/type");
+        assert!(ensure_valid(diagnostics).is_err());
+    }
+
+    #[test]
+    fn checks_commands_on_path_and_urls() {
+        let check = |entry: Value| {
+            validate_server(&entry, "", &FakeEnvironment)
+                .into_iter()
+                .map(|d| d.message)
+                .collect::<Vec<_>>()
+        };
+        assert!(check(json!({"command": "uvx"})).is_empty());
+        assert!(check(json!({"command": "/usr/bin/npx"})).is_empty());
+        assert!(check(json!({"command": "${UNSET}/server"})).is_empty());
+        assert_eq!(
+            check(json!({"command": "bunx"})),
+            vec!["'bunx' was not found on PATH"]
+        );
+        assert!(check(json!({"type": "http", "url": "https://${HOST}/mcp"})).is_empty());
+        assert!(check(json!({"type": "sse", "url": "not a url"}))[0].starts_with("Invalid URL"));
+        assert!(check(json!({"type": "http", "url": "ftp://x.test"}))[0].contains("'ftp'"));
+    }
+
+    #[test]
+    fn reports_shadowed_names_and_unreadable_scopes() {
+        let scope = |scope: &str, servers: Result<Option<Value>, String>| ScopeServers {
+            scope: scope.to_string(),
+            source_path: format!("/{}.json", scope),
+            pointer: "/mcpServers".to_string(),
+            servers,
+        };
+        let report = validate_scopes(
+            &[
+                scope(
+                    "user",
+                    Ok(Some(
+                        json!({"gh": {"command": "npx"}, "ok": {"command": "npx"}}),
+                    )),
+                ),
+                scope(
+                    "project",
+                    Err("Failed to parse MCP config: EOF".to_string()),
+                ),
+                scope("local", Ok(Some(json!({"gh": {"command": "uvx"}})))),
+            ],
+            &FakeEnvironment,
+        );
+
+        let found: Vec<_> = report
+            .iter()
+            .map(|s| {
+                (
+                    s.scope.as_str(),
+                    s.name.as_deref(),
+                    s.diagnostics[0].message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "user",
+                    Some("gh"),
+                    "Shadowed by the definition in local scope"
+                ),
+                ("project", None, "Failed to parse MCP config: EOF"),
+                (
+                    "local",
+                    Some("gh"),
+                    "Also defined in user scope; this definition takes precedence"
+                ),
+            ]
+        );
+        assert_eq!(report[0].pointer, "/mcpServers/gh");
+    }
+}
@@ -0,0 +1,1082 @@
+use crate::mcp_client::LATEST_PROTOCOL_VERSION;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Name sent during dynamic client registration
+const CLIENT_NAME: &str = "Vinsly";
+
+/// Time allowed for each discovery, registration and token request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Endpoints of an OAuth authorization server (RFC 8414)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthorizationServerMetadata {
+    #[serde(default)]
+    pub issuer: Option<String>,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    #[serde(default)]
+    pub registration_endpoint: Option<String>,
+    #[serde(default)]
+    pub scopes_supported: Option<Vec<String>>,
+    #[serde(default)]
+    pub code_challenge_methods_supported: Option<Vec<String>>,
+}
+
+/// What the MCP server told us about how to authorize against it
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    /// Canonical URI of the MCP server, sent as the `resource` parameter (RFC 8707)
+    pub resource: String,
+    pub metadata: AuthorizationServerMetadata,
+    /// Scope requested by the server's challenge or listed in its resource metadata
+    pub scope: Option<String>,
+}
+
+/// Credentials from dynamic client registration (RFC 7591)
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegisteredClient {
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    #[serde(default)]
+    pub token_endpoint_auth_method: Option<String>,
+}
+
+/// Token set kept in the keyring, with what is needed to refresh it later
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Unix seconds
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    #[serde(default = "default_token_type")]
+    pub token_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_endpoint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_endpoint_auth_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+fn default_token_type() -> String {
+    "Bearer".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    token_type: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+pub(crate) fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+fn parse_url(url: &str) -> Result<Url, String> {
+    Url::parse(url.trim()).map_err(|e| format!("Invalid URL '{}': {}", url, e))
+}
+
+/// Canonical resource URI of an MCP server: no fragment, no trailing slash
+fn canonical_resource(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string().trim_end_matches('/').to_string()
+}
+
+fn origin(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+/// Path of `url` without its trailing slash, empty for the root
+fn path_suffix(url: &Url) -> String {
+    url.path().trim_end_matches('/').to_string()
+}
+
+/// Value of one auth-param in a `WWW-Authenticate: Bearer ...` challenge
+pub fn challenge_param(header: &str, name: &str) -> Option<String> {
+    let rest = header.trim();
+    let rest = match rest.split_once(char::is_whitespace) {
+        Some((scheme, params)) if !scheme.contains('=') => params,
+        _ => rest,
+    };
+    let mut chars = rest.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| *c == ',' || c.is_whitespace()) {
+            chars.next();
+        }
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if key.is_empty() {
+            return None;
+        }
+        let value: String = if chars.peek() == Some(&'"') {
+            chars.next();
+            let mut value = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+            value
+        } else {
+            chars.by_ref().take_while(|c| *c != ',').collect()
+        };
+        if key.trim().eq_ignore_ascii_case(name) {
+            return Some(value.trim().to_string());
+        }
+    }
+}
+
+async fn get_json(client: &reqwest::Client, url: &str) -> Option<Value> {
+    let response = client
+        .get(url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response.json().await.ok()
+}
+
+/// Ask the MCP server for its challenge: an unauthenticated initialize should get a 401
+/// whose `WWW-Authenticate` header points at the protected resource metadata.
+async fn fetch_challenge(client: &reqwest::Client, server_url: &Url) -> Option<String> {
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": LATEST_PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": CLIENT_NAME, "version": env!("CARGO_PKG_VERSION") },
+        },
+    });
+    let response = client
+        .post(server_url.clone())
+        .header(
+            reqwest::header::ACCEPT,
+            "application/json, text/event-stream",
+        )
+        .json(&initialize)
+        .send()
+        .await
+        .ok()?;
+    if response.status() != reqwest::StatusCode::UNAUTHORIZED {
+        return None;
+    }
+    response
+        .headers()
+        .get(reqwest::header::WWW_AUTHENTICATE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+/// Protected resource metadata (RFC 9728), from the challenge or the well-known URIs
+async fn fetch_resource_metadata(
+    client: &reqwest::Client,
+    server_url: &Url,
+    challenge: Option<&str>,
+) -> Option<Value> {
+    let mut candidates = Vec::new();
+    if let Some(url) = challenge.and_then(|c| challenge_param(c, "resource_metadata")) {
+        candidates.push(url);
+    }
+    let base = origin(server_url);
+    let path = path_suffix(server_url);
+    if !path.is_empty() {
+        candidates.push(format!(
+            "{}/.well-known/oauth-protected-resource{}",
+            base, path
+        ));
+    }
+    candidates.push(format!("{}/.well-known/oauth-protected-resource", base));
+
+    for url in candidates {
+        if let Some(metadata) = get_json(client, &url).await {
+            return Some(metadata);
+        }
+    }
+    None
+}
+
+/// Authorization server metadata, trying the RFC 8414 and OpenID Connect well-known URIs
+async fn fetch_authorization_server_metadata(
+    client: &reqwest::Client,
+    issuer: &Url,
+) -> Option<AuthorizationServerMetadata> {
+    let base = origin(issuer);
+    let path = path_suffix(issuer);
+    let candidates = if path.is_empty() {
+        vec![
+            format!("{}/.well-known/oauth-authorization-server", base),
+            format!("{}/.well-known/openid-configuration", base),
+        ]
+    } else {
+        vec![
+            format!("{}/.well-known/oauth-authorization-server{}", base, path),
+            format!("{}/.well-known/openid-configuration{}", base, path),
+            format!("{}{}/.well-known/openid-configuration", base, path),
+        ]
+    };
+
+    for url in candidates {
+        if let Some(metadata) = get_json(client, &url).await {
+            if let Ok(metadata) = serde_json::from_value(metadata) {
+                return Some(metadata);
+            }
+        }
+    }
+    None
+}
+
+/// Find the authorization server protecting `server_url` and read its metadata.
+/// Servers without any metadata get the default `/authorize`, `/token` and `/register`
+/// endpoints on their own origin.
+pub async fn discover(server_url: &str) -> Result<Discovery, String> {
+    let server_url = parse_url(server_url)?;
+    let client = http_client()?;
+
+    let challenge = fetch_challenge(&client, &server_url).await;
+    let resource_metadata =
+        fetch_resource_metadata(&client, &server_url, challenge.as_deref()).await;
+
+    let resource = resource_metadata
+        .as_ref()
+        .and_then(|m| m.get("resource"))
+        .and_then(|r| r.as_str())
+        .map(|r| r.to_string())
+        .unwrap_or_else(|| canonical_resource(&server_url));
+    let scope = challenge
+        .as_deref()
+        .and_then(|c| challenge_param(c, "scope"))
+        .or_else(|| {
+            let scopes = resource_metadata
+                .as_ref()?
+                .get("scopes_supported")?
+                .as_array()?
+                .iter()
+                .filter_map(|s| s.as_str())
+                .collect::<Vec<_>>();
+            (!scopes.is_empty()).then(|| scopes.join(" "))
+        });
+    let issuer = match resource_metadata
+        .as_ref()
+        .and_then(|m| m.get("authorization_servers"))
+        .and_then(|s| s.as_array())
+        .and_then(|s| s.first())
+        .and_then(|s| s.as_str())
+    {
+        Some(issuer) => parse_url(issuer)?,
+        None => parse_url(&origin(&server_url))?,
+    };
+
+    let metadata = match fetch_authorization_server_metadata(&client, &issuer).await {
+        Some(metadata) => metadata,
+        None => {
+            let base = origin(&issuer);
+            AuthorizationServerMetadata {
+                issuer: Some(base.clone()),
+                authorization_endpoint: format!("{}/authorize", base),
+                token_endpoint: format!("{}/token", base),
+                registration_endpoint: Some(format!("{}/register", base)),
+                ..Default::default()
+            }
+        }
+    };
+
+    Ok(Discovery {
+        resource,
+        metadata,
+        scope,
+    })
+}
+
+/// Register Vinsly as a public client with `redirect_uri`
+pub async fn register_client(
+    metadata: &AuthorizationServerMetadata,
+    redirect_uri: &str,
+) -> Result<RegisteredClient, String> {
+    let endpoint = metadata.registration_endpoint.as_deref().ok_or_else(|| {
+        "The authorization server does not support dynamic client registration".to_string()
+    })?;
+    let response = http_client()?
+        .post(endpoint)
+        .json(&json!({
+            "client_name": CLIENT_NAME,
+            "redirect_uris": [redirect_uri],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "token_endpoint_auth_method": "none",
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to register client: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Client registration failed: {}",
+            error_summary(response).await
+        ));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse client registration: {}", e))
+}
+
+/// `error: error_description` from an OAuth error body, or the status and body start
+async fn error_summary(response: reqwest::Response) -> String {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if let Ok(error) = serde_json::from_str::<Value>(&body) {
+        if let Some(code) = error.get("error").and_then(|e| e.as_str()) {
+            return match error.get("error_description").and_then(|d| d.as_str()) {
+                Some(description) => format!("{}: {}", code, description),
+                None => code.to_string(),
+            };
+        }
+    }
+    let snippet: String = body.trim().chars().take(200).collect();
+    if snippet.is_empty() {
+        format!("HTTP {}", status)
+    } else {
+        format!("HTTP {}: {}", status, snippet)
+    }
+}
+
+/// PKCE verifier and its S256 challenge (RFC 7636)
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub fn new() -> Self {
+        let mut random = Vec::with_capacity(32);
+        random.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+        random.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+        let verifier = URL_SAFE_NO_PAD.encode(random);
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+impl Default for Pkce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Browser URL that starts the authorization code flow
+pub fn authorization_url(
+    discovery: &Discovery,
+    client: &RegisteredClient,
+    redirect_uri: &str,
+    pkce: &Pkce,
+    state: &str,
+) -> Result<String, String> {
+    let mut url = parse_url(&discovery.metadata.authorization_endpoint)?;
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &client.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("code_challenge", &pkce.challenge)
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("state", state)
+            .append_pair("resource", &discovery.resource);
+        if let Some(scope) = &discovery.scope {
+            query.append_pair("scope", scope);
+        }
+    }
+    Ok(url.to_string())
+}
+
+/// Failed token request
+#[derive(Debug, Clone)]
+pub struct TokenError {
+    pub message: String,
+    /// The authorization server turned the grant down (HTTP 400/401), as opposed to being
+    /// unreachable or failing on its side. A rejected refresh token needs a new sign-in.
+    pub rejected: bool,
+}
+
+impl From<String> for TokenError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            rejected: false,
+        }
+    }
+}
+
+/// POST a token request, authenticating the client the way it was registered
+async fn token_request(
+    token_endpoint: &str,
+    client_id: &str,
+    client_secret: Option<&str>,
+    auth_method: Option<&str>,
+    mut form: Vec<(&str, String)>,
+) -> Result<TokenResponse, TokenError> {
+    let mut request = http_client()?
+        .post(token_endpoint)
+        .header(reqwest::header::ACCEPT, "application/json");
+    match (client_secret, auth_method) {
+        (Some(secret), Some("client_secret_basic")) => {
+            request = request.basic_auth(client_id, Some(secret));
+        }
+        (Some(secret), _) => {
+            form.push(("client_id", client_id.to_string()));
+            form.push(("client_secret", secret.to_string()));
+        }
+        (None, _) => form.push(("client_id", client_id.to_string())),
+    }
+
+    let response = request
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach token endpoint: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(TokenError {
+            message: format!("Token request failed: {}", error_summary(response).await),
+            rejected: status == reqwest::StatusCode::BAD_REQUEST
+                || status == reqwest::StatusCode::UNAUTHORIZED,
+        });
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse token response: {}", e).into())
+}
+
+fn stored_token(response: TokenResponse) -> StoredToken {
+    StoredToken {
+        access_token: response.access_token,
+        refresh_token: response.refresh_token,
+        expires_at: response.expires_in.map(|secs| now_secs() + secs),
+        token_type: response.token_type.unwrap_or_else(default_token_type),
+        scope: response.scope,
+        ..Default::default()
+    }
+}
+
+/// Exchange an authorization code for tokens, proving possession of the PKCE verifier
+pub async fn exchange_code(
+    discovery: &Discovery,
+    client: &RegisteredClient,
+    redirect_uri: &str,
+    code: &str,
+    verifier: &str,
+) -> Result<StoredToken, String> {
+    let response = token_request(
+        &discovery.metadata.token_endpoint,
+        &client.client_id,
+        client.client_secret.as_deref(),
+        client.token_endpoint_auth_method.as_deref(),
+        vec![
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code.to_string()),
+            ("redirect_uri", redirect_uri.to_string()),
+            ("code_verifier", verifier.to_string()),
+            ("resource", discovery.resource.clone()),
+        ],
+    )
+    .await
+    .map_err(|e| e.message)?;
+
+    let mut token = stored_token(response);
+    token.token_endpoint = Some(discovery.metadata.token_endpoint.clone());
+    token.client_id = Some(client.client_id.clone());
+    token.client_secret = client.client_secret.clone();
+    token.token_endpoint_auth_method = client.token_endpoint_auth_method.clone();
+    token.resource = Some(discovery.resource.clone());
+    if token.scope.is_none() {
+        token.scope = discovery.scope.clone();
+    }
+    Ok(token)
+}
+
+/// Whether `token` expires within `margin_secs` of `now` (unix seconds).
+/// Tokens without an expiry are treated as long-lived.
+pub fn expires_within(token: &StoredToken, now: i64, margin_secs: i64) -> bool {
+    token
+        .expires_at
+        .is_some_and(|expires_at| expires_at - margin_secs <= now)
+}
+
+/// Whether the token records everything needed for a refresh grant
+pub fn can_refresh(token: &StoredToken) -> bool {
+    token.refresh_token.is_some() && token.token_endpoint.is_some() && token.client_id.is_some()
+}
+
+/// Renew `token` with its refresh token at the token endpoint recorded at authorization
+/// time. The client and resource details carry over; servers that don't rotate refresh
+/// tokens keep the old one.
+pub async fn refresh_token(token: &StoredToken) -> Result<StoredToken, TokenError> {
+    let (Some(refresh_token), Some(token_endpoint), Some(client_id)) = (
+        token.refresh_token.as_deref(),
+        token.token_endpoint.as_deref(),
+        token.client_id.as_deref(),
+    ) else {
+        return Err(TokenError {
+            message: "The stored token cannot be refreshed".to_string(),
+            rejected: true,
+        });
+    };
+
+    let mut form = vec![
+        ("grant_type", "refresh_token".to_string()),
+        ("refresh_token", refresh_token.to_string()),
+    ];
+    if let Some(resource) = &token.resource {
+        form.push(("resource", resource.clone()));
+    }
+    let response = token_request(
+        token_endpoint,
+        client_id,
+        token.client_secret.as_deref(),
+        token.token_endpoint_auth_method.as_deref(),
+        form,
+    )
+    .await?;
+
+    let mut refreshed = stored_token(response);
+    if refreshed.refresh_token.is_none() {
+        refreshed.refresh_token = token.refresh_token.clone();
+    }
+    if refreshed.scope.is_none() {
+        refreshed.scope = token.scope.clone();
+    }
+    refreshed.token_endpoint = token.token_endpoint.clone();
+    refreshed.client_id = token.client_id.clone();
+    refreshed.client_secret = token.client_secret.clone();
+    refreshed.token_endpoint_auth_method = token.token_endpoint_auth_method.clone();
+    refreshed.resource = token.resource.clone();
+    Ok(refreshed)
+}
+
+/// Redirect target for the browser, listening on a random port of 127.0.0.1
+pub struct LoopbackListener {
+    listener: TcpListener,
+    pub redirect_uri: String,
+}
+
+const CALLBACK_PATH: &str = "/callback";
+
+const CALLBACK_PAGE: &str = "<!doctype html><html><head><meta charset=\"utf-8\"><title>Vinsly</title></head>\
+<body style=\"font-family: sans-serif; text-align: center; margin-top: 4em\"><h2>{title}</h2><p>{detail}</p></body></html>";
+
+impl LoopbackListener {
+    pub async fn bind() -> Result<Self, String> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("Failed to start OAuth callback listener: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to start OAuth callback listener: {}", e))?
+            .port();
+        Ok(Self {
+            listener,
+            redirect_uri: format!("http://127.0.0.1:{}{}", port, CALLBACK_PATH),
+        })
+    }
+
+    /// Wait for the browser to hit the callback and return the authorization code.
+    /// Other paths (favicon, probes) are answered with 404 and ignored.
+    pub async fn wait_for_code(self, state: &str) -> Result<String, String> {
+        loop {
+            let (mut stream, _) = self
+                .listener
+                .accept()
+                .await
+                .map_err(|e| format!("OAuth callback listener failed: {}", e))?;
+            let Some(target) = read_request_target(&mut stream).await else {
+                continue;
+            };
+            let Ok(url) = Url::parse(&format!("http://127.0.0.1{}", target)) else {
+                respond(&mut stream, "400 Bad Request", "Bad request", "").await;
+                continue;
+            };
+            if url.path() != CALLBACK_PATH {
+                respond(&mut stream, "404 Not Found", "Not found", "").await;
+                continue;
+            }
+
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+            };
+            let result = if let Some(error) = param("error") {
+                Err(match param("error_description") {
+                    Some(description) => {
+                        format!("Authorization denied: {}: {}", error, description)
+                    }
+                    None => format!("Authorization denied: {}", error),
+                })
+            } else if param("state").as_deref() != Some(state) {
+                Err("Authorization response has an unexpected state; try again".to_string())
+            } else {
+                param("code").ok_or_else(|| "Authorization response has no code".to_string())
+            };
+
+            match &result {
+                Ok(_) => {
+                    respond(
+                        &mut stream,
+                        "200 OK",
+                        "Authorization complete",
+                        "You can close this window and return to Vinsly.",
+                    )
+                    .await
+                }
+                Err(message) => {
+                    respond(
+                        &mut stream,
+                        "400 Bad Request",
+                        "Authorization failed",
+                        message,
+                    )
+                    .await
+                }
+            }
+            return result;
+        }
+    }
+}
+
+/// Request target of the first line of an HTTP request
+async fn read_request_target(stream: &mut TcpStream) -> Option<String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 2048];
+    while !buffer.windows(4).any(|w| w == b"\r\n\r\n") && buffer.len() < 64 * 1024 {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+    let head = String::from_utf8_lossy(&buffer);
+    let mut request_line = head.lines().next()?.split_whitespace();
+    let _method = request_line.next()?;
+    request_line.next().map(|t| t.to_string())
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, title: &str, detail: &str) {
+    let body = CALLBACK_PAGE
+        .replace("{title}", &html_escape(title))
+        .replace("{detail}", &html_escape(detail));
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Run the whole authorization code flow for the MCP server at `server_url`: discovery,
+/// client registration, the browser round trip through `open_browser` and the PKCE code
+/// exchange. `scope` overrides the scope the server asks for. `timeout` bounds the wait
+/// for the user to finish in the browser.
+pub async fn authorize(
+    server_url: &str,
+    scope: Option<String>,
+    open_browser: impl FnOnce(&str) -> Result<(), String>,
+    timeout: Duration,
+) -> Result<StoredToken, String> {
+    let mut discovery = discover(server_url).await?;
+    if let Some(scope) = scope.filter(|s| !s.trim().is_empty()) {
+        discovery.scope = Some(scope);
+    }
+    if let Some(methods) = &discovery.metadata.code_challenge_methods_supported {
+        if !methods.iter().any(|m| m == "S256") {
+            return Err("The authorization server does not support PKCE with S256".to_string());
+        }
+    }
+
+    let listener = LoopbackListener::bind().await?;
+    let client = register_client(&discovery.metadata, &listener.redirect_uri).await?;
+    let redirect_uri = listener.redirect_uri.clone();
+    let pkce = Pkce::new();
+    let state = uuid::Uuid::new_v4().simple().to_string();
+
+    open_browser(&authorization_url(
+        &discovery,
+        &client,
+        &redirect_uri,
+        &pkce,
+        &state,
+    )?)?;
+    let code = tokio::time::timeout(timeout, listener.wait_for_code(&state))
+        .await
+        .map_err(|_| {
+            format!(
+                "Authorization was not completed within {} seconds",
+                timeout.as_secs()
+            )
+        })??;
+
+    exchange_code(&discovery, &client, &redirect_uri, &code, &pkce.verifier).await
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::mcp_client::tests::{read_request, respond as respond_with};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    fn query(target: &str) -> HashMap<String, String> {
+        Url::parse(&format!("http://localhost{}", target))
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect()
+    }
+
+    /// Stand-in MCP server and authorization server on one port. `/mcp` demands a token,
+    /// the authorization endpoint redirects straight back with a code, and the token
+    /// endpoint checks the PKCE verifier and renews `refresh-1`. Returns the base URL.
+    pub(crate) async fn mock_authorization_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        // code -> (code_challenge, redirect_uri)
+        let codes: Arc<Mutex<HashMap<String, (String, String)>>> = Arc::default();
+        let served = base.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let base = served.clone();
+                let codes = codes.clone();
+                tokio::spawn(async move {
+                    let Some(request) = read_request(&mut stream).await else {
+                        return;
+                    };
+                    let path = request.path.split('?').next().unwrap_or_default();
+                    let json_type = [("Content-Type", "application/json")];
+                    match (request.method.as_str(), path) {
+                        ("POST", "/mcp") => {
+                            let challenge = format!(
+                                "Bearer resource_metadata=\"{}/.well-known/oauth-protected-resource/mcp\", scope=\"mcp:tools\"",
+                                base
+                            );
+                            respond_with(
+                                &mut stream,
+                                "401 Unauthorized",
+                                &[("WWW-Authenticate", challenge.as_str())],
+                                "",
+                            )
+                            .await;
+                        }
+                        ("GET", "/.well-known/oauth-protected-resource/mcp") => {
+                            let body = json!({
+                                "resource": format!("{}/mcp", base),
+                                "authorization_servers": [format!("{}/auth", base)],
+                            });
+                            respond_with(&mut stream, "200 OK", &json_type, &body.to_string())
+                                .await;
+                        }
+                        ("GET", "/.well-known/oauth-authorization-server/auth") => {
+                            let body = json!({
+                                "issuer": format!("{}/auth", base),
+                                "authorization_endpoint": format!("{}/auth/authorize", base),
+                                "token_endpoint": format!("{}/auth/token", base),
+                                "registration_endpoint": format!("{}/auth/register", base),
+                                "code_challenge_methods_supported": ["S256"],
+                            });
+                            respond_with(&mut stream, "200 OK", &json_type, &body.to_string())
+                                .await;
+                        }
+                        ("POST", "/auth/register") => {
+                            let registration: Value =
+                                serde_json::from_slice(&request.body).unwrap_or_default();
+                            assert_eq!(registration["token_endpoint_auth_method"], "none");
+                            assert!(registration["redirect_uris"][0]
+                                .as_str()
+                                .unwrap()
+                                .starts_with("http://127.0.0.1:"));
+                            let body = json!({ "client_id": "client-1" });
+                            respond_with(&mut stream, "201 Created", &json_type, &body.to_string())
+                                .await;
+                        }
+                        ("GET", "/auth/authorize") => {
+                            let params = query(&request.path);
+                            assert_eq!(params["client_id"], "client-1");
+                            assert_eq!(params["code_challenge_method"], "S256");
+                            assert_eq!(params["scope"], "mcp:tools");
+                            assert_eq!(params["resource"], format!("{}/mcp", base));
+                            let code = uuid::Uuid::new_v4().simple().to_string();
+                            codes.lock().unwrap().insert(
+                                code.clone(),
+                                (
+                                    params["code_challenge"].clone(),
+                                    params["redirect_uri"].clone(),
+                                ),
+                            );
+                            let location = format!(
+                                "{}?code={}&state={}",
+                                params["redirect_uri"], code, params["state"]
+                            );
+                            respond_with(
+                                &mut stream,
+                                "302 Found",
+                                &[("Location", location.as_str())],
+                                "",
+                            )
+                            .await;
+                        }
+                        ("POST", "/auth/token") => {
+                            let form =
+                                query(&format!("/?{}", String::from_utf8_lossy(&request.body)));
+                            let field = |name: &str| form.get(name).cloned().unwrap_or_default();
+                            let body = match field("grant_type").as_str() {
+                                "authorization_code" => {
+                                    let issued = codes.lock().unwrap().remove(&field("code"));
+                                    let verified =
+                                        issued.is_some_and(|(challenge, redirect_uri)| {
+                                            URL_SAFE_NO_PAD.encode(Sha256::digest(
+                                                field("code_verifier").as_bytes(),
+                                            )) == challenge
+                                                && field("redirect_uri") == redirect_uri
+                                        });
+                                    verified.then(|| {
+                                        json!({
+                                            "access_token": "access-1",
+                                            "refresh_token": "refresh-1",
+                                            "token_type": "Bearer",
+                                            "expires_in": 3600,
+                                        })
+                                    })
+                                }
+                                // Doesn't rotate the refresh token
+                                "refresh_token" if field("refresh_token") == "refresh-1" => {
+                                    Some(json!({
+                                        "access_token": "access-2",
+                                        "token_type": "Bearer",
+                                        "expires_in": 3600,
+                                    }))
+                                }
+                                _ => None,
+                            };
+                            match body {
+                                Some(body) => {
+                                    respond_with(
+                                        &mut stream,
+                                        "200 OK",
+                                        &json_type,
+                                        &body.to_string(),
+                                    )
+                                    .await
+                                }
+                                None => {
+                                    let body = json!({
+                                        "error": "invalid_grant",
+                                        "error_description": "Grant is invalid or expired",
+                                    });
+                                    respond_with(
+                                        &mut stream,
+                                        "400 Bad Request",
+                                        &json_type,
+                                        &body.to_string(),
+                                    )
+                                    .await
+                                }
+                            }
+                        }
+                        _ => respond_with(&mut stream, "404 Not Found", &[], "").await,
+                    }
+                });
+            }
+        });
+        base
+    }
+
+    /// Stands in for the user's browser: follows the redirects back to the listener
+    fn browser(url: &str) -> Result<(), String> {
+        let url = url.to_string();
+        tokio::spawn(async move {
+            let _ = reqwest::get(url).await;
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn challenge_params_are_read_quoted_or_bare() {
+        let header = r#"Bearer realm="mcp", resource_metadata="https://x.test/.well-known/oauth-protected-resource", scope=read"#;
+        assert_eq!(
+            challenge_param(header, "resource_metadata").as_deref(),
+            Some("https://x.test/.well-known/oauth-protected-resource")
+        );
+        assert_eq!(challenge_param(header, "scope").as_deref(), Some("read"));
+        assert_eq!(challenge_param(header, "error"), None);
+    }
+
+    #[test]
+    fn pkce_challenge_is_the_s256_of_the_verifier() {
+        let pkce = Pkce::new();
+        assert_eq!(pkce.verifier.len(), 43);
+        assert_eq!(
+            pkce.challenge,
+            URL_SAFE_NO_PAD.encode(Sha256::digest(pkce.verifier.as_bytes()))
+        );
+        assert_ne!(pkce.verifier, Pkce::new().verifier);
+    }
+
+    #[tokio::test]
+    async fn discovers_the_authorization_server_from_the_challenge() {
+        let base = mock_authorization_server().await;
+        let discovery = discover(&format!("{}/mcp", base)).await.unwrap();
+        assert_eq!(discovery.resource, format!("{}/mcp", base));
+        assert_eq!(discovery.scope.as_deref(), Some("mcp:tools"));
+        assert_eq!(
+            discovery.metadata.token_endpoint,
+            format!("{}/auth/token", base)
+        );
+    }
+
+    #[tokio::test]
+    async fn completes_the_flow_end_to_end() {
+        let base = mock_authorization_server().await;
+        let token = authorize(
+            &format!("{}/mcp", base),
+            None,
+            browser,
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(token.access_token, "access-1");
+        assert_eq!(token.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(token.expires_at.unwrap() > now_secs());
+        assert_eq!(token.client_id.as_deref(), Some("client-1"));
+        assert_eq!(token.token_endpoint, Some(format!("{}/auth/token", base)));
+    }
+
+    #[tokio::test]
+    async fn a_wrong_verifier_is_rejected() {
+        let base = mock_authorization_server().await;
+        let discovery = discover(&format!("{}/mcp", base)).await.unwrap();
+        let listener = LoopbackListener::bind().await.unwrap();
+        let redirect_uri = listener.redirect_uri.clone();
+        let client = register_client(&discovery.metadata, &redirect_uri)
+            .await
+            .unwrap();
+        let pkce = Pkce::new();
+        let url = authorization_url(&discovery, &client, &redirect_uri, &pkce, "s1").unwrap();
+        browser(&url).unwrap();
+        let code = listener.wait_for_code("s1").await.unwrap();
+
+        let error = exchange_code(
+            &discovery,
+            &client,
+            &redirect_uri,
+            &code,
+            &Pkce::new().verifier,
+        )
+        .await
+        .unwrap_err();
+        assert!(error.contains("invalid_grant"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn callback_with_a_foreign_state_or_an_error_fails() {
+        let listener = LoopbackListener::bind().await.unwrap();
+        browser(&format!("{}?code=x&state=other", listener.redirect_uri)).unwrap();
+        let error = listener.wait_for_code("mine").await.unwrap_err();
+        assert!(error.contains("state"), "{}", error);
+
+        let listener = LoopbackListener::bind().await.unwrap();
+        browser(&format!(
+            "{}?error=access_denied&state=mine",
+            listener.redirect_uri
+        ))
+        .unwrap();
+        let error = listener.wait_for_code("mine").await.unwrap_err();
+        assert!(error.contains("access_denied"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_the_client_details_and_an_unrotated_refresh_token() {
+        let base = mock_authorization_server().await;
+        let token = StoredToken {
+            access_token: "access-1".to_string(),
+            refresh_token: Some("refresh-1".to_string()),
+            expires_at: Some(now_secs() - 10),
+            token_endpoint: Some(format!("{}/auth/token", base)),
+            client_id: Some("client-1".to_string()),
+            resource: Some(format!("{}/mcp", base)),
+            ..Default::default()
+        };
+        assert!(expires_within(&token, now_secs(), 0));
+
+        let refreshed = refresh_token(&token).await.unwrap();
+        assert_eq!(refreshed.access_token, "access-2");
+        assert_eq!(refreshed.refresh_token.as_deref(), Some("refresh-1"));
+        assert_eq!(refreshed.client_id.as_deref(), Some("client-1"));
+        assert!(!expires_within(&refreshed, now_secs(), 300));
+
+        let revoked = StoredToken {
+            refresh_token: Some("revoked".to_string()),
+            ..token
+        };
+        let error = refresh_token(&revoked).await.unwrap_err();
+        assert!(error.rejected, "{}", error.message);
+        assert!(error.message.contains("invalid_grant"), "{}", error.message);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::keychain_index::read_name_list;
+use crate::oauth::{can_refresh, expires_within, now_secs, refresh_token, StoredToken};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Keychain service holding one JSON token entry per server name
+pub const KEYRING_SERVICE: &str = "vinsly-mcp-oauth";
+
+pub const TOKEN_REFRESHED_EVENT: &str = "mcp-oauth:refreshed";
+pub const RELOGIN_REQUIRED_EVENT: &str = "mcp-oauth:relogin-required";
+
+/// Tokens are renewed once they are this close to expiring
+const REFRESH_MARGIN_SECS: i64 = 300;
+/// How often the background refresher looks at the stored tokens
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Payload for `mcp-oauth:refreshed` and `mcp-oauth:relogin-required`
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenEvent {
+    pub server_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Where tokens are kept. The keychain in the app, memory in tests.
+pub trait TokenStore: Sync {
+    fn load(&self, server_name: &str) -> Option<StoredToken>;
+    fn save(&self, server_name: &str, token: &StoredToken) -> Result<(), String>;
+}
+
+pub struct KeyringTokenStore;
+
+impl TokenStore for KeyringTokenStore {
+    fn load(&self, server_name: &str) -> Option<StoredToken> {
+        let entry = keyring::Entry::new(KEYRING_SERVICE, server_name).ok()?;
+        let token_json = entry.get_password().ok()?;
+        serde_json::from_str(&token_json).ok()
+    }
+
+    fn save(&self, server_name: &str, token: &StoredToken) -> Result<(), String> {
+        let token_json = serde_json::to_string(token)
+            .map_err(|e| format!("Failed to serialize token: {}", e))?;
+        keyring::Entry::new(KEYRING_SERVICE, server_name)
+            .map_err(|e| format!("Failed to access keyring: {}", e))?
+            .set_password(&token_json)
+            .map_err(|e| format!("Failed to store token: {}", e))
+    }
+}
+
+/// Serializes refreshes of one server: rotating refresh tokens only survive one use.
+/// Other servers keep refreshing while one waits on its token endpoint.
+fn server_lock(server_name: &str) -> Arc<Mutex<()>> {
+    static LOCKS: OnceLock<std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>> = OnceLock::new();
+    LOCKS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(server_name.to_string())
+        .or_default()
+        .clone()
+}
+
+/// Record whether `server_name` has been reported as needing a new sign-in, so the
+/// refresher reports each once. Returns true when that changed.
+fn set_relogin_reported(server_name: &str, reported: bool) -> bool {
+    static REPORTED: OnceLock<std::sync::Mutex<HashSet<String>>> = OnceLock::new();
+    let mut names = REPORTED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if reported {
+        names.insert(server_name.to_string())
+    } else {
+        names.remove(server_name)
+    }
+}
+
+/// Access token to use for `server_name`, refreshed first if it is about to expire.
+/// Returns the event to report, if the refresh succeeded or the user has to sign in again.
+/// Transient failures (server unreachable) keep the current token and are retried later.
+pub async fn fresh_access_token(
+    store: &dyn TokenStore,
+    server_name: &str,
+) -> (Option<String>, Option<(&'static str, TokenEvent)>) {
+    let lock = server_lock(server_name);
+    let _guard = lock.lock().await;
+    let Some(token) = store.load(server_name) else {
+        return (None, None);
+    };
+    let now = now_secs();
+    if !expires_within(&token, now, REFRESH_MARGIN_SECS) {
+        set_relogin_reported(server_name, false);
+        return (Some(token.access_token), None);
+    }
+
+    let relogin = |message: String| {
+        set_relogin_reported(server_name, true).then(|| {
+            (
+                RELOGIN_REQUIRED_EVENT,
+                TokenEvent {
+                    server_name: server_name.to_string(),
+                    expires_at: token.expires_at,
+                    message: Some(message),
+                },
+            )
+        })
+    };
+
+    if !can_refresh(&token) {
+        // Nothing to do until it actually expires
+        let event = if expires_within(&token, now, 0) {
+            relogin("The access token expired and cannot be refreshed; sign in again".to_string())
+        } else {
+            None
+        };
+        return (Some(token.access_token.clone()), event);
+    }
+
+    match refresh_token(&token).await {
+        Ok(refreshed) => {
+            if let Err(e) = store.save(server_name, &refreshed) {
+                eprintln!("Failed to save refreshed token for {}: {}", server_name, e);
+            }
+            set_relogin_reported(server_name, false);
+            let event = TokenEvent {
+                server_name: server_name.to_string(),
+                expires_at: refreshed.expires_at,
+                message: None,
+            };
+            (
+                Some(refreshed.access_token),
+                Some((TOKEN_REFRESHED_EVENT, event)),
+            )
+        }
+        Err(e) if e.rejected => {
+            let event = relogin(e.message);
+            (Some(token.access_token.clone()), event)
+        }
+        Err(e) => {
+            eprintln!("Failed to refresh token for {}: {}", server_name, e.message);
+            (Some(token.access_token.clone()), None)
+        }
+    }
+}
+
+/// Renew every recorded token shortly before it expires, forever. `emit` receives the
+/// event name and payload of each refresh and each failure that needs a new sign-in.
+pub async fn run_refresher(
+    store: &dyn TokenStore,
+    index_path: &Path,
+    emit: impl Fn(&'static str, TokenEvent),
+) {
+    loop {
+        for server_name in read_name_list(index_path) {
+            if let (_, Some((event, payload))) = fresh_access_token(store, &server_name).await {
+                emit(event, payload);
+            }
+        }
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oauth::tests::mock_authorization_server;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MemoryStore(std::sync::Mutex<HashMap<String, StoredToken>>);
+
+    impl TokenStore for MemoryStore {
+        fn load(&self, server_name: &str) -> Option<StoredToken> {
+            self.0.lock().unwrap().get(server_name).cloned()
+        }
+
+        fn save(&self, server_name: &str, token: &StoredToken) -> Result<(), String> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(server_name.to_string(), token.clone());
+            Ok(())
+        }
+    }
+
+    fn expiring_token(base: &str, refresh_token: &str) -> StoredToken {
+        StoredToken {
+            access_token: "access-1".to_string(),
+            refresh_token: Some(refresh_token.to_string()),
+            expires_at: Some(now_secs() + 60),
+            token_endpoint: Some(format!("{}/auth/token", base)),
+            client_id: Some("client-1".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn renews_a_token_close_to_expiry_and_stores_it() {
+        let base = mock_authorization_server().await;
+        let store = MemoryStore::default();
+        store
+            .save("refresh-ok", &expiring_token(&base, "refresh-1"))
+            .unwrap();
+
+        let (token, event) = fresh_access_token(&store, "refresh-ok").await;
+        assert_eq!(token.as_deref(), Some("access-2"));
+        let (name, payload) = event.unwrap();
+        assert_eq!(name, TOKEN_REFRESHED_EVENT);
+        assert_eq!(payload.server_name, "refresh-ok");
+        assert_eq!(store.load("refresh-ok").unwrap().access_token, "access-2");
+
+        // Fresh now: no further refresh or event
+        let (token, event) = fresh_access_token(&store, "refresh-ok").await;
+        assert_eq!(token.as_deref(), Some("access-2"));
+        assert!(event.is_none());
+    }
+
+    #[tokio::test]
+    async fn a_rejected_refresh_asks_for_a_new_sign_in_once() {
+        let base = mock_authorization_server().await;
+        let store = MemoryStore::default();
+        store
+            .save("refresh-revoked", &expiring_token(&base, "revoked"))
+            .unwrap();
+
+        let (token, event) = fresh_access_token(&store, "refresh-revoked").await;
+        assert_eq!(token.as_deref(), Some("access-1"));
+        let (name, payload) = event.unwrap();
+        assert_eq!(name, RELOGIN_REQUIRED_EVENT);
+        assert!(payload.message.unwrap().contains("invalid_grant"));
+
+        let (_, event) = fresh_access_token(&store, "refresh-revoked").await;
+        assert!(event.is_none());
+    }
+
+    #[tokio::test]
+    async fn a_hanging_token_endpoint_does_not_hold_up_other_servers() {
+        // Accepts connections and never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hanging = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                held.push(stream);
+            }
+        });
+        let base = mock_authorization_server().await;
+        let store = Arc::new(MemoryStore::default());
+        store
+            .save("refresh-hanging", &expiring_token(&hanging, "refresh-1"))
+            .unwrap();
+        store
+            .save("refresh-other", &expiring_token(&base, "refresh-1"))
+            .unwrap();
+
+        let stuck = Arc::clone(&store);
+        tokio::spawn(async move { fresh_access_token(&*stuck, "refresh-hanging").await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (token, _) = tokio::time::timeout(
+            Duration::from_secs(5),
+            fresh_access_token(&*store, "refresh-other"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(token.as_deref(), Some("access-2"));
+    }
+}
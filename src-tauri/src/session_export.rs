@@ -0,0 +1,581 @@
+use crate::text_diff::{line_diff, DiffKind, DiffLine};
+use crate::token_usage::{bucket_messages, build_report, PriceTable, SessionBuckets, UsageTotals};
+use crate::transcript::{ContentBlock, Role, Transcript, TranscriptMessage};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Tool results longer than this are cut in Markdown and HTML; JSON keeps them whole
+const MAX_RESULT_CHARS: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+/// Tokens and estimated cost of a session, per model
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenSummary {
+    pub totals: UsageTotals,
+    pub by_model: IndexMap<String, UsageTotals>,
+    pub unpriced_models: Vec<String>,
+}
+
+pub fn token_summary(transcript: &Transcript, prices: &PriceTable) -> TokenSummary {
+    let session = SessionBuckets {
+        session_id: String::new(),
+        project_dir: String::new(),
+        cwd: None,
+        buckets: bucket_messages(&transcript.messages),
+    };
+    let report = build_report(&[session], prices, None, None);
+    TokenSummary {
+        totals: report.totals,
+        by_model: report.by_model,
+        unpriced_models: report.unpriced_models,
+    }
+}
+
+/// A session being exported
+pub struct SessionExport<'a> {
+    pub session_id: &'a str,
+    pub transcript: &'a Transcript,
+    pub tokens: &'a TokenSummary,
+    /// RFC 3339
+    pub exported_at: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    format_version: u32,
+    exported_at: &'a str,
+    session_id: &'a str,
+    cwd: Option<&'a str>,
+    git_branch: Option<&'a str>,
+    claude_code_version: Option<&'a str>,
+    summaries: &'a [String],
+    token_usage: &'a TokenSummary,
+    messages: &'a [TranscriptMessage],
+}
+
+impl SessionExport<'_> {
+    pub fn render(&self, format: ExportFormat) -> Result<String, String> {
+        match format {
+            ExportFormat::Markdown => Ok(self.markdown()),
+            ExportFormat::Html => Ok(self.html()),
+            ExportFormat::Json => self.json(),
+        }
+    }
+
+    /// On one line, so a multi-line prompt can't break the heading
+    fn title(&self) -> String {
+        let title = self
+            .transcript
+            .summaries
+            .last()
+            .cloned()
+            .or_else(|| self.transcript.first_prompt())
+            .unwrap_or_else(|| format!("Claude Code session {}", self.session_id));
+        title.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Messages worth showing: injected ones are left out, and so are user messages
+    /// that only carry results already shown under their tool calls
+    fn shown_messages(&self) -> impl Iterator<Item = &TranscriptMessage> {
+        self.transcript.messages.iter().filter(|message| {
+            !message.is_meta
+                && message.content.iter().any(|block| {
+                    !matches!(
+                        block,
+                        ContentBlock::ToolResult {
+                            tool_name: Some(_),
+                            ..
+                        }
+                    )
+                })
+        })
+    }
+
+    /// Result of the call `id`, from the message at `result_index`
+    fn tool_result(&self, id: &str, result_index: Option<usize>) -> Option<(&str, bool)> {
+        let message = self.transcript.messages.get(result_index?)?;
+        message.content.iter().find_map(|block| match block {
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+                ..
+            } if tool_use_id == id => Some((content.as_str(), *is_error)),
+            _ => None,
+        })
+    }
+
+    fn heading(message: &TranscriptMessage) -> String {
+        let mut parts = vec![match message.role {
+            Role::User => "User".to_string(),
+            Role::Assistant => "Assistant".to_string(),
+            Role::System => "System".to_string(),
+        }];
+        if message.is_sidechain {
+            parts.push(match &message.agent_id {
+                Some(agent_id) => format!("subagent {}", agent_id),
+                None => "subagent".to_string(),
+            });
+        }
+        parts.extend(message.model.clone());
+        parts.extend(message.timestamp.clone());
+        parts.join(" · ")
+    }
+
+    fn metadata(&self) -> Vec<(&'static str, String)> {
+        let transcript = self.transcript;
+        let timestamps = || {
+            transcript
+                .messages
+                .iter()
+                .filter_map(|message| message.timestamp.as_deref())
+        };
+        let mut rows = vec![("Session", self.session_id.to_string())];
+        rows.extend(transcript.cwd.clone().map(|cwd| ("Project", cwd)));
+        rows.extend(
+            transcript
+                .git_branch
+                .clone()
+                .map(|branch| ("Branch", branch)),
+        );
+        rows.extend(timestamps().min().map(|at| ("Started", at.to_string())));
+        rows.extend(
+            timestamps()
+                .max()
+                .map(|at| ("Last activity", at.to_string())),
+        );
+        rows.push(("Messages", transcript.messages.len().to_string()));
+        rows.push(("Exported", self.exported_at.to_string()));
+        rows
+    }
+
+    fn token_rows(&self) -> Vec<[String; 6]> {
+        let row = |name: String, totals: &UsageTotals| {
+            [
+                name,
+                totals.usage.input_tokens.to_string(),
+                totals.usage.output_tokens.to_string(),
+                totals.usage.cache_creation_input_tokens.to_string(),
+                totals.usage.cache_read_input_tokens.to_string(),
+                format!("{:.4}", totals.cost_usd),
+            ]
+        };
+        let mut rows: Vec<[String; 6]> = self
+            .tokens
+            .by_model
+            .iter()
+            .map(|(model, totals)| row(model.clone(), totals))
+            .collect();
+        rows.push(row("Total".to_string(), &self.tokens.totals));
+        rows
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.title());
+        for (label, value) in self.metadata() {
+            out.push_str(&format!("- **{}:** {}\n", label, value));
+        }
+
+        out.push_str("\n## Token usage\n\n");
+        out.push_str("| Model | Input | Output | Cache write | Cache read | Cost (USD) |\n");
+        out.push_str("|---|---:|---:|---:|---:|---:|\n");
+        for row in self.token_rows() {
+            out.push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+        if !self.tokens.unpriced_models.is_empty() {
+            out.push_str(&format!(
+                "\nNo price for: {}\n",
+                self.tokens.unpriced_models.join(", ")
+            ));
+        }
+
+        out.push_str("\n## Conversation\n");
+        for message in self.shown_messages() {
+            out.push_str(&format!("\n### {}\n\n", Self::heading(message)));
+            for block in &message.content {
+                match block {
+                    ContentBlock::Text { text } => out.push_str(&format!("{}\n\n", text.trim())),
+                    ContentBlock::Thinking { thinking } => out.push_str(&format!(
+                        "<details>\n<summary>Thinking</summary>\n\n{}\n\n</details>\n\n",
+                        thinking.trim()
+                    )),
+                    ContentBlock::ToolUse {
+                        id,
+                        name,
+                        input,
+                        result_index,
+                    } => {
+                        out.push_str(&format!(
+                            "<details>\n<summary>{}</summary>\n\n",
+                            escape_html(&tool_label(name, input))
+                        ));
+                        match tool_diffs(name, input) {
+                            Some(diffs) => {
+                                for diff in diffs {
+                                    out.push_str(&fence(&diff_text(&diff), "diff"));
+                                }
+                            }
+                            None => out.push_str(&fence(&pretty_json(input), "json")),
+                        }
+                        if let Some((content, is_error)) = self.tool_result(id, *result_index) {
+                            out.push_str(if is_error {
+                                "**Error**\n\n"
+                            } else {
+                                "**Result**\n\n"
+                            });
+                            out.push_str(&fence(&truncate(content), ""));
+                        }
+                        out.push_str("</details>\n\n");
+                    }
+                    ContentBlock::ToolResult { content, .. } => {
+                        out.push_str("**Tool result**\n\n");
+                        out.push_str(&fence(&truncate(content), ""));
+                    }
+                    ContentBlock::Image { .. } => out.push_str("*[image]*\n\n"),
+                    ContentBlock::Other { .. } => {}
+                }
+            }
+        }
+        out
+    }
+
+    fn html(&self) -> String {
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n<dl>\n",
+            escape_html(&self.title()),
+            HTML_STYLE,
+            escape_html(&self.title())
+        );
+        for (label, value) in self.metadata() {
+            out.push_str(&format!(
+                "<dt>{}</dt><dd>{}</dd>\n",
+                label,
+                escape_html(&value)
+            ));
+        }
+        out.push_str("</dl>\n<h2>Token usage</h2>\n<table>\n<tr><th>Model</th><th>Input</th><th>Output</th><th>Cache write</th><th>Cache read</th><th>Cost (USD)</th></tr>\n");
+        for row in self.token_rows() {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| format!("<td>{}</td>", escape_html(cell)))
+                .collect();
+            out.push_str(&format!("<tr>{}</tr>\n", cells.concat()));
+        }
+        out.push_str("</table>\n");
+        if !self.tokens.unpriced_models.is_empty() {
+            out.push_str(&format!(
+                "<p>No price for: {}</p>\n",
+                escape_html(&self.tokens.unpriced_models.join(", "))
+            ));
+        }
+
+        out.push_str("<h2>Conversation</h2>\n");
+        for message in self.shown_messages() {
+            let class = match message.role {
+                Role::User => "user",
+                Role::Assistant => "assistant",
+                Role::System => "system",
+            };
+            out.push_str(&format!(
+                "<section class=\"message {}{}\">\n<h3>{}</h3>\n",
+                class,
+                if message.is_sidechain {
+                    " sidechain"
+                } else {
+                    ""
+                },
+                escape_html(&Self::heading(message))
+            ));
+            for block in &message.content {
+                match block {
+                    ContentBlock::Text { text } => out.push_str(&format!(
+                        "<div class=\"text\">{}</div>\n",
+                        escape_html(text.trim())
+                    )),
+                    ContentBlock::Thinking { thinking } => out.push_str(&format!(
+                        "<details><summary>Thinking</summary><div class=\"text\">{}</div></details>\n",
+                        escape_html(thinking.trim())
+                    )),
+                    ContentBlock::ToolUse {
+                        id,
+                        name,
+                        input,
+                        result_index,
+                    } => {
+                        out.push_str(&format!(
+                            "<details class=\"tool\"><summary>{}</summary>\n",
+                            escape_html(&tool_label(name, input))
+                        ));
+                        match tool_diffs(name, input) {
+                            Some(diffs) => {
+                                for diff in diffs {
+                                    out.push_str(&diff_html(&diff));
+                                }
+                            }
+                            None => out.push_str(&format!(
+                                "<pre>{}</pre>\n",
+                                escape_html(&pretty_json(input))
+                            )),
+                        }
+                        if let Some((content, is_error)) = self.tool_result(id, *result_index) {
+                            out.push_str(&format!(
+                                "<h4>{}</h4><pre{}>{}</pre>\n",
+                                if is_error { "Error" } else { "Result" },
+                                if is_error { " class=\"error\"" } else { "" },
+                                escape_html(&truncate(content))
+                            ));
+                        }
+                        out.push_str("</details>\n");
+                    }
+                    ContentBlock::ToolResult { content, .. } => out.push_str(&format!(
+                        "<h4>Tool result</h4><pre>{}</pre>\n",
+                        escape_html(&truncate(content))
+                    )),
+                    ContentBlock::Image { .. } => out.push_str("<p><em>[image]</em></p>\n"),
+                    ContentBlock::Other { .. } => {}
+                }
+            }
+            out.push_str("</section>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    fn json(&self) -> Result<String, String> {
+        let transcript = self.transcript;
+        let export = JsonExport {
+            format_version: 1,
+            exported_at: self.exported_at,
+            session_id: self.session_id,
+            cwd: transcript.cwd.as_deref(),
+            git_branch: transcript.git_branch.as_deref(),
+            claude_code_version: transcript.version.as_deref(),
+            summaries: &transcript.summaries,
+            token_usage: self.tokens,
+            messages: &transcript.messages,
+        };
+        serde_json::to_string_pretty(&export)
+            .map_err(|e| format!("Failed to serialize session: {}", e))
+    }
+}
+
+const HTML_STYLE: &str = "body{font:14px/1.5 -apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:960px;margin:2em auto;padding:0 1em;color:#1f2229}\
+dl{display:grid;grid-template-columns:max-content auto;gap:.2em 1em}dt{font-weight:600}dd{margin:0}\
+table{border-collapse:collapse}th,td{border:1px solid #ddd;padding:.3em .6em;text-align:right}th:first-child,td:first-child{text-align:left}\
+.message{border-left:3px solid #ccc;padding:.2em 1em;margin:1.2em 0}.user{border-color:#3b82f6}.assistant{border-color:#d97706}.sidechain{margin-left:2em}\
+h3{font-size:.9em;color:#666;margin:.4em 0}.text{white-space:pre-wrap}\
+pre{background:#f6f6f4;padding:.6em;overflow-x:auto;white-space:pre-wrap}pre.error{background:#fdecec}\
+summary{cursor:pointer;font-family:ui-monospace,monospace}\
+.diff span{display:block}.diff .added{background:#e6ffec}.diff .removed{background:#ffebe9}";
+
+/// One line describing a tool call: its name and what it worked on
+pub fn tool_label(name: &str, input: &Value) -> String {
+    let subject = [
+        "file_path",
+        "notebook_path",
+        "command",
+        "pattern",
+        "url",
+        "path",
+        "description",
+    ]
+    .iter()
+    .find_map(|key| input.get(*key).and_then(Value::as_str));
+    match subject {
+        Some(subject) => {
+            let subject = subject.lines().next().unwrap_or_default();
+            format!("{}: {}", name, subject)
+        }
+        None => name.to_string(),
+    }
+}
+
+/// Diffs an Edit, MultiEdit or Write call would make to its file. None for other tools.
+pub fn tool_diffs(name: &str, input: &Value) -> Option<Vec<Vec<DiffLine>>> {
+    let text = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    match name {
+        "Edit" => Some(vec![line_diff(
+            &text(input, "old_string"),
+            &text(input, "new_string"),
+        )]),
+        "MultiEdit" => Some(
+            input
+                .get("edits")
+                .and_then(Value::as_array)
+                .map(|edits| {
+                    edits
+                        .iter()
+                        .map(|edit| line_diff(&text(edit, "old_string"), &text(edit, "new_string")))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+        "Write" => Some(vec![line_diff("", &text(input, "content"))]),
+        _ => None,
+    }
+}
+
+fn diff_text(diff: &[DiffLine]) -> String {
+    diff.iter()
+        .map(|line| {
+            let sign = match line.kind {
+                DiffKind::Context => ' ',
+                DiffKind::Added => '+',
+                DiffKind::Removed => '-',
+            };
+            format!("{}{}", sign, line.text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn diff_html(diff: &[DiffLine]) -> String {
+    let lines: String = diff
+        .iter()
+        .map(|line| {
+            let (class, sign) = match line.kind {
+                DiffKind::Context => ("context", ' '),
+                DiffKind::Added => ("added", '+'),
+                DiffKind::Removed => ("removed", '-'),
+            };
+            format!(
+                "<span class=\"{}\">{}{}</span>",
+                class,
+                sign,
+                escape_html(&line.text)
+            )
+        })
+        .collect();
+    format!("<pre class=\"diff\">{}</pre>\n", lines)
+}
+
+fn pretty_json(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+fn truncate(text: &str) -> String {
+    let text = text.trim_end();
+    match text.char_indices().nth(MAX_RESULT_CHARS) {
+        Some((end, _)) => format!(
+            "{}\n… ({} more characters)",
+            &text[..end],
+            text[end..].chars().count()
+        ),
+        None => text.to_string(),
+    }
+}
+
+/// A Markdown code block whose fence is longer than any backtick run in `content`
+fn fence(content: &str, lang: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}\n\n", fence, lang, content, fence)
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_usage::default_prices;
+    use crate::transcript::parse_entry;
+
+    fn fixture() -> Transcript {
+        let mut transcript = Transcript::default();
+        for line in include_str!("../tests/fixtures/session.jsonl").lines() {
+            if let Ok(entry) = parse_entry(line) {
+                transcript.push(entry);
+            }
+        }
+        transcript
+    }
+
+    #[test]
+    fn renders_tool_calls_with_diffs_and_results() {
+        let transcript = fixture();
+        let tokens = token_summary(&transcript, &default_prices());
+        let export = SessionExport {
+            session_id: "5f0c",
+            transcript: &transcript,
+            tokens: &tokens,
+            exported_at: "2025-06-02T00:00:00Z",
+        };
+
+        let markdown = export.render(ExportFormat::Markdown).unwrap();
+        assert!(markdown.starts_with("# Why does migration 0042 run before 0041?\n"));
+        assert!(markdown.contains("<summary>Edit: /work/app/db/migrate.rs</summary>"));
+        assert!(
+            markdown.contains("```diff\n-names.sort();\n+names.sort_by_key(|n| version(n));\n```")
+        );
+        assert!(markdown.contains(
+            "**Result**\n\n```\nThe file /work/app/db/migrate.rs has been updated.\n```"
+        ));
+        assert!(markdown.contains("| Total | 57 | 63 | 900 | 1060 | 0.0046 |"));
+        assert!(markdown.contains("### Assistant · subagent · claude-3-5-haiku-20241022"));
+        // The tool result is shown under its call, not as a message of its own
+        assert_eq!(markdown.matches("### User").count(), 1);
+
+        let html = export.render(ExportFormat::Html).unwrap();
+        assert!(html.contains("<span class=\"added\">+names.sort_by_key(|n| version(n));</span>"));
+        assert!(html
+            .contains("<details class=\"tool\"><summary>Edit: /work/app/db/migrate.rs</summary>"));
+
+        let json: Value =
+            serde_json::from_str(&export.render(ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["messages"].as_array().unwrap().len(), 5);
+        assert_eq!(
+            json["token_usage"]["totals"]["cache_read_input_tokens"],
+            1060
+        );
+        assert_eq!(json["messages"][1]["content"][1]["result_index"], 2);
+    }
+
+    #[test]
+    fn titles_stay_on_the_heading_line() {
+        let mut transcript = Transcript::default();
+        transcript.push(
+            parse_entry(
+                r#"{"type":"user","uuid":"u1","message":{"role":"user","content":"Fix the build\n\n- it fails on CI"}}"#,
+            )
+            .unwrap(),
+        );
+        let tokens = token_summary(&transcript, &default_prices());
+        let export = SessionExport {
+            session_id: "5f0c",
+            transcript: &transcript,
+            tokens: &tokens,
+            exported_at: "2025-06-02T00:00:00Z",
+        };
+
+        let markdown = export.render(ExportFormat::Markdown).unwrap();
+        assert!(markdown.starts_with("# Fix the build - it fails on CI\n\n"));
+    }
+
+    #[test]
+    fn fences_outgrow_backticks_in_the_content() {
+        assert_eq!(fence("a ```` b", ""), "`````\na ```` b\n`````\n\n");
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+}
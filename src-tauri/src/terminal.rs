@@ -92,6 +92,7 @@ pub fn create_terminal(
     shell: Option<String>,
     cols: u16,
     rows: u16,
+    extra_env: HashMap<String, String>,
 ) -> Result<String, String> {
     let terminal_id = uuid::Uuid::new_v4().to_string();
 
@@ -156,6 +157,10 @@ pub fn create_terminal(
         }
     }
 
+    for (name, value) in extra_env {
+        cmd.env(name, value);
+    }
+
     // Spawn the shell process
     let child = pair
         .slave
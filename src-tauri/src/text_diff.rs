@@ -0,0 +1,91 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// One line of a line-based diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// Line diff of `old` against `new` (longest common subsequence), removals before
+/// additions within each changed block. Meant for config entries and tool inputs, not
+/// whole files: the table is `old lines × new lines`.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j]: length of the common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let line = |kind, text: &str| DiffLine {
+        kind,
+        text: text.to_string(),
+    };
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(line(DiffKind::Context, old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(line(DiffKind::Removed, old[i]));
+            i += 1;
+        } else {
+            diff.push(line(DiffKind::Added, new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|text| line(DiffKind::Removed, text)));
+    diff.extend(new[j..].iter().map(|text| line(DiffKind::Added, text)));
+    diff
+}
+
+/// True when the diff has no added or removed lines
+pub fn is_unchanged(diff: &[DiffLine]) -> bool {
+    diff.iter().all(|line| line.kind == DiffKind::Context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(diff: &[DiffLine]) -> Vec<String> {
+        diff.iter()
+            .map(|line| {
+                let sign = match line.kind {
+                    DiffKind::Context => ' ',
+                    DiffKind::Added => '+',
+                    DiffKind::Removed => '-',
+                };
+                format!("{}{}", sign, line.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn marks_changed_lines_between_common_ones() {
+        let diff = line_diff("a\nb\nc\nd", "a\nx\nc\nd\ne");
+        assert_eq!(render(&diff), vec![" a", "-b", "+x", " c", " d", "+e"]);
+        assert!(!is_unchanged(&diff));
+        assert!(is_unchanged(&line_diff("a\nb", "a\nb")));
+        assert_eq!(render(&line_diff("", "a")), vec!["+a"]);
+    }
+}
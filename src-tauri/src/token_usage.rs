@@ -0,0 +1,406 @@
+use crate::transcript::{Role, TranscriptMessage, Usage};
+use chrono::{DateTime, Local};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Model name for assistant messages that don't record one
+const UNKNOWN_MODEL: &str = "unknown";
+
+/// USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    /// Prompt cache writes, at the 5-minute cache rate
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_creation_input_tokens as f64 * self.cache_write
+            + usage.cache_read_input_tokens as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Prices keyed by model ID prefix, e.g. "claude-sonnet-4" for every Sonnet 4 snapshot
+pub type PriceTable = IndexMap<String, ModelPrice>;
+
+/// Anthropic's list prices, used until the user saves a table of their own
+pub fn default_prices() -> PriceTable {
+    let price = |input, output, cache_write, cache_read| ModelPrice {
+        input,
+        output,
+        cache_write,
+        cache_read,
+    };
+    [
+        ("claude-opus-4-5", price(5.0, 25.0, 6.25, 0.5)),
+        ("claude-opus-4", price(15.0, 75.0, 18.75, 1.5)),
+        ("claude-sonnet-4", price(3.0, 15.0, 3.75, 0.3)),
+        ("claude-haiku-4", price(1.0, 5.0, 1.25, 0.1)),
+        ("claude-3-opus", price(15.0, 75.0, 18.75, 1.5)),
+        ("claude-3-7-sonnet", price(3.0, 15.0, 3.75, 0.3)),
+        ("claude-3-5-sonnet", price(3.0, 15.0, 3.75, 0.3)),
+        ("claude-3-5-haiku", price(0.8, 4.0, 1.0, 0.08)),
+        ("claude-3-haiku", price(0.25, 1.25, 0.3, 0.03)),
+    ]
+    .into_iter()
+    .map(|(prefix, price)| (prefix.to_string(), price))
+    .collect()
+}
+
+/// Price of `model`: the entry with the longest matching prefix
+pub fn price_for<'a>(prices: &'a PriceTable, model: &str) -> Option<&'a ModelPrice> {
+    prices
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| price)
+}
+
+/// The table saved at `path`, or the defaults when none has been saved
+pub fn load_prices(path: &Path) -> Result<PriceTable, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse price table: {}", e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(default_prices()),
+        Err(e) => Err(format!("Failed to read price table: {}", e)),
+    }
+}
+
+/// Reject entries that would make every estimate wrong
+pub fn validate_prices(prices: &PriceTable) -> Result<(), String> {
+    for (prefix, price) in prices {
+        if prefix.trim().is_empty() {
+            return Err("Model prefixes can't be empty".to_string());
+        }
+        let rates = [
+            price.input,
+            price.output,
+            price.cache_write,
+            price.cache_read,
+        ];
+        if rates.iter().any(|rate| !rate.is_finite() || *rate < 0.0) {
+            return Err(format!("Prices for '{}' must be zero or more", prefix));
+        }
+    }
+    Ok(())
+}
+
+/// Usage of one model on one local day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageBucket {
+    pub model: String,
+    /// YYYY-MM-DD in the local time zone
+    pub day: String,
+    pub usage: Usage,
+    pub messages: u64,
+}
+
+/// Local calendar day of an RFC 3339 timestamp
+pub fn local_day(timestamp: &str) -> Option<String> {
+    let at = DateTime::parse_from_rfc3339(timestamp).ok()?;
+    Some(at.with_timezone(&Local).format("%Y-%m-%d").to_string())
+}
+
+/// The model and day an assistant message's usage counts towards. None for other
+/// messages, and ones without usage or a timestamp.
+pub fn usage_bucket(message: &TranscriptMessage) -> Option<UsageBucket> {
+    if message.role != Role::Assistant {
+        return None;
+    }
+    Some(UsageBucket {
+        model: message
+            .model
+            .as_deref()
+            .unwrap_or(UNKNOWN_MODEL)
+            .to_string(),
+        day: local_day(message.timestamp.as_deref()?)?,
+        usage: message.usage?,
+        messages: 1,
+    })
+}
+
+/// Add `bucket` to the one with the same model and day
+pub fn add_bucket(buckets: &mut Vec<UsageBucket>, bucket: UsageBucket) {
+    match buckets
+        .iter_mut()
+        .find(|existing| existing.model == bucket.model && existing.day == bucket.day)
+    {
+        Some(existing) => {
+            existing.usage += bucket.usage;
+            existing.messages += bucket.messages;
+        }
+        None => buckets.push(bucket),
+    }
+}
+
+/// Usage of a session's messages, sidechains included, per model and day
+pub fn bucket_messages(messages: &[TranscriptMessage]) -> Vec<UsageBucket> {
+    let mut buckets = Vec::new();
+    for bucket in messages.iter().filter_map(usage_bucket) {
+        add_bucket(&mut buckets, bucket);
+    }
+    buckets
+}
+
+/// Tokens, assistant messages and estimated cost. Tokens of models missing from the
+/// price table count towards the tokens but not the cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    #[serde(flatten)]
+    pub usage: Usage,
+    pub messages: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, bucket: &UsageBucket, cost: f64) {
+        self.usage += bucket.usage;
+        self.messages += bucket.messages;
+        self.cost_usd += cost;
+    }
+}
+
+/// Usage of one session file, as collected for a report
+#[derive(Debug, Clone)]
+pub struct SessionBuckets {
+    pub session_id: String,
+    /// Name of the directory under ~/.claude/projects
+    pub project_dir: String,
+    /// Working directory the session ran in
+    pub cwd: Option<String>,
+    pub buckets: Vec<UsageBucket>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionUsage {
+    pub session_id: String,
+    pub project_dir: String,
+    pub cwd: Option<String>,
+    pub first_day: String,
+    pub last_day: String,
+    pub totals: UsageTotals,
+    pub by_model: IndexMap<String, UsageTotals>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectUsage {
+    pub project_dir: String,
+    pub cwd: Option<String>,
+    pub sessions: usize,
+    pub totals: UsageTotals,
+    pub by_model: IndexMap<String, UsageTotals>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageReport {
+    pub totals: UsageTotals,
+    /// Most expensive first
+    pub by_model: IndexMap<String, UsageTotals>,
+    /// Oldest first
+    pub by_day: IndexMap<String, UsageTotals>,
+    /// Most expensive first
+    pub sessions: Vec<SessionUsage>,
+    /// Most expensive first
+    pub projects: Vec<ProjectUsage>,
+    /// Models with usage but no price
+    pub unpriced_models: Vec<String>,
+}
+
+fn by_cost(totals: &mut IndexMap<String, UsageTotals>) {
+    totals.sort_by(|_, a, _, b| b.cost_usd.total_cmp(&a.cost_usd));
+}
+
+/// Totals of `sessions` between the days `since` and `until` (YYYY-MM-DD, inclusive)
+pub fn build_report(
+    sessions: &[SessionBuckets],
+    prices: &PriceTable,
+    since: Option<&str>,
+    until: Option<&str>,
+) -> UsageReport {
+    let mut report = UsageReport::default();
+    let mut projects: IndexMap<String, ProjectUsage> = IndexMap::new();
+
+    for session in sessions {
+        let mut session_usage = SessionUsage {
+            session_id: session.session_id.clone(),
+            project_dir: session.project_dir.clone(),
+            cwd: session.cwd.clone(),
+            first_day: String::new(),
+            last_day: String::new(),
+            totals: UsageTotals::default(),
+            by_model: IndexMap::new(),
+        };
+        let in_range = |bucket: &&UsageBucket| {
+            since.is_none_or(|since| bucket.day.as_str() >= since)
+                && until.is_none_or(|until| bucket.day.as_str() <= until)
+        };
+        for bucket in session.buckets.iter().filter(in_range) {
+            let cost = match price_for(prices, &bucket.model) {
+                Some(price) => price.cost(&bucket.usage),
+                None => {
+                    if !report.unpriced_models.contains(&bucket.model) {
+                        report.unpriced_models.push(bucket.model.clone());
+                    }
+                    0.0
+                }
+            };
+            session_usage.totals.add(bucket, cost);
+            session_usage
+                .by_model
+                .entry(bucket.model.clone())
+                .or_default()
+                .add(bucket, cost);
+            report
+                .by_day
+                .entry(bucket.day.clone())
+                .or_default()
+                .add(bucket, cost);
+            if session_usage.first_day.is_empty() || bucket.day < session_usage.first_day {
+                session_usage.first_day = bucket.day.clone();
+            }
+            if bucket.day > session_usage.last_day {
+                session_usage.last_day = bucket.day.clone();
+            }
+        }
+        if session_usage.totals.messages == 0 {
+            continue;
+        }
+
+        let project = projects
+            .entry(session.project_dir.clone())
+            .or_insert_with(|| ProjectUsage {
+                project_dir: session.project_dir.clone(),
+                cwd: None,
+                sessions: 0,
+                totals: UsageTotals::default(),
+                by_model: IndexMap::new(),
+            });
+        project.cwd = project.cwd.take().or_else(|| session.cwd.clone());
+        project.sessions += 1;
+        for (model, totals) in &session_usage.by_model {
+            let project_model = project.by_model.entry(model.clone()).or_default();
+            let report_model = report.by_model.entry(model.clone()).or_default();
+            for target in [
+                &mut project.totals,
+                project_model,
+                &mut report.totals,
+                report_model,
+            ] {
+                target.usage += totals.usage;
+                target.messages += totals.messages;
+                target.cost_usd += totals.cost_usd;
+            }
+        }
+        by_cost(&mut session_usage.by_model);
+        report.sessions.push(session_usage);
+    }
+
+    report.projects = projects.into_values().collect();
+    for project in &mut report.projects {
+        by_cost(&mut project.by_model);
+    }
+    report
+        .projects
+        .sort_by(|a, b| b.totals.cost_usd.total_cmp(&a.totals.cost_usd));
+    report
+        .sessions
+        .sort_by(|a, b| b.totals.cost_usd.total_cmp(&a.totals.cost_usd));
+    by_cost(&mut report.by_model);
+    report.by_day.sort_keys();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::{parse_entry, Transcript};
+
+    fn fixture_buckets() -> Vec<UsageBucket> {
+        let mut transcript = Transcript::default();
+        for line in include_str!("../tests/fixtures/session.jsonl").lines() {
+            if let Ok(entry) = parse_entry(line) {
+                transcript.push(entry);
+            }
+        }
+        bucket_messages(&transcript.messages)
+    }
+
+    #[test]
+    fn reports_cache_tokens_and_costs_per_model() {
+        let sessions = vec![SessionBuckets {
+            session_id: "5f0c".to_string(),
+            project_dir: "-work-app".to_string(),
+            cwd: Some("/work/app".to_string()),
+            buckets: fixture_buckets(),
+        }];
+        let report = build_report(&sessions, &default_prices(), None, None);
+
+        let sonnet = report.by_model["claude-sonnet-4-20250514"];
+        // The streamed msg_1 counts once, with its final usage
+        assert_eq!(
+            sonnet.usage,
+            Usage {
+                input_tokens: 17,
+                output_tokens: 57,
+                cache_creation_input_tokens: 900,
+                cache_read_input_tokens: 960,
+            }
+        );
+        assert_eq!(sonnet.messages, 2);
+        assert!((sonnet.cost_usd - 0.004569).abs() < 1e-9);
+        let haiku = report.by_model["claude-3-5-haiku-20241022"];
+        assert!((haiku.cost_usd - 0.000064).abs() < 1e-9);
+        assert_eq!(
+            report.by_model.get_index(0).unwrap().0,
+            "claude-sonnet-4-20250514"
+        );
+        assert_eq!(report.projects[0].sessions, 1);
+        assert_eq!(report.projects[0].totals, report.totals);
+        assert!(report.unpriced_models.is_empty());
+
+        let mut prices = default_prices();
+        prices.shift_remove("claude-3-5-haiku");
+        let report = build_report(&sessions, &prices, None, None);
+        assert_eq!(report.unpriced_models, vec!["claude-3-5-haiku-20241022"]);
+        assert_eq!(report.totals.usage.input_tokens, 57);
+        assert!(build_report(&sessions, &prices, Some("2999-01-01"), None)
+            .sessions
+            .is_empty());
+    }
+
+    #[test]
+    fn the_longest_matching_prefix_sets_the_price() {
+        let prices = default_prices();
+        assert_eq!(
+            price_for(&prices, "claude-opus-4-5-20251101")
+                .unwrap()
+                .input,
+            5.0
+        );
+        assert_eq!(
+            price_for(&prices, "claude-opus-4-1-20250805")
+                .unwrap()
+                .input,
+            15.0
+        );
+        assert!(price_for(&prices, "<synthetic>").is_none());
+        let mut invalid = prices.clone();
+        invalid.insert(
+            "custom".to_string(),
+            ModelPrice {
+                input: -1.0,
+                ..prices["claude-3-haiku"]
+            },
+        );
+        assert!(validate_prices(&invalid).is_err());
+    }
+}
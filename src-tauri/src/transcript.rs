@@ -0,0 +1,765 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Longest first prompt kept in a session summary, in characters
+const PROMPT_PREVIEW_CHARS: usize = 200;
+
+/// `~/.claude/projects`, where Claude Code keeps one directory of transcripts per project
+pub fn projects_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".claude").join("projects"))
+}
+
+/// Claude Code's directory name for a project: every character other than an ASCII
+/// letter or digit becomes '-', so /Users/foo/my.app -> -Users-foo-my-app
+pub fn encode_project_path(project_path: &str) -> String {
+    project_path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Transcript directories of `project_path`. Matching is case-insensitive (macOS paths
+/// are), also accepts the older encoding that only replaced slashes, and like the
+/// original token usage scan takes `<encoded>-*` directories Claude Code may suffix.
+pub fn project_dirs(projects_dir: &Path, project_path: &str) -> Vec<PathBuf> {
+    let project_path = project_path.trim_end_matches('/');
+    let encoded = encode_project_path(project_path).to_lowercase();
+    let legacy = format!(
+        "-{}",
+        project_path.trim_start_matches('/').replace('/', "-")
+    )
+    .to_lowercase();
+
+    let Ok(entries) = fs::read_dir(projects_dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            [&encoded, &legacy].into_iter().any(|prefix| {
+                name == *prefix
+                    || name
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with('-'))
+            })
+        })
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Every project directory Claude Code has written transcripts to
+pub fn all_project_dirs(projects_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(projects_dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Session transcripts (`<session id>.jsonl`) directly inside a project directory
+pub fn session_files(project_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(project_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Session IDs become file names, so only accept the characters Claude Code uses
+pub fn validate_session_id(session_id: &str) -> Result<(), String> {
+    if session_id.is_empty()
+        || !session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid session ID: '{}'", session_id));
+    }
+    Ok(())
+}
+
+/// Transcript file of `session_id` in one of `project_path`'s directories
+pub fn find_session(
+    projects_dir: &Path,
+    project_path: &str,
+    session_id: &str,
+) -> Result<PathBuf, String> {
+    validate_session_id(session_id)?;
+    project_dirs(projects_dir, project_path)
+        .into_iter()
+        .map(|dir| dir.join(format!("{}.jsonl", session_id)))
+        .find(|path| path.is_file())
+        .ok_or_else(|| format!("Session '{}' not found for {}", session_id, project_path))
+}
+
+/// Transcripts of the subagents a session started, kept by newer Claude Code versions
+/// in `<session id>/subagents/*.jsonl` next to the session file
+pub fn subagent_files(session_path: &Path) -> Vec<PathBuf> {
+    match session_path.file_stem() {
+        Some(stem) => session_files(&session_path.with_file_name(stem).join("subagents")),
+        None => Vec::new(),
+    }
+}
+
+fn count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    Ok(Option::<u64>::deserialize(deserializer)?.unwrap_or(0))
+}
+
+/// Token counts the API reported for one assistant message
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default, deserialize_with = "count")]
+    pub input_tokens: u64,
+    #[serde(default, deserialize_with = "count")]
+    pub output_tokens: u64,
+    /// Prompt tokens written to the cache
+    #[serde(default, deserialize_with = "count")]
+    pub cache_creation_input_tokens: u64,
+    /// Prompt tokens served from the cache
+    #[serde(default, deserialize_with = "count")]
+    pub cache_read_input_tokens: u64,
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
+}
+
+impl std::ops::SubAssign for Usage {
+    fn sub_assign(&mut self, other: Usage) {
+        self.input_tokens = self.input_tokens.saturating_sub(other.input_tokens);
+        self.output_tokens = self.output_tokens.saturating_sub(other.output_tokens);
+        self.cache_creation_input_tokens = self
+            .cache_creation_input_tokens
+            .saturating_sub(other.cache_creation_input_tokens);
+        self.cache_read_input_tokens = self
+            .cache_read_input_tokens
+            .saturating_sub(other.cache_read_input_tokens);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    User,
+    Assistant,
+    System,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        thinking: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+        /// Index of the message carrying the result, once it arrived
+        result_index: Option<usize>,
+    },
+    ToolResult {
+        tool_use_id: String,
+        /// Name of the tool the result answers, when its call is in the transcript
+        tool_name: Option<String>,
+        /// Text of the result; images and other parts are left out
+        content: String,
+        is_error: bool,
+    },
+    Image {
+        media_type: Option<String>,
+    },
+    /// A block type Vinsly doesn't know
+    Other {
+        kind: String,
+    },
+}
+
+impl ContentBlock {
+    fn parse(block: &Value) -> ContentBlock {
+        let text = |key: &str| {
+            block
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        match block
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+        {
+            "text" => ContentBlock::Text { text: text("text") },
+            "thinking" => ContentBlock::Thinking {
+                thinking: text("thinking"),
+            },
+            "tool_use" | "server_tool_use" => ContentBlock::ToolUse {
+                id: text("id"),
+                name: text("name"),
+                input: block.get("input").cloned().unwrap_or(Value::Null),
+                result_index: None,
+            },
+            "tool_result" => ContentBlock::ToolResult {
+                tool_use_id: text("tool_use_id"),
+                tool_name: None,
+                content: flatten_text(block.get("content").unwrap_or(&Value::Null)),
+                is_error: block
+                    .get("is_error")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            },
+            "image" => ContentBlock::Image {
+                media_type: block
+                    .pointer("/source/media_type")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            },
+            other => ContentBlock::Other {
+                kind: other.to_string(),
+            },
+        }
+    }
+}
+
+/// Text of a string or of the text parts of a block array
+fn flatten_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn parse_content(content: &Value) -> Vec<ContentBlock> {
+    match content {
+        Value::String(text) => vec![ContentBlock::Text { text: text.clone() }],
+        Value::Array(blocks) => blocks.iter().map(ContentBlock::parse).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A user, assistant or system message. Claude Code writes an assistant reply one
+/// content block per line; the lines are merged back into one message here.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptMessage {
+    /// Position in the session, sidechains included
+    pub index: usize,
+    pub role: Role,
+    pub uuid: Option<String>,
+    pub parent_uuid: Option<String>,
+    pub timestamp: Option<String>,
+    /// Assistant messages only
+    pub model: Option<String>,
+    /// API message ID, assistant messages only
+    pub message_id: Option<String>,
+    pub content: Vec<ContentBlock>,
+    pub usage: Option<Usage>,
+    /// Written by a subagent rather than the main conversation
+    pub is_sidechain: bool,
+    pub agent_id: Option<String>,
+    /// Injected by Claude Code (command expansions, reminders), not typed by the user
+    pub is_meta: bool,
+}
+
+impl TranscriptMessage {
+    /// The message's text blocks joined, for previews and search
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// True when this line is the next part of a streamed reply whose previous line had
+    /// the API message ID `previous_id`
+    pub fn continues(&self, previous_id: Option<&str>) -> bool {
+        self.message_id.is_some() && self.message_id.as_deref() == previous_id
+    }
+
+    /// A prompt the user typed: not a tool result, command wrapper or injected text
+    pub fn is_prompt(&self) -> bool {
+        self.role == Role::User
+            && !self.is_meta
+            && !self.is_sidechain
+            && self
+                .content
+                .iter()
+                .any(|block| matches!(block, ContentBlock::Text { text } if !text.trim_start().starts_with('<')))
+    }
+}
+
+/// Per-line session metadata Claude Code repeats on every message
+#[derive(Debug, Clone, Default)]
+pub struct EntryContext {
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub git_branch: Option<String>,
+    pub version: Option<String>,
+}
+
+/// One transcript line, typed
+#[derive(Debug, Clone)]
+pub enum Entry {
+    Message(Box<TranscriptMessage>, EntryContext),
+    Summary {
+        summary: String,
+        leaf_uuid: Option<String>,
+    },
+    /// Bookkeeping lines (file-history-snapshot, …) with nothing to show
+    Other,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct RawEntry {
+    #[serde(rename = "type")]
+    kind: String,
+    uuid: Option<String>,
+    parent_uuid: Option<String>,
+    session_id: Option<String>,
+    timestamp: Option<String>,
+    cwd: Option<String>,
+    git_branch: Option<String>,
+    version: Option<String>,
+    is_sidechain: Option<bool>,
+    is_meta: Option<bool>,
+    agent_id: Option<String>,
+    message: Option<RawMessage>,
+    /// System entries
+    content: Value,
+    summary: Option<String>,
+    leaf_uuid: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawMessage {
+    id: Option<String>,
+    model: Option<String>,
+    content: Value,
+    usage: Option<Usage>,
+}
+
+/// Parse one JSONL line
+pub fn parse_entry(line: &str) -> Result<Entry, String> {
+    let raw: RawEntry = serde_json::from_str(line)
+        .map_err(|e| format!("Failed to parse transcript line: {}", e))?;
+    let role = match raw.kind.as_str() {
+        "user" => Role::User,
+        "assistant" => Role::Assistant,
+        "system" => Role::System,
+        "summary" => {
+            return Ok(Entry::Summary {
+                summary: raw.summary.unwrap_or_default(),
+                leaf_uuid: raw.leaf_uuid,
+            })
+        }
+        _ => return Ok(Entry::Other),
+    };
+    let message = raw.message.unwrap_or_default();
+    let content = match role {
+        Role::System => parse_content(&raw.content),
+        _ => parse_content(&message.content),
+    };
+    let message = TranscriptMessage {
+        index: 0,
+        role,
+        uuid: raw.uuid,
+        parent_uuid: raw.parent_uuid,
+        timestamp: raw.timestamp,
+        model: message.model.filter(|_| role == Role::Assistant),
+        message_id: message.id.filter(|_| role == Role::Assistant),
+        content,
+        usage: message.usage,
+        is_sidechain: raw.is_sidechain.unwrap_or(false),
+        agent_id: raw.agent_id,
+        is_meta: raw.is_meta.unwrap_or(false),
+    };
+    let context = EntryContext {
+        session_id: raw.session_id,
+        cwd: raw.cwd,
+        git_branch: raw.git_branch,
+        version: raw.version,
+    };
+    Ok(Entry::Message(Box::new(message), context))
+}
+
+/// Parsed lines of a transcript file and how many lines couldn't be parsed
+pub fn read_entries(path: &Path) -> Result<(Vec<Entry>, usize), String> {
+    let file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open transcript {}: {}", path.display(), e))?;
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for line in BufReader::new(file).lines() {
+        let line =
+            line.map_err(|e| format!("Failed to read transcript {}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_entry(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => skipped += 1,
+        }
+    }
+    Ok((entries, skipped))
+}
+
+fn timestamp_of(entry: &Entry) -> Option<&str> {
+    match entry {
+        Entry::Message(message, _) => message.timestamp.as_deref(),
+        _ => None,
+    }
+}
+
+/// Interleave a subagent's entries into the main ones by timestamp, keeping each
+/// sequence's own order. Entries without a timestamp stay where they are.
+fn merge_by_timestamp(main: Vec<Entry>, side: Vec<Entry>) -> Vec<Entry> {
+    let mut merged = Vec::with_capacity(main.len() + side.len());
+    let mut side = side.into_iter().peekable();
+    for entry in main {
+        if let Some(at) = timestamp_of(&entry) {
+            while let Some(next) =
+                side.next_if(|next| timestamp_of(next).is_none_or(|next_at| next_at < at))
+            {
+                merged.push(next);
+            }
+        }
+        merged.push(entry);
+    }
+    merged.extend(side);
+    merged
+}
+
+/// A whole session, ready to page through
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Transcript {
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub git_branch: Option<String>,
+    pub version: Option<String>,
+    /// Summaries Claude Code wrote for conversations ending in this session
+    pub summaries: Vec<String>,
+    pub messages: Vec<TranscriptMessage>,
+    /// Lines that weren't valid JSON
+    pub skipped_lines: usize,
+    #[serde(skip)]
+    summary_leaves: Vec<(String, Option<String>)>,
+    /// tool_use id -> (message index, block index)
+    #[serde(skip)]
+    tool_uses: HashMap<String, (usize, usize)>,
+}
+
+impl Transcript {
+    pub fn push(&mut self, entry: Entry) {
+        let (mut message, context) = match entry {
+            Entry::Message(message, context) => (message, context),
+            Entry::Summary { summary, leaf_uuid } => {
+                self.summary_leaves.push((summary, leaf_uuid));
+                return;
+            }
+            Entry::Other => return,
+        };
+        self.session_id = self.session_id.take().or(context.session_id);
+        self.cwd = self.cwd.take().or(context.cwd);
+        self.git_branch = self.git_branch.take().or(context.git_branch);
+        self.version = context.version.or(self.version.take());
+
+        let previous_id = self
+            .messages
+            .last()
+            .and_then(|last| last.message_id.as_deref());
+        if message.continues(previous_id) {
+            let target = self.messages.last_mut().expect("continues a message");
+            target.content.append(&mut message.content);
+            // Every line of a streamed reply repeats the usage; the last one is final
+            target.usage = message.usage.or(target.usage);
+        } else {
+            message.index = self.messages.len();
+            self.messages.push(*message);
+        }
+        self.link_tool_calls(self.messages.len() - 1);
+    }
+
+    /// Pair the tool calls and results of message `index` with their counterparts
+    fn link_tool_calls(&mut self, index: usize) {
+        for block_index in 0..self.messages[index].content.len() {
+            match &self.messages[index].content[block_index] {
+                ContentBlock::ToolUse { id, .. } => {
+                    self.tool_uses.insert(id.clone(), (index, block_index));
+                }
+                ContentBlock::ToolResult { tool_use_id, .. } => {
+                    let Some(&(use_index, use_block)) = self.tool_uses.get(tool_use_id) else {
+                        continue;
+                    };
+                    let name = match &mut self.messages[use_index].content[use_block] {
+                        ContentBlock::ToolUse {
+                            name, result_index, ..
+                        } => {
+                            *result_index = Some(index);
+                            name.clone()
+                        }
+                        _ => continue,
+                    };
+                    if let ContentBlock::ToolResult { tool_name, .. } =
+                        &mut self.messages[index].content[block_index]
+                    {
+                        *tool_name = Some(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Summaries whose conversation ends in this transcript, most recent last
+    fn finish(&mut self) {
+        let uuids: std::collections::HashSet<&str> = self
+            .messages
+            .iter()
+            .filter_map(|message| message.uuid.as_deref())
+            .collect();
+        self.summaries = self
+            .summary_leaves
+            .iter()
+            .filter(|(_, leaf)| leaf.as_deref().is_some_and(|leaf| uuids.contains(leaf)))
+            .map(|(summary, _)| summary.clone())
+            .collect();
+    }
+
+    /// First thing the user typed, shortened for lists
+    pub fn first_prompt(&self) -> Option<String> {
+        let message = self.messages.iter().find(|message| message.is_prompt())?;
+        Some(prompt_preview(&message.text()))
+    }
+}
+
+/// A prompt shortened for session lists
+pub fn prompt_preview(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(PROMPT_PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Read a session file, with its subagents' transcripts interleaved when `include_subagents`
+pub fn read_session(path: &Path, include_subagents: bool) -> Result<Transcript, String> {
+    let (mut entries, mut skipped_lines) = read_entries(path)?;
+    if include_subagents {
+        for subagent in subagent_files(path) {
+            let (mut side, skipped) = read_entries(&subagent)?;
+            let agent_id = subagent.file_stem().map(|stem| {
+                stem.to_string_lossy()
+                    .trim_start_matches("agent-")
+                    .to_string()
+            });
+            for entry in &mut side {
+                if let Entry::Message(message, _) = entry {
+                    message.is_sidechain = true;
+                    message.agent_id = message.agent_id.take().or_else(|| agent_id.clone());
+                }
+            }
+            entries = merge_by_timestamp(entries, side);
+            skipped_lines += skipped;
+        }
+    }
+
+    let mut transcript = Transcript {
+        skipped_lines,
+        ..Default::default()
+    };
+    for entry in entries {
+        transcript.push(entry);
+    }
+    transcript.finish();
+    Ok(transcript)
+}
+
+/// A session as listed for a project, from the transcript index
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub path: String,
+    /// Claude Code's summary of the conversation, else the first prompt
+    pub title: Option<String>,
+    pub first_prompt: Option<String>,
+    pub cwd: Option<String>,
+    pub git_branch: Option<String>,
+    pub started_at: Option<String>,
+    pub last_activity_at: Option<String>,
+    pub message_count: usize,
+    pub sidechain_message_count: usize,
+    pub has_subagents: bool,
+    /// Seconds since the epoch
+    pub modified: u64,
+    pub size: u64,
+}
+
+/// A window of a session's messages
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionPage {
+    pub session_id: String,
+    /// Messages available with the chosen filter
+    pub total: usize,
+    /// Index of the first message asked for
+    pub offset: usize,
+    /// Index to ask for next, if any messages are left
+    pub next_offset: Option<usize>,
+    pub messages: Vec<TranscriptMessage>,
+    pub skipped_lines: usize,
+}
+
+/// Up to `limit` messages from index `offset` on. Without `include_sidechains` subagent
+/// messages are left out, but every message keeps its index in `transcript`, so paging
+/// continues from `next_offset` rather than `offset + limit`.
+pub fn page(
+    session_id: &str,
+    transcript: Transcript,
+    offset: usize,
+    limit: usize,
+    include_sidechains: bool,
+) -> SessionPage {
+    let messages: Vec<TranscriptMessage> = transcript
+        .messages
+        .into_iter()
+        .filter(|message| include_sidechains || !message.is_sidechain)
+        .collect();
+    let total = messages.len();
+    let mut rest = messages
+        .into_iter()
+        .skip_while(|message| message.index < offset);
+    let messages: Vec<TranscriptMessage> = rest.by_ref().take(limit).collect();
+    SessionPage {
+        session_id: session_id.to_string(),
+        total,
+        offset,
+        next_offset: rest.next().map(|message| message.index),
+        messages,
+        skipped_lines: transcript.skipped_lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION_FIXTURE: &str = include_str!("../tests/fixtures/session.jsonl");
+
+    fn session_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vinsly-transcript-{}", uuid::Uuid::new_v4()));
+        let project = dir.join("-work-app");
+        fs::create_dir_all(project.join("5f0c").join("subagents")).unwrap();
+        fs::write(project.join("5f0c.jsonl"), SESSION_FIXTURE).unwrap();
+        fs::write(
+            project.join("5f0c").join("subagents").join("agent-9b.jsonl"),
+            r#"{"type":"user","uuid":"g1","timestamp":"2025-06-01T10:00:05.500Z","message":{"role":"user","content":"Look for other loaders"}}"#,
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn project_dirs_match_both_encodings_and_suffixed_dirs() {
+        let dir = std::env::temp_dir().join(format!("vinsly-projects-{}", uuid::Uuid::new_v4()));
+        for name in [
+            "-Work-my-app",
+            "-work-my.app",
+            "-work-my-app-2",
+            "-work-my-apple",
+        ] {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+
+        let names: Vec<String> = project_dirs(&dir, "/work/my.app/")
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["-Work-my-app", "-work-my-app-2", "-work-my.app"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parses_a_session_into_typed_messages() {
+        let dir = session_dir();
+        let path = find_session(&dir, "/work/app", "5f0c").unwrap();
+        let transcript = read_session(&path, false).unwrap();
+
+        assert_eq!(transcript.skipped_lines, 1);
+        assert_eq!(transcript.summaries, vec!["Fix the migration ordering bug"]);
+        assert_eq!(transcript.git_branch.as_deref(), Some("main"));
+        // Two streamed lines of msg_1 become one message with the final usage
+        let roles: Vec<_> = transcript
+            .messages
+            .iter()
+            .map(|m| (m.role, m.is_sidechain))
+            .collect();
+        assert_eq!(
+            roles,
+            vec![
+                (Role::User, false),
+                (Role::Assistant, false),
+                (Role::User, false),
+                (Role::Assistant, true),
+                (Role::Assistant, false),
+            ]
+        );
+        let reply = &transcript.messages[1];
+        assert_eq!(reply.content.len(), 2);
+        assert_eq!(reply.usage.unwrap().output_tokens, 48);
+        assert!(
+            matches!(&reply.content[1], ContentBlock::ToolUse { name, result_index: Some(2), .. } if name == "Edit")
+        );
+        assert!(matches!(
+            &transcript.messages[2].content[0],
+            ContentBlock::ToolResult { tool_name: Some(name), content, .. }
+                if name == "Edit" && content.ends_with("has been updated.")
+        ));
+
+        let page = page("5f0c", read_session(&path, true).unwrap(), 1, 2, false);
+        assert_eq!(page.total, 4);
+        assert_eq!(
+            page.messages.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+
+        let with_subagent = read_session(&path, true).unwrap();
+        assert_eq!(with_subagent.messages[2].agent_id.as_deref(), Some("9b"));
+
+        assert!(find_session(&dir, "/work/app", "../5f0c").is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,491 @@
+use crate::fs_write::{content_hash, write_atomic};
+use crate::token_usage::{add_bucket, usage_bucket, SessionBuckets, UsageBucket};
+use crate::transcript::{
+    parse_entry, prompt_preview, session_files, subagent_files, Entry, SessionSummary, Usage,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever what gets recorded changes; an index of another version is rebuilt
+const INDEX_VERSION: u32 = 2;
+
+/// Bytes at the start of a file that are hashed to recognise it later
+const HEAD_BYTES: usize = 4096;
+
+fn modified_ms(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |modified| modified.as_millis() as u64)
+}
+
+/// Lines appended to a file since it was last read
+#[derive(Debug, Default)]
+pub struct Appended {
+    /// The file shrank or its start changed: it was rewritten, and `text` holds it from
+    /// the beginning. Whatever was derived from the old content is stale.
+    pub replaced: bool,
+    /// Byte offset of `text` in the file
+    pub start: u64,
+    /// Complete lines only; a line still being written is left for the next read
+    pub text: String,
+}
+
+/// How far an append-only file has been read, and enough of its start to notice when
+/// it's been replaced rather than appended to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileCursor {
+    /// Always just past a newline
+    pub offset: u64,
+    pub len: u64,
+    pub modified_ms: u64,
+    head_len: u64,
+    head_hash: String,
+}
+
+impl FileCursor {
+    fn head_matches(&self, file: &mut fs::File) -> bool {
+        if self.head_len == 0 {
+            return true;
+        }
+        let mut head = vec![0; self.head_len as usize];
+        file.seek(SeekFrom::Start(0)).is_ok()
+            && file.read_exact(&mut head).is_ok()
+            && content_hash(&head) == self.head_hash
+    }
+
+    /// Read what was appended to `path` since the last call. Unchanged files (same
+    /// length and modification time) aren't opened.
+    pub fn read_appended(&mut self, path: &Path) -> Result<Appended, String> {
+        let mut file = fs::File::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let metadata = file
+            .metadata()
+            .map_err(|e| format!("Failed to read metadata of {}: {}", path.display(), e))?;
+        let (len, modified_ms) = (metadata.len(), modified_ms(&metadata));
+        if len == self.len && modified_ms == self.modified_ms {
+            return Ok(Appended::default());
+        }
+
+        let replaced = len < self.offset || !self.head_matches(&mut file);
+        if replaced {
+            *self = FileCursor::default();
+        }
+        let start = self.offset;
+        let mut bytes = Vec::new();
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_to_end(&mut bytes))
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let complete = bytes
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |end| end + 1);
+        bytes.truncate(complete);
+
+        if self.offset == 0 && complete > 0 {
+            let head = &bytes[..complete.min(HEAD_BYTES)];
+            self.head_len = head.len() as u64;
+            self.head_hash = content_hash(head);
+        }
+        self.offset += complete as u64;
+        // Only remember the length once every complete line has been consumed, so a
+        // partly written last line is picked up on the next call
+        self.len = if self.offset == len { len } else { 0 };
+        self.modified_ms = modified_ms;
+        Ok(Appended {
+            replaced,
+            start,
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+        })
+    }
+}
+
+/// Where the usage of the last message went, for its next streamed line to replace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LastReply {
+    model: String,
+    day: String,
+    usage: Usage,
+}
+
+/// What the index knows about one transcript file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileEntry {
+    cursor: FileCursor,
+    /// Directory under ~/.claude/projects the session belongs to
+    pub project_dir: String,
+    /// A subagent's transcript belongs to the session that started it
+    pub session_id: String,
+    pub is_subagent: bool,
+    pub cwd: Option<String>,
+    pub git_branch: Option<String>,
+    pub started_at: Option<String>,
+    pub last_activity_at: Option<String>,
+    pub message_count: usize,
+    pub sidechain_message_count: usize,
+    pub first_prompt: Option<String>,
+    /// Claude Code's summary of a conversation ending in this file
+    pub title: Option<String>,
+    pub skipped_lines: usize,
+    pub usage: Vec<UsageBucket>,
+    /// Summaries (leaf uuid, summary) whose conversation hasn't shown up yet
+    pending_summaries: Vec<(String, String)>,
+    /// API message ID of the last line, which the next line may continue
+    last_message_id: Option<String>,
+    last_reply: Option<LastReply>,
+}
+
+impl FileEntry {
+    fn new(project_dir: &Path, session_id: &str, is_subagent: bool) -> Self {
+        FileEntry {
+            project_dir: project_dir.to_string_lossy().to_string(),
+            session_id: session_id.to_string(),
+            is_subagent,
+            ..Default::default()
+        }
+    }
+
+    /// Seconds since the epoch
+    pub fn modified(&self) -> u64 {
+        self.cursor.modified_ms / 1000
+    }
+
+    fn add_line(&mut self, line: &str) {
+        let (message, context) = match parse_entry(line) {
+            Ok(Entry::Message(message, context)) => (message, context),
+            Ok(Entry::Summary {
+                summary,
+                leaf_uuid: Some(leaf_uuid),
+            }) => {
+                self.pending_summaries.push((leaf_uuid, summary));
+                return;
+            }
+            Ok(_) => return,
+            Err(_) => {
+                self.skipped_lines += 1;
+                return;
+            }
+        };
+
+        self.cwd = self.cwd.take().or(context.cwd);
+        self.git_branch = self.git_branch.take().or(context.git_branch);
+        if let Some(timestamp) = &message.timestamp {
+            if self
+                .started_at
+                .as_ref()
+                .is_none_or(|started| timestamp < started)
+            {
+                self.started_at = Some(timestamp.clone());
+            }
+            if self
+                .last_activity_at
+                .as_ref()
+                .is_none_or(|last| timestamp > last)
+            {
+                self.last_activity_at = Some(timestamp.clone());
+            }
+        }
+        if let Some(uuid) = &message.uuid {
+            if let Some(position) = self
+                .pending_summaries
+                .iter()
+                .position(|(leaf, _)| leaf == uuid)
+            {
+                self.title = Some(self.pending_summaries.remove(position).1);
+            }
+        }
+        if self.first_prompt.is_none() && message.is_prompt() {
+            self.first_prompt = Some(prompt_preview(&message.text()));
+        }
+
+        let previous_id = std::mem::replace(&mut self.last_message_id, message.message_id.clone());
+        if message.continues(previous_id.as_deref()) {
+            // Later lines of a streamed reply repeat its usage, with the final output count
+            if let (Some(last), Some(usage)) = (&mut self.last_reply, message.usage) {
+                if let Some(bucket) = self
+                    .usage
+                    .iter_mut()
+                    .find(|bucket| bucket.model == last.model && bucket.day == last.day)
+                {
+                    bucket.usage -= last.usage;
+                    bucket.usage += usage;
+                }
+                last.usage = usage;
+            }
+            return;
+        }
+
+        if self.is_subagent || message.is_sidechain {
+            self.sidechain_message_count += 1;
+        } else {
+            self.message_count += 1;
+        }
+        self.last_reply = usage_bucket(&message).map(|bucket| {
+            let last = LastReply {
+                model: bucket.model.clone(),
+                day: bucket.day.clone(),
+                usage: bucket.usage,
+            };
+            add_bucket(&mut self.usage, bucket);
+            last
+        });
+    }
+
+    /// Index the lines appended to `path` since the last refresh. Returns whether
+    /// anything changed.
+    fn refresh(&mut self, path: &Path) -> Result<bool, String> {
+        let before = (self.cursor.len, self.cursor.modified_ms);
+        let appended = self.cursor.read_appended(path)?;
+        if appended.replaced {
+            let cursor = std::mem::take(&mut self.cursor);
+            *self = FileEntry {
+                cursor,
+                ..FileEntry::new(
+                    Path::new(&self.project_dir),
+                    &self.session_id,
+                    self.is_subagent,
+                )
+            };
+        }
+        for line in appended.text.lines().filter(|line| !line.trim().is_empty()) {
+            self.add_line(line);
+        }
+        Ok(before != (self.cursor.len, self.cursor.modified_ms))
+    }
+
+    fn summary(&self, path: &str, has_subagents: bool) -> SessionSummary {
+        SessionSummary {
+            session_id: self.session_id.clone(),
+            path: path.to_string(),
+            title: self.title.clone().or_else(|| self.first_prompt.clone()),
+            first_prompt: self.first_prompt.clone(),
+            cwd: self.cwd.clone(),
+            git_branch: self.git_branch.clone(),
+            started_at: self.started_at.clone(),
+            last_activity_at: self.last_activity_at.clone(),
+            message_count: self.message_count,
+            sidechain_message_count: self.sidechain_message_count,
+            has_subagents,
+            modified: self.modified(),
+            size: self.cursor.offset,
+        }
+    }
+}
+
+/// Aggregates of every transcript read so far, keyed by file path, so that polling only
+/// has to parse what Claude Code appended since the last call
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TranscriptIndex {
+    version: u32,
+    files: BTreeMap<String, FileEntry>,
+}
+
+impl TranscriptIndex {
+    /// The index saved at `path`; missing, unreadable or outdated ones start over empty
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<TranscriptIndex>(&content).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .unwrap_or_else(|| TranscriptIndex {
+                version: INDEX_VERSION,
+                files: BTreeMap::new(),
+            })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize transcript index: {}", e))?;
+        write_atomic(path, &content, None).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Bring the entries of every session and subagent transcript in `project_dirs` up
+    /// to date, and forget files that were deleted. Files that can't be read keep their
+    /// last entry. Returns whether anything changed.
+    pub fn refresh(&mut self, project_dirs: &[PathBuf]) -> bool {
+        let mut changed = false;
+        let mut seen = HashSet::new();
+        for dir in project_dirs {
+            for session in session_files(dir) {
+                let session_id = session
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                let subagents = subagent_files(&session);
+                let files = std::iter::once((session, false))
+                    .chain(subagents.into_iter().map(|path| (path, true)));
+                for (path, is_subagent) in files {
+                    let key = path.to_string_lossy().to_string();
+                    let entry = self
+                        .files
+                        .entry(key.clone())
+                        .or_insert_with(|| FileEntry::new(dir, &session_id, is_subagent));
+                    changed |= entry.refresh(&path).unwrap_or(false);
+                    seen.insert(key);
+                }
+            }
+        }
+
+        let dirs: HashSet<String> = project_dirs
+            .iter()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect();
+        let before = self.files.len();
+        self.files
+            .retain(|key, entry| seen.contains(key) || !dirs.contains(&entry.project_dir));
+        changed || self.files.len() != before
+    }
+
+    fn entries_in<'a>(
+        &'a self,
+        project_dirs: &[PathBuf],
+    ) -> impl Iterator<Item = (&'a String, &'a FileEntry)> {
+        let dirs: HashSet<String> = project_dirs
+            .iter()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect();
+        self.files
+            .iter()
+            .filter(move |(_, entry)| dirs.contains(&entry.project_dir))
+    }
+
+    /// Sessions in `project_dirs`, most recently modified first
+    pub fn sessions(&self, project_dirs: &[PathBuf]) -> Vec<SessionSummary> {
+        let with_subagents: HashSet<(&str, &str)> = self
+            .entries_in(project_dirs)
+            .filter(|(_, entry)| entry.is_subagent)
+            .map(|(_, entry)| (entry.project_dir.as_str(), entry.session_id.as_str()))
+            .collect();
+        let mut sessions: Vec<SessionSummary> = self
+            .entries_in(project_dirs)
+            .filter(|(_, entry)| !entry.is_subagent)
+            .map(|(path, entry)| {
+                let has_subagents = with_subagents
+                    .contains(&(entry.project_dir.as_str(), entry.session_id.as_str()));
+                entry.summary(path, has_subagents)
+            })
+            .collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.modified));
+        sessions
+    }
+
+    /// Usage of each session in `project_dirs`, its subagents included
+    pub fn session_usage(&self, project_dirs: &[PathBuf]) -> Vec<SessionBuckets> {
+        let mut sessions: BTreeMap<(&str, &str), SessionBuckets> = BTreeMap::new();
+        for (_, entry) in self.entries_in(project_dirs) {
+            let session = sessions
+                .entry((entry.project_dir.as_str(), entry.session_id.as_str()))
+                .or_insert_with(|| SessionBuckets {
+                    session_id: entry.session_id.clone(),
+                    project_dir: Path::new(&entry.project_dir)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    cwd: None,
+                    buckets: Vec::new(),
+                });
+            if !entry.is_subagent {
+                session.cwd = entry.cwd.clone();
+            }
+            for bucket in &entry.usage {
+                add_bucket(&mut session.buckets, bucket.clone());
+            }
+        }
+        sessions.into_values().collect()
+    }
+
+    /// Input plus output tokens of files in `project_dirs` modified at or after
+    /// `since` (seconds since the epoch). Cached prompt tokens aren't counted.
+    pub fn tokens_since(&self, project_dirs: &[PathBuf], since: u64) -> u64 {
+        self.entries_in(project_dirs)
+            .filter(|(_, entry)| entry.modified() >= since)
+            .flat_map(|(_, entry)| &entry.usage)
+            .map(|bucket| bucket.usage.input_tokens + bucket.usage.output_tokens)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const SESSION_FIXTURE: &str = include_str!("../tests/fixtures/session.jsonl");
+
+    #[test]
+    fn only_appended_lines_are_parsed_and_rewrites_start_over() {
+        let dir = std::env::temp_dir().join(format!("vinsly-index-{}", uuid::Uuid::new_v4()));
+        let project = dir.join("-work-app");
+        fs::create_dir_all(project.join("5f0c").join("subagents")).unwrap();
+        fs::write(
+            project.join("5f0c").join("subagents").join("agent-9b.jsonl"),
+            "{\"type\":\"assistant\",\"timestamp\":\"2025-06-01T10:00:05.500Z\",\"message\":{\"id\":\"msg_9\",\"model\":\"claude-3-5-haiku-20241022\",\"content\":[],\"usage\":{\"input_tokens\":7,\"output_tokens\":1}}}\n",
+        )
+        .unwrap();
+        let path = project.join("5f0c.jsonl");
+        let lines: Vec<&str> = SESSION_FIXTURE.lines().collect();
+        // Stop between the two streamed lines of msg_1, mid-way through the second
+        fs::write(
+            &path,
+            format!("{}\n{}", lines[..3].join("\n"), &lines[3][..40]),
+        )
+        .unwrap();
+
+        let dirs = vec![project.clone()];
+        let mut index = TranscriptIndex::load(&dir.join("missing.json"));
+        assert!(index.refresh(&dirs));
+        assert_eq!(index.tokens_since(&dirs, 0), 12 + 3 + 7 + 1);
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "{}\n{}", &lines[3][40..], lines[4..].join("\n")).unwrap();
+        drop(file);
+        assert!(index.refresh(&dirs));
+        let sessions = index.sessions(&dirs);
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert_eq!(
+            session.title.as_deref(),
+            Some("Fix the migration ordering bug")
+        );
+        assert_eq!(
+            session.first_prompt.as_deref(),
+            Some("Why does migration 0042 run before 0041?")
+        );
+        assert_eq!(
+            (session.message_count, session.sidechain_message_count),
+            (4, 1)
+        );
+        assert!(session.has_subagents);
+        // msg_1 counted once with its final usage, across the two reads
+        assert_eq!(
+            index.tokens_since(&dirs, 0),
+            (12 + 48) + (40 + 6) + (5 + 9) + (7 + 1)
+        );
+        let usage = index.session_usage(&dirs);
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].cwd.as_deref(), Some("/work/app"));
+
+        let saved = dir.join("index.json");
+        index.save(&saved).unwrap();
+        let mut index = TranscriptIndex::load(&saved);
+        assert!(!index.refresh(&dirs));
+
+        fs::write(&path, format!("{}\n", lines[1])).unwrap();
+        assert!(index.refresh(&dirs));
+        assert_eq!(index.sessions(&dirs)[0].message_count, 1);
+        assert_eq!(index.tokens_since(&dirs, 0), 7 + 1);
+
+        fs::remove_file(&path).unwrap();
+        assert!(index.refresh(&dirs));
+        assert!(index.sessions(&dirs).is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
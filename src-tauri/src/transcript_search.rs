@@ -0,0 +1,474 @@
+use crate::fs_write::write_atomic;
+use crate::token_usage::local_day;
+use crate::transcript::{parse_entry, session_files, ContentBlock, Entry, Role, TranscriptMessage};
+use crate::transcript_index::FileCursor;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever what gets indexed changes; an index of another version is rebuilt
+const SEARCH_INDEX_VERSION: u32 = 1;
+
+/// Longer words are hashes, base64 and the like, not something anyone searches for
+const MAX_TERM_CHARS: usize = 64;
+
+/// Characters of message text shown around the first match
+const SNIPPET_CHARS: usize = 160;
+const SNIPPET_LEAD_CHARS: usize = 40;
+
+const DEFAULT_LIMIT: usize = 50;
+
+/// Tool input keys naming the file a tool touched
+const PATH_KEYS: &[&str] = &["file_path", "notebook_path", "path"];
+
+/// Lowercased words of `text`: runs of letters and digits, at least two long
+pub fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| (2..=MAX_TERM_CHARS).contains(&word.chars().count()))
+        .map(str::to_lowercase)
+}
+
+/// Tool names and touched paths of a message
+fn tools_and_paths(message: &TranscriptMessage) -> (Vec<String>, Vec<String>) {
+    let mut tools = Vec::new();
+    let mut paths = Vec::new();
+    for block in &message.content {
+        if let ContentBlock::ToolUse { name, input, .. } = block {
+            tools.push(name.clone());
+            paths.extend(
+                PATH_KEYS
+                    .iter()
+                    .filter_map(|key| input.get(*key).and_then(|path| path.as_str()))
+                    .map(str::to_string),
+            );
+        }
+    }
+    (tools, paths)
+}
+
+/// One indexed transcript line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedLine {
+    /// Byte offset of the line, to read it back for a snippet
+    offset: u64,
+    message_index: usize,
+    role: Role,
+    timestamp: Option<String>,
+    is_sidechain: bool,
+    tools: Vec<String>,
+    paths: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexedFile {
+    cursor: FileCursor,
+    project_dir: String,
+    session_id: String,
+    /// Messages seen so far, counted the way `transcript::read_session` does
+    message_count: usize,
+    last_message_id: Option<String>,
+    lines: Vec<IndexedLine>,
+    /// Term -> positions in `lines`, ascending
+    terms: BTreeMap<String, Vec<u32>>,
+}
+
+impl IndexedFile {
+    fn add_line(&mut self, offset: u64, line: &str) {
+        let Ok(Entry::Message(message, _)) = parse_entry(line) else {
+            return;
+        };
+        let previous_id = std::mem::replace(&mut self.last_message_id, message.message_id.clone());
+        if !message.continues(previous_id.as_deref()) {
+            self.message_count += 1;
+        }
+
+        let (tools, paths) = tools_and_paths(&message);
+        let mut words: HashSet<String> = terms(&message.text()).collect();
+        for text in tools.iter().chain(&paths) {
+            words.extend(terms(text));
+        }
+        if words.is_empty() {
+            return;
+        }
+        let position = self.lines.len() as u32;
+        for word in words {
+            self.terms.entry(word).or_default().push(position);
+        }
+        self.lines.push(IndexedLine {
+            offset,
+            message_index: self.message_count - 1,
+            role: message.role,
+            timestamp: message.timestamp.clone(),
+            is_sidechain: message.is_sidechain,
+            tools,
+            paths,
+        });
+    }
+
+    fn refresh(&mut self, path: &Path) -> Result<bool, String> {
+        let before = (self.cursor.len, self.cursor.modified_ms);
+        let appended = self.cursor.read_appended(path)?;
+        if appended.replaced {
+            *self = IndexedFile {
+                cursor: std::mem::take(&mut self.cursor),
+                project_dir: std::mem::take(&mut self.project_dir),
+                session_id: std::mem::take(&mut self.session_id),
+                ..Default::default()
+            };
+        }
+        let mut offset = appended.start;
+        for line in appended.text.split_inclusive('\n') {
+            if !line.trim().is_empty() {
+                self.add_line(offset, line);
+            }
+            offset += line.len() as u64;
+        }
+        Ok(before != (self.cursor.len, self.cursor.modified_ms))
+    }
+
+    /// Positions of lines with a word starting with `term`
+    fn matching(&self, term: &str) -> BTreeSet<u32> {
+        self.terms
+            .range(term.to_string()..)
+            .take_while(|(word, _)| word.starts_with(term))
+            .flat_map(|(_, positions)| positions.iter().copied())
+            .collect()
+    }
+}
+
+/// What to look for; `text` is split into words that must all appear, each as the
+/// start of a word in the message
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub text: String,
+    /// YYYY-MM-DD in the local time zone, inclusive
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// Every role when empty
+    pub roles: Vec<Role>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub session_id: String,
+    pub project_dir: String,
+    pub path: String,
+    /// Index of the message in the session, as paged without subagent transcripts
+    pub message_index: usize,
+    pub role: Role,
+    pub timestamp: Option<String>,
+    pub is_sidechain: bool,
+    pub tools: Vec<String>,
+    pub paths: Vec<String>,
+    /// Message text around the first match
+    pub snippet: String,
+}
+
+/// Text around the first occurrence of one of `query_terms`, whitespace collapsed
+fn snippet(text: &str, query_terms: &[String]) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lower = text.to_lowercase();
+    // Byte positions only carry over when lowercasing kept every character's length
+    let at = if lower.len() == text.len() {
+        query_terms
+            .iter()
+            .filter_map(|term| lower.find(term.as_str()))
+            .min()
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    let start = text[..at]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_LEAD_CHARS - 1)
+        .map_or(0, |(index, _)| index);
+    let excerpt: String = text[start..].chars().take(SNIPPET_CHARS).collect();
+    let lead = if start > 0 { "…" } else { "" };
+    let tail = if start + excerpt.len() < text.len() {
+        "…"
+    } else {
+        ""
+    };
+    format!("{}{}{}", lead, excerpt, tail)
+}
+
+/// Read the line at `offset` back from the transcript
+fn read_line_at(path: &Path, offset: u64) -> Option<TranscriptMessage> {
+    let mut file = fs::File::open(path).ok()?;
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut line = String::new();
+    BufReader::new(file).read_line(&mut line).ok()?;
+    match parse_entry(&line).ok()? {
+        Entry::Message(message, _) => Some(*message),
+        _ => None,
+    }
+}
+
+/// Word index of every session transcript searched so far, updated from where each
+/// file was last read
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    version: u32,
+    files: BTreeMap<String, IndexedFile>,
+}
+
+impl SearchIndex {
+    /// The index saved at `path`; missing, unreadable or outdated ones start over empty
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<SearchIndex>(&content).ok())
+            .filter(|index| index.version == SEARCH_INDEX_VERSION)
+            .unwrap_or_else(|| SearchIndex {
+                version: SEARCH_INDEX_VERSION,
+                files: BTreeMap::new(),
+            })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize search index: {}", e))?;
+        write_atomic(path, &content, None).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Index what was appended to the session transcripts in `project_dirs` and forget
+    /// deleted ones. Subagent transcripts aren't searched. Returns whether anything changed.
+    pub fn refresh(&mut self, project_dirs: &[PathBuf]) -> bool {
+        let mut changed = false;
+        let mut seen = HashSet::new();
+        for dir in project_dirs {
+            for path in session_files(dir) {
+                let key = path.to_string_lossy().to_string();
+                let file = self
+                    .files
+                    .entry(key.clone())
+                    .or_insert_with(|| IndexedFile {
+                        project_dir: dir.to_string_lossy().to_string(),
+                        session_id: path
+                            .file_stem()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string(),
+                        ..Default::default()
+                    });
+                changed |= file.refresh(&path).unwrap_or(false);
+                seen.insert(key);
+            }
+        }
+
+        let dirs: HashSet<String> = project_dirs
+            .iter()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect();
+        let before = self.files.len();
+        self.files
+            .retain(|key, file| seen.contains(key) || !dirs.contains(&file.project_dir));
+        changed || self.files.len() != before
+    }
+
+    /// Messages in `project_dirs` matching `query`, newest first, one hit per message
+    pub fn search(&self, project_dirs: &[PathBuf], query: &SearchQuery) -> Vec<SearchHit> {
+        let query_terms: Vec<String> = terms(&query.text)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        if query_terms.is_empty() {
+            return Vec::new();
+        }
+        let dirs: HashSet<String> = project_dirs
+            .iter()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect();
+        let in_range = |line: &IndexedLine| {
+            if query.since.is_none() && query.until.is_none() {
+                return true;
+            }
+            let Some(day) = line.timestamp.as_deref().and_then(local_day) else {
+                return false;
+            };
+            query
+                .since
+                .as_deref()
+                .is_none_or(|since| day.as_str() >= since)
+                && query
+                    .until
+                    .as_deref()
+                    .is_none_or(|until| day.as_str() <= until)
+        };
+
+        let mut hits: Vec<(&str, &IndexedFile, &IndexedLine)> = Vec::new();
+        for (path, file) in self
+            .files
+            .iter()
+            .filter(|(_, file)| dirs.contains(&file.project_dir))
+        {
+            let mut matches = file.matching(&query_terms[0]);
+            for term in &query_terms[1..] {
+                if matches.is_empty() {
+                    break;
+                }
+                let also = file.matching(term);
+                matches.retain(|position| also.contains(position));
+            }
+            let mut messages = HashSet::new();
+            for position in matches {
+                let line = &file.lines[position as usize];
+                if (query.roles.is_empty() || query.roles.contains(&line.role))
+                    && in_range(line)
+                    && messages.insert(line.message_index)
+                {
+                    hits.push((path.as_str(), file, line));
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| b.2.timestamp.cmp(&a.2.timestamp));
+        hits.truncate(query.limit.unwrap_or(DEFAULT_LIMIT));
+        hits.into_iter()
+            .map(|(path, file, line)| {
+                let text = read_line_at(Path::new(path), line.offset)
+                    .map(|message| message.text())
+                    .filter(|text| !text.trim().is_empty())
+                    .unwrap_or_else(|| {
+                        line.tools
+                            .iter()
+                            .chain(&line.paths)
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    });
+                SearchHit {
+                    session_id: file.session_id.clone(),
+                    project_dir: Path::new(&file.project_dir)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    path: path.to_string(),
+                    message_index: line.message_index,
+                    role: line.role,
+                    timestamp: line.timestamp.clone(),
+                    is_sidechain: line.is_sidechain,
+                    tools: line.tools.clone(),
+                    paths: line.paths.clone(),
+                    snippet: snippet(&text, &query_terms),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const SESSION_FIXTURE: &str = include_str!("../tests/fixtures/session.jsonl");
+
+    #[test]
+    fn finds_messages_by_words_tools_and_paths() {
+        let dir = std::env::temp_dir().join(format!("vinsly-search-{}", uuid::Uuid::new_v4()));
+        let project = dir.join("-work-app");
+        fs::create_dir_all(&project).unwrap();
+        let path = project.join("5f0c.jsonl");
+        let lines: Vec<&str> = SESSION_FIXTURE.lines().collect();
+        fs::write(&path, format!("{}\n", lines[..4].join("\n"))).unwrap();
+
+        let dirs = vec![project.clone()];
+        let mut index = SearchIndex::load(&dir.join("missing.json"));
+        assert!(index.refresh(&dirs));
+        let search = |index: &SearchIndex, text: &str, roles: Vec<Role>| -> Vec<usize> {
+            let query = SearchQuery {
+                text: text.to_string(),
+                roles,
+                ..Default::default()
+            };
+            index
+                .search(&dirs, &query)
+                .iter()
+                .map(|hit| hit.message_index)
+                .collect()
+        };
+        assert_eq!(search(&index, "migration", vec![]), vec![0]);
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "{}", lines[4..].join("\n")).unwrap();
+        drop(file);
+        assert!(index.refresh(&dirs));
+
+        // Prefixes match, newest message first
+        assert_eq!(search(&index, "migration", vec![]), vec![4, 0]);
+        assert_eq!(search(&index, "MIGRATION", vec![Role::User]), vec![0]);
+        assert_eq!(search(&index, "loader", vec![]), vec![3, 1]);
+        // Tool names and touched paths count as words of the message
+        assert_eq!(search(&index, "edit db/migrate.rs", vec![]), vec![1]);
+        assert!(search(&index, "migration loader", vec![]).is_empty());
+
+        let hits = index.search(
+            &dirs,
+            &SearchQuery {
+                text: "0042".to_string(),
+                ..Default::default()
+            },
+        );
+        assert_eq!(hits[0].session_id, "5f0c");
+        assert_eq!(hits[0].project_dir, "-work-app");
+        assert_eq!(hits[0].snippet, "Why does migration 0042 run before 0041?");
+        let later = SearchQuery {
+            text: "0042".to_string(),
+            since: Some("2999-01-01".to_string()),
+            ..Default::default()
+        };
+        assert!(index.search(&dirs, &later).is_empty());
+
+        let saved = dir.join("search.json");
+        index.save(&saved).unwrap();
+        let mut index = SearchIndex::load(&saved);
+        assert!(!index.refresh(&dirs));
+        assert_eq!(search(&index, "edit", vec![Role::Assistant]), vec![1]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn hits_after_a_sidechain_line_start_their_page() {
+        let dir = std::env::temp_dir().join(format!("vinsly-search-{}", uuid::Uuid::new_v4()));
+        let project = dir.join("-work-app");
+        fs::create_dir_all(&project).unwrap();
+        let path = project.join("5f0c.jsonl");
+        fs::write(&path, SESSION_FIXTURE).unwrap();
+
+        let dirs = vec![project];
+        let mut index = SearchIndex::load(&dir.join("missing.json"));
+        index.refresh(&dirs);
+        let query = SearchQuery {
+            text: "migration".to_string(),
+            roles: vec![Role::Assistant],
+            ..Default::default()
+        };
+        let hit = &index.search(&dirs, &query)[0];
+        let session = crate::transcript::read_session(&path, false).unwrap();
+        // The sidechain line before the hit is counted but not paged
+        assert!(session.messages[hit.message_index - 1].is_sidechain);
+
+        let page = crate::transcript::page("5f0c", session, hit.message_index, 1, false);
+        assert_eq!(page.messages[0].index, hit.message_index);
+        assert_eq!(page.messages[0].uuid.as_deref(), Some("a3"));
+        assert_eq!(page.next_offset, None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn snippets_center_on_the_first_match() {
+        let text = format!("{} needle {}", "a ".repeat(100), "b ".repeat(100));
+        let excerpt = snippet(&text, &["needle".to_string()]);
+        assert!(excerpt.starts_with('…') && excerpt.ends_with('…'));
+        assert!(excerpt.contains("needle"));
+        assert_eq!(excerpt.chars().count(), SNIPPET_CHARS + 2);
+    }
+}
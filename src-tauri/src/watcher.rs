@@ -0,0 +1,464 @@
+use crate::fs_write::is_temp_file;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Quiet period after the last filesystem event before a burst is flushed
+const DEBOUNCE_WINDOW: Duration = Duration::from_millis(300);
+/// Upper bound on how long a continuous burst (e.g. `git pull`) can delay events
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+
+pub const RESOURCE_CHANGED_EVENT: &str = "resource:changed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    Agent,
+    Skill,
+    Command,
+    Memory,
+    Mcp,
+    Settings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// Event payload for `resource:changed`.
+/// `scope` uses the same vocabulary as the matching `list_*` command:
+/// "global"/"project" for agents, skills, commands and memory, "user"/"project"/"local" for MCP and settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceChangedEvent {
+    pub kind: ResourceKind,
+    pub scope: String,
+    pub project_path: Option<String>,
+    pub path: String,
+    pub change: ChangeKind,
+}
+
+/// Where a watched path lives, used to classify incoming events
+#[derive(Debug, Clone)]
+enum Root {
+    /// ~/.claude plus ~/.claude.json
+    Global { home: PathBuf },
+    Project { root: PathBuf },
+}
+
+struct WatchState {
+    watcher: RecommendedWatcher,
+    home: PathBuf,
+    projects: Vec<PathBuf>,
+    /// Paths currently registered with the OS watcher
+    active: HashSet<PathBuf>,
+}
+
+impl WatchState {
+    fn roots(&self) -> Vec<Root> {
+        let mut roots = vec![Root::Global {
+            home: self.home.clone(),
+        }];
+        roots.extend(self.projects.iter().map(|p| Root::Project { root: p.clone() }));
+        roots
+    }
+
+    /// Register every target that exists and drop the ones that are gone.
+    /// Called after each flush so directories created later (e.g. a new
+    /// `.claude/agents`) start being watched without a restart.
+    fn sync_watches(&mut self) {
+        let wanted: Vec<(PathBuf, RecursiveMode)> =
+            self.roots().iter().flat_map(watch_targets).collect();
+        let wanted_paths: HashSet<PathBuf> = wanted.iter().map(|(p, _)| p.clone()).collect();
+
+        let stale: Vec<PathBuf> = self
+            .active
+            .iter()
+            .filter(|p| !wanted_paths.contains(*p) || !p.exists())
+            .cloned()
+            .collect();
+        for path in stale {
+            let _ = self.watcher.unwatch(&path);
+            self.active.remove(&path);
+        }
+
+        for (path, mode) in wanted {
+            if self.active.contains(&path) || !path.is_dir() {
+                continue;
+            }
+            if self.watcher.watch(&path, mode).is_ok() {
+                self.active.insert(path);
+            }
+        }
+    }
+}
+
+static WATCH_STATE: OnceLock<Arc<Mutex<Option<WatchState>>>> = OnceLock::new();
+
+fn get_state() -> Arc<Mutex<Option<WatchState>>> {
+    WATCH_STATE.get_or_init(|| Arc::new(Mutex::new(None))).clone()
+}
+
+/// Directories to register for a root. Only the resource folders are watched
+/// recursively so transcript churn under ~/.claude/projects never reaches us.
+fn watch_targets(root: &Root) -> Vec<(PathBuf, RecursiveMode)> {
+    let (base, claude_dir) = match root {
+        Root::Global { home } => (home.clone(), home.join(".claude")),
+        Root::Project { root } => (root.clone(), root.join(".claude")),
+    };
+    vec![
+        (base, RecursiveMode::NonRecursive),
+        (claude_dir.clone(), RecursiveMode::NonRecursive),
+        (claude_dir.join("agents"), RecursiveMode::Recursive),
+        (claude_dir.join("skills"), RecursiveMode::Recursive),
+        (claude_dir.join("commands"), RecursiveMode::Recursive),
+    ]
+}
+
+fn scope_label(kind: ResourceKind, global: bool, file_name: &str) -> &'static str {
+    match (kind, global) {
+        (ResourceKind::Settings, _) if file_name == "settings.local.json" => "local",
+        (ResourceKind::Mcp | ResourceKind::Settings, true) => "user",
+        (_, true) => "global",
+        (_, false) => "project",
+    }
+}
+
+/// Map a changed path to the resource it belongs to, if any
+fn classify(path: &Path, roots: &[Root]) -> Option<(ResourceKind, String, Option<String>)> {
+    if is_temp_file(path) {
+        return None;
+    }
+    for root in roots {
+        let (base, global) = match root {
+            Root::Global { home } => (home, true),
+            Root::Project { root } => (root, false),
+        };
+        let Ok(relative) = path.strip_prefix(base) else {
+            continue;
+        };
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        let parts: Vec<&str> = parts.iter().map(|s| s.as_str()).collect();
+        let is_markdown = path.extension().and_then(|e| e.to_str()) == Some("md");
+
+        let kind = match parts.as_slice() {
+            [".claude.json"] if global => Some(ResourceKind::Mcp),
+            [".mcp.json"] if !global => Some(ResourceKind::Mcp),
+            ["CLAUDE.md"] if !global => Some(ResourceKind::Memory),
+            [".claude", "CLAUDE.md"] => Some(ResourceKind::Memory),
+            [".claude", "settings.json" | "settings.local.json"] => Some(ResourceKind::Settings),
+            [".claude", "agents", ..] if is_markdown || parts.len() == 2 => Some(ResourceKind::Agent),
+            [".claude", "commands", ..] if is_markdown || parts.len() == 2 => {
+                Some(ResourceKind::Command)
+            }
+            [".claude", "skills", _, ..] => Some(ResourceKind::Skill),
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            let file_name = parts.last().copied().unwrap_or("");
+            let scope = scope_label(kind, global, file_name).to_string();
+            let project_path = (!global).then(|| base.to_string_lossy().to_string());
+            return Some((kind, scope, project_path));
+        }
+    }
+    None
+}
+
+/// Raw filesystem events collected between flushes
+#[derive(Default)]
+struct Batch {
+    /// Path -> whether the first event seen for it in this batch was a create
+    pending: HashMap<PathBuf, bool>,
+    started: Option<Instant>,
+}
+
+impl Batch {
+    /// Fold `event` into the batch. Returns true once a continuous burst has
+    /// been collecting for `MAX_BATCH_DELAY` and should be flushed without
+    /// waiting for a quiet period.
+    fn add(&mut self, event: notify::Event, now: Instant) -> bool {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return false;
+        }
+        let created = matches!(event.kind, EventKind::Create(_));
+        for path in event.paths {
+            self.pending.entry(path).or_insert(created);
+        }
+        let started = *self.started.get_or_insert(now);
+        now.duration_since(started) >= MAX_BATCH_DELAY
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Take the collected paths and start a new batch
+    fn take(&mut self) -> HashMap<PathBuf, bool> {
+        self.started = None;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+fn flush(
+    pending: HashMap<PathBuf, bool>,
+    state: &Arc<Mutex<Option<WatchState>>>,
+    emit: &dyn Fn(ResourceChangedEvent),
+) {
+    let roots = {
+        let Ok(mut guard) = state.lock() else {
+            return;
+        };
+        let Some(watch_state) = guard.as_mut() else {
+            return;
+        };
+        watch_state.sync_watches();
+        watch_state.roots()
+    };
+
+    for (path, created) in pending {
+        let Some((kind, scope, project_path)) = classify(&path, &roots) else {
+            continue;
+        };
+        let change = if !path.exists() {
+            ChangeKind::Removed
+        } else if created {
+            ChangeKind::Created
+        } else {
+            ChangeKind::Modified
+        };
+        emit(ResourceChangedEvent {
+            kind,
+            scope,
+            project_path,
+            path: path.to_string_lossy().to_string(),
+            change,
+        });
+    }
+}
+
+fn start(emit: Box<dyn Fn(ResourceChangedEvent) + Send>) -> Result<RecommendedWatcher, String> {
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    })
+    .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+
+    let state = get_state();
+    thread::spawn(move || {
+        let mut batch = Batch::default();
+
+        loop {
+            match rx.recv_timeout(DEBOUNCE_WINDOW) {
+                Ok(Ok(event)) => {
+                    if batch.add(event, Instant::now()) {
+                        flush(batch.take(), &state, emit.as_ref());
+                    }
+                }
+                Ok(Err(e)) => {
+                    eprintln!("File watcher error: {}", e);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if !batch.is_empty() {
+                        flush(batch.take(), &state, emit.as_ref());
+                    }
+                }
+                // Sender dropped: the watcher was stopped or replaced
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+/// Start watching ~/.claude and the given project roots, or update the
+/// project list of an already running watcher.
+pub fn watch_resources(app: AppHandle, project_paths: Vec<String>) -> Result<(), String> {
+    let home = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+
+    let mut projects: Vec<PathBuf> = Vec::new();
+    for project in project_paths {
+        let path = PathBuf::from(project);
+        if path.is_absolute() && path != home && !projects.contains(&path) {
+            projects.push(path);
+        }
+    }
+
+    let state = get_state();
+    let mut guard = state.lock().map_err(|e| format!("Lock error: {}", e))?;
+
+    if guard.is_none() {
+        let watcher = start(Box::new(move |event| {
+            let _ = app.emit(RESOURCE_CHANGED_EVENT, event);
+        }))?;
+        *guard = Some(WatchState {
+            watcher,
+            home,
+            projects: Vec::new(),
+            active: HashSet::new(),
+        });
+    }
+
+    if let Some(watch_state) = guard.as_mut() {
+        watch_state.projects = projects;
+        watch_state.sync_watches();
+    }
+
+    Ok(())
+}
+
+/// Stop the watcher; dropping it also ends the debounce thread
+pub fn stop_watching_resources() -> Result<(), String> {
+    let state = get_state();
+    let mut guard = state.lock().map_err(|e| format!("Lock error: {}", e))?;
+    *guard = None;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+
+    fn roots() -> Vec<Root> {
+        vec![
+            Root::Global {
+                home: PathBuf::from("/home/me"),
+            },
+            Root::Project {
+                root: PathBuf::from("/work/app"),
+            },
+        ]
+    }
+
+    fn classified(path: &str) -> Option<(ResourceKind, String, Option<String>)> {
+        classify(Path::new(path), &roots())
+    }
+
+    fn event(kind: EventKind, path: &str) -> notify::Event {
+        notify::Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn classify_maps_paths_to_resources_and_scopes() {
+        let global = |kind| Some((kind, "global".to_string(), None));
+        let user = |kind| Some((kind, "user".to_string(), None));
+        let project =
+            |kind, scope: &str| Some((kind, scope.to_string(), Some("/work/app".to_string())));
+
+        assert_eq!(
+            classified("/home/me/.claude/agents/reviewer.md"),
+            global(ResourceKind::Agent)
+        );
+        assert_eq!(
+            classified("/home/me/.claude/skills/pdf/scripts/run.py"),
+            global(ResourceKind::Skill)
+        );
+        assert_eq!(
+            classified("/home/me/.claude/commands/git/commit.md"),
+            global(ResourceKind::Command)
+        );
+        assert_eq!(
+            classified("/home/me/.claude/CLAUDE.md"),
+            global(ResourceKind::Memory)
+        );
+        assert_eq!(classified("/home/me/.claude.json"), user(ResourceKind::Mcp));
+        assert_eq!(
+            classified("/home/me/.claude/settings.json"),
+            user(ResourceKind::Settings)
+        );
+
+        assert_eq!(
+            classified("/work/app/.claude/agents/reviewer.md"),
+            project(ResourceKind::Agent, "project")
+        );
+        assert_eq!(
+            classified("/work/app/.mcp.json"),
+            project(ResourceKind::Mcp, "project")
+        );
+        assert_eq!(
+            classified("/work/app/CLAUDE.md"),
+            project(ResourceKind::Memory, "project")
+        );
+        assert_eq!(
+            classified("/work/app/.claude/settings.local.json"),
+            project(ResourceKind::Settings, "local")
+        );
+        // Deleting a directory under agents/ reports the directory itself
+        assert_eq!(
+            classified("/work/app/.claude/agents"),
+            project(ResourceKind::Agent, "project")
+        );
+    }
+
+    #[test]
+    fn classify_ignores_unrelated_and_temp_files() {
+        assert_eq!(classified("/home/me/.claude/agents/notes.txt"), None);
+        assert_eq!(
+            classified("/home/me/.claude/agents/.reviewer.md.1234.tmp"),
+            None
+        );
+        assert_eq!(
+            classified("/home/me/.claude/projects/app/session.jsonl"),
+            None
+        );
+        assert_eq!(classified("/home/me/.claude/skills"), None);
+        // ~/CLAUDE.md is not a memory file and projects have no .claude.json
+        assert_eq!(classified("/home/me/CLAUDE.md"), None);
+        assert_eq!(classified("/work/app/.claude.json"), None);
+        assert_eq!(classified("/home/me/.mcp.json"), None);
+        assert_eq!(classified("/elsewhere/.claude/agents/reviewer.md"), None);
+    }
+
+    #[test]
+    fn batches_coalesce_events_per_path_and_keep_the_first_kind() {
+        let now = Instant::now();
+        let mut batch = Batch::default();
+
+        assert!(!batch.add(event(EventKind::Create(CreateKind::File), "/a.md"), now));
+        assert!(!batch.add(event(EventKind::Modify(ModifyKind::Any), "/a.md"), now));
+        assert!(!batch.add(event(EventKind::Modify(ModifyKind::Any), "/b.md"), now));
+        assert!(!batch.add(event(EventKind::Create(CreateKind::File), "/b.md"), now));
+        assert!(!batch.add(event(EventKind::Access(AccessKind::Any), "/c.md"), now));
+
+        let pending = batch.take();
+        assert_eq!(pending.len(), 2);
+        assert!(pending[Path::new("/a.md")]);
+        assert!(!pending[Path::new("/b.md")]);
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn continuous_bursts_flush_after_the_max_delay() {
+        let start = Instant::now();
+        let mut batch = Batch::default();
+        let modify = || event(EventKind::Modify(ModifyKind::Any), "/a.md");
+
+        // Access events alone never open a batch
+        assert!(!batch.add(event(EventKind::Access(AccessKind::Any), "/a.md"), start));
+        assert!(batch.is_empty());
+
+        assert!(!batch.add(modify(), start));
+        assert!(!batch.add(modify(), start + MAX_BATCH_DELAY / 2));
+        assert!(batch.add(modify(), start + MAX_BATCH_DELAY));
+        batch.take();
+
+        // The next batch measures its delay from its own first event
+        let later = start + MAX_BATCH_DELAY * 2;
+        assert!(!batch.add(modify(), later));
+        assert!(!batch.add(modify(), later + MAX_BATCH_DELAY / 2));
+    }
+}
@@ -295,7 +295,7 @@ describe('MCP Server Types', () => {
     it('should return correct paths', () => {
       expect(getMCPConfigPath('user')).toBe('~/.claude.json');
       expect(getMCPConfigPath('project', '/my/project')).toBe('/my/project/.mcp.json');
-      expect(getMCPConfigPath('local', '/my/project')).toBe('/my/project/.claude/settings.local.json');
+      expect(getMCPConfigPath('local', '/my/project')).toBe('~/.claude.json (projects["/my/project"])');
     });
   });
 });
@@ -354,16 +354,11 @@ export const AppContent: React.FC = () => {
   };
 
   const handleDeleteMCP = async (server: MCPServer) => {
-    // For project/local scoped servers, derive projectPath from sourcePath
-    let projectPath: string | undefined;
-    if ((server.scope === 'project' || server.scope === 'local') && server.sourcePath) {
-      if (server.scope === 'project') {
-        // Remove /.mcp.json from end to get project path
-        projectPath = server.sourcePath.replace(/[/\\][^/\\]+$/, '');
-      } else {
-        // Remove /.claude/settings.local.json from end to get project path
-        projectPath = server.sourcePath.replace(/[/\\]\.claude[/\\][^/\\]+$/, '');
-      }
+    // Project/local scoped servers carry their project; older entries fall back to sourcePath
+    let projectPath = server.projectPath;
+    if (!projectPath && server.scope === 'project' && server.sourcePath) {
+      // Remove /.mcp.json from end to get project path
+      projectPath = server.sourcePath.replace(/[/\\][^/\\]+$/, '');
     }
     await removeMCPServer(server.name, server.scope, projectPath);
   };
@@ -520,7 +520,8 @@ export const HooksEditorScreen: React.FC<HooksEditorScreenProps> = ({
     // Build final hook object
     const finalHook: Hook = {
       ...formData,
-      id: createHookId(formData.name, formData.scope),
+      // Edits keep the backend id so the save updates the existing hook
+      id: mode === 'edit' && hook ? hook.id : createHookId(formData.name, formData.scope),
     };
 
     const errors = validateHook(finalHook);
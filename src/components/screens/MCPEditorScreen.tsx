@@ -136,8 +136,8 @@ const SCOPE_DETAILS: Record<MCPScope, { title: string; description: string; path
   },
   local: {
     title: 'Local (Private)',
-    description: 'Saved to .claude/settings.local.json. Not tracked by git.',
-    path: '.claude/settings.local.json'
+    description: 'Saved to this project\'s entry in ~/.claude.json. Private to you, not tracked by git.',
+    path: '~/.claude.json'
   }
 };
 
@@ -175,7 +175,7 @@ export const MCPEditorScreen: React.FC<MCPEditorScreenProps> = ({
   const [argsEntries, setArgsEntries] = useState<string[]>([]);
 
   // OAuth state
-  const [oauthUrl, setOauthUrl] = useState('');
+  const [oauthScope, setOauthScope] = useState('');
 
   // Test connection state
   const [testResult, setTestResult] = useState<MCPHealthResult | null>(null);
@@ -847,36 +847,37 @@ export const MCPEditorScreen: React.FC<MCPEditorScreenProps> = ({
               </div>
             )}
 
-            {/* OAuth URL Input for Manual Authentication */}
-            <div className="p-4 rounded-lg border border-v-light-border dark:border-v-border">
-              <h4 className="font-medium text-v-light-text-primary dark:text-v-text-primary mb-2">
-                Manual OAuth Authentication
-              </h4>
-              <p className="text-sm text-v-light-text-secondary dark:text-v-text-secondary mb-3">
-                If the server provides an OAuth URL, enter it below to authenticate.
-              </p>
-              <div className="flex items-center gap-2">
-                <input
-                  type="text"
-                  value={oauthUrl}
-                  onChange={e => setOauthUrl(e.target.value)}
-                  placeholder="https://example.com/oauth/authorize?..."
-                  className="flex-1 px-3 py-2 bg-v-light-surface dark:bg-v-mid-dark border border-v-light-border dark:border-v-border rounded-md text-sm text-v-light-text-primary dark:text-v-text-primary focus:border-v-accent focus:ring-1 focus:ring-v-accent"
-                />
-                <button
-                  type="button"
-                  onClick={() => {
-                    if (oauthUrl && server) {
-                      void startOAuth(server, oauthUrl);
-                    }
-                  }}
-                  disabled={!oauthUrl || !server || currentAuthStatus === 'pending'}
-                  className="px-4 py-2 text-sm font-medium bg-v-accent text-white rounded-md hover:bg-v-accent-hover transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
-                >
-                  Authenticate
-                </button>
+            {/* Sign in through the server's OAuth authorization server */}
+            {mode === 'edit' && server && (
+              <div className="p-4 rounded-lg border border-v-light-border dark:border-v-border">
+                <h4 className="font-medium text-v-light-text-primary dark:text-v-text-primary mb-2">
+                  Sign In
+                </h4>
+                <p className="text-sm text-v-light-text-secondary dark:text-v-text-secondary mb-3">
+                  Opens your browser to sign in with the server's authorization provider. Leave the scope
+                  empty to use the one the server asks for.
+                </p>
+                <div className="flex items-center gap-2">
+                  <input
+                    type="text"
+                    value={oauthScope}
+                    onChange={e => setOauthScope(e.target.value)}
+                    placeholder="Scope (optional)"
+                    className="flex-1 px-3 py-2 bg-v-light-surface dark:bg-v-mid-dark border border-v-light-border dark:border-v-border rounded-md text-sm text-v-light-text-primary dark:text-v-text-primary focus:border-v-accent focus:ring-1 focus:ring-v-accent"
+                  />
+                  <button
+                    type="button"
+                    onClick={() => void startOAuth(server, oauthScope.trim() || undefined)}
+                    disabled={!server.url || currentAuthStatus === 'pending'}
+                    className="px-4 py-2 text-sm font-medium bg-v-accent text-white rounded-md hover:bg-v-accent-hover transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+                  >
+                    {currentAuthStatus === 'authenticated' || currentAuthStatus === 'expired'
+                      ? 'Sign In Again'
+                      : 'Sign In'}
+                  </button>
+                </div>
               </div>
-            </div>
+            )}
 
             {/* Info Note */}
             <div className="p-3 rounded-lg bg-blue-50 dark:bg-blue-900/20 border border-blue-200 dark:border-blue-800">
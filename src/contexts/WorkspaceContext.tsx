@@ -1,4 +1,4 @@
-import React, { createContext, useContext, useCallback, useEffect } from 'react';
+import React, { createContext, useContext, useCallback, useEffect, useMemo, useRef, useState } from 'react';
 import { Agent, AgentScope, Skill, LoadAgentsOptions, SlashCommand, ClaudeMemory, DetailedScanResult } from '../types';
 import { MCPServer, MCPScope } from '../types/mcp';
 import { Hook } from '../types/hooks';
@@ -10,16 +10,26 @@ import { useSlashCommands } from '../hooks/useSlashCommands';
 import { useMCPServers } from '../hooks/useMCPServers';
 import { useHooks } from '../hooks/useHooks';
 import { useHistory } from '../hooks/useHistory';
+import { useResourceWatcher } from '../hooks/useResourceWatcher';
 import { useToast } from './ToastContext';
 import { useAppBootstrapContext } from './AppBootstrapContext';
 import { DEFAULT_HOME_DISCOVERY_DEPTH, discoverHomeDirectories } from '../utils/homeDiscovery';
 import { saveScanSettings } from '../utils/scanSettings';
 import { AgentCommands, SkillCommands } from '../utils/workspaceCommands';
+import { ResourceChangedEvent } from '../utils/tauriCommands';
+import { extractProjectRootFromAgentPath, extractProjectRootFromSkillPath } from '../utils/path';
 import { ScanSettings } from '../types';
 import { devLog } from '../utils/devLogger';
 
 const HOME_DISCOVERY_MAX_DEPTH = DEFAULT_HOME_DISCOVERY_DEPTH;
 
+// What the last startup load or full scan covered, reused when the watcher reports a change
+interface LoadedScope {
+  includeGlobal: boolean;
+  projectPaths: string[];
+  watchedDirectories: string[];
+}
+
 interface WorkspaceContextType {
   // Agents
   agents: Agent[];
@@ -186,6 +196,10 @@ export const WorkspaceProvider: React.FC<WorkspaceProviderProps> = ({ children }
     toggleFavorite: toggleHookFavorite,
   } = hooksHook;
 
+  const [loadedScope, setLoadedScope] = useState<LoadedScope | null>(null);
+  const loadedScopeRef = useRef<LoadedScope | null>(null);
+  loadedScopeRef.current = loadedScope;
+
   // Initialize workspace when onboarding completes
   useEffect(() => {
     if (!isOnboardingComplete || isWelcomeOpen) {
@@ -256,6 +270,12 @@ export const WorkspaceProvider: React.FC<WorkspaceProviderProps> = ({ children }
         includeGlobal: storedSettings.autoScanGlobalOnStartup,
         projectPaths: allProjectPaths,
       });
+
+      setLoadedScope({
+        includeGlobal: storedSettings.autoScanGlobalOnStartup,
+        projectPaths: allProjectPaths,
+        watchedDirectories: storedSettings.watchedDirectories,
+      });
     };
 
     initializeWorkspace();
@@ -509,6 +529,12 @@ export const WorkspaceProvider: React.FC<WorkspaceProviderProps> = ({ children }
       }),
     ]);
 
+    setLoadedScope({
+      includeGlobal: options?.includeGlobal ?? true,
+      projectPaths: allProjectPaths,
+      watchedDirectories: scanSettings.watchedDirectories,
+    });
+
     // Log any failures but don't throw - allow app to continue with partial data
     const loaderNames = ['Commands', 'MCP Servers', 'Hooks', 'Memories'];
     results.forEach((result, index) => {
@@ -564,6 +590,63 @@ export const WorkspaceProvider: React.FC<WorkspaceProviderProps> = ({ children }
     return detailedResult;
   }, [loadAgents, loadCommands, loadMCPServers, loadHooks, loadMemories, scanSettings.watchedDirectories, showToast]);
 
+  // Project roots to watch: everything scanned plus the projects loaded resources live in
+  const watchedProjectPaths = useMemo(() => {
+    const paths = new Set<string>(loadedScope?.projectPaths ?? []);
+    for (const agent of agents) {
+      const root = agent.scope === AgentScope.Project ? extractProjectRootFromAgentPath(agent.path) : null;
+      if (root) paths.add(root);
+    }
+    for (const skill of skills) {
+      const root = skill.scope === AgentScope.Project
+        ? extractProjectRootFromSkillPath(skill.directoryPath || skill.path)
+        : null;
+      if (root) paths.add(root);
+    }
+    return Array.from(paths);
+  }, [loadedScope, agents, skills]);
+
+  // Reload whichever lists the changed files belong to
+  const handleResourceChanges = useCallback((events: ResourceChangedEvent[]) => {
+    const scope = loadedScopeRef.current;
+    const includeGlobal = scope?.includeGlobal ?? true;
+    const projectPaths = Array.from(new Set([
+      ...(scope?.projectPaths ?? []),
+      ...events.flatMap(event => event.project_path ? [event.project_path] : []),
+    ]));
+    const kinds = new Set(events.map(event => event.kind));
+
+    const agentEvents = events.filter(event => event.kind === 'agent' || event.kind === 'skill');
+    if (agentEvents.length > 0) {
+      void loadAgents({
+        includeGlobal: agentEvents.some(event => event.project_path === null),
+        projectPaths: Array.from(new Set(agentEvents.flatMap(event => event.project_path ? [event.project_path] : []))),
+      });
+    }
+    if (kinds.has('command')) {
+      void loadCommands({
+        includeGlobal,
+        projectPaths,
+        watchedDirectories: scope?.watchedDirectories ?? [],
+      });
+    }
+    if (kinds.has('mcp')) {
+      void loadMCPServers({ includeGlobal, projectPaths });
+    }
+    if (kinds.has('settings')) {
+      void loadHooks({ includeGlobal, projectPaths });
+    }
+    if (kinds.has('memory')) {
+      void loadMemories({ includeGlobal, projectPaths });
+    }
+  }, [loadAgents, loadCommands, loadMCPServers, loadHooks, loadMemories]);
+
+  useResourceWatcher({
+    enabled: isOnboardingComplete && !isWelcomeOpen,
+    projectPaths: watchedProjectPaths,
+    onChange: handleResourceChanges,
+  });
+
   const value: WorkspaceContextType = {
     // Agents
     agents,
@@ -48,6 +48,13 @@ jest.mock('../../utils/tauriCommands', () => ({
   readClaudeMemory: jest.fn(),
   writeClaudeMemory: jest.fn(),
   checkClaudeMemoryExists: jest.fn(),
+  // Resource watcher
+  startResourceWatcher: jest.fn().mockResolvedValue(undefined),
+  stopResourceWatcher: jest.fn().mockResolvedValue(undefined),
+}));
+
+jest.mock('@tauri-apps/api/event', () => ({
+  listen: jest.fn().mockResolvedValue(() => {}),
 }));
 
 jest.mock('../../utils/agentImport', () => ({
@@ -148,7 +155,10 @@ describe('WorkspaceContext', () => {
     (skillParser.skillToMarkdown as jest.Mock).mockReturnValue('skill markdown');
 
     // Write/delete mocks
-    (tauriCommands.writeAgent as jest.Mock).mockResolvedValue('/path/to/agent.md');
+    (tauriCommands.writeAgent as jest.Mock).mockResolvedValue({
+      path: '/path/to/agent.md',
+      version: { hash: 'abc', mtime_ms: 1 },
+    });
     (tauriCommands.deleteAgent as jest.Mock).mockResolvedValue(undefined);
     (tauriCommands.writeSkill as jest.Mock).mockResolvedValue('/path/to/skill.md');
     (tauriCommands.deleteSkill as jest.Mock).mockResolvedValue(undefined);
@@ -157,7 +167,10 @@ describe('WorkspaceContext', () => {
     // Slash commands mocks
     (tauriCommands.listSlashCommands as jest.Mock).mockResolvedValue([]);
     (tauriCommands.listSlashCommandsFromDirectory as jest.Mock).mockResolvedValue([]);
-    (tauriCommands.writeSlashCommand as jest.Mock).mockResolvedValue('/path/to/command.md');
+    (tauriCommands.writeSlashCommand as jest.Mock).mockResolvedValue({
+      path: '/path/to/command.md',
+      version: { hash: 'abc', mtime_ms: 1 },
+    });
     (tauriCommands.deleteSlashCommand as jest.Mock).mockResolvedValue(undefined);
 
     // MCP mocks
@@ -172,7 +185,10 @@ describe('WorkspaceContext', () => {
 
     // Memory mocks
     (tauriCommands.readClaudeMemory as jest.Mock).mockResolvedValue({ exists: false, path: '', content: '' });
-    (tauriCommands.writeClaudeMemory as jest.Mock).mockResolvedValue(undefined);
+    (tauriCommands.writeClaudeMemory as jest.Mock).mockResolvedValue({
+      path: '/path/to/CLAUDE.md',
+      version: { hash: 'abc', mtime_ms: 1 },
+    });
     (tauriCommands.checkClaudeMemoryExists as jest.Mock).mockResolvedValue(false);
   });
 
@@ -75,7 +75,10 @@ describe('useWorkspace', () => {
     (skillParser.skillFileToSkill as jest.Mock).mockReturnValue(skill);
     (agentExport.agentToMarkdown as jest.Mock).mockReturnValue('markdown');
     (skillParser.skillToMarkdown as jest.Mock).mockReturnValue('skill-md');
-    (tauriCommands.writeAgent as jest.Mock).mockResolvedValue('/tmp/agent.md');
+    (tauriCommands.writeAgent as jest.Mock).mockResolvedValue({
+      path: '/tmp/agent.md',
+      version: { hash: 'abc', mtime_ms: 1 },
+    });
     (tauriCommands.writeSkill as jest.Mock).mockResolvedValue('/tmp/skill/skill.md');
     (tauriCommands.migrateSkill as jest.Mock).mockResolvedValue('/tmp/skill/skill.md');
     (tauriCommands.deleteAgent as jest.Mock).mockResolvedValue(undefined);
@@ -129,6 +132,33 @@ describe('useWorkspace', () => {
     expect(result.current.agents.find(a => a.name === agent.name)).toBeTruthy();
   });
 
+  test('saveAgent sends back the version the agent was loaded with', async () => {
+    const listedVersion = { hash: 'listed', mtime_ms: 1 };
+    (tauriCommands.listAgents as jest.Mock).mockResolvedValue([
+      { content: '---\nname: Agent One\n---', name: 'Agent One', path: agent.path, version: listedVersion },
+    ]);
+    const { result } = renderHook(() =>
+      useWorkspace({ showToast: mockShowToast, scanSettingsRef, isOnboardingComplete: true })
+    );
+    await act(async () => {
+      await result.current.loadAgents({ includeGlobal: true });
+    });
+
+    await act(async () => {
+      await result.current.saveAgent(agent);
+    });
+    expect(tauriCommands.writeAgent).toHaveBeenLastCalledWith('global', agent.name, 'markdown', undefined, listedVersion);
+
+    // The next save checks against what this one wrote
+    await act(async () => {
+      await result.current.saveAgent(result.current.agents[0]!);
+    });
+    expect(tauriCommands.writeAgent).toHaveBeenLastCalledWith('global', agent.name, 'markdown', undefined, {
+      hash: 'abc',
+      mtime_ms: 1,
+    });
+  });
+
   test('saveSkill persists via migrateSkill for existing skills', async () => {
     const { result } = renderHook(() =>
       useWorkspace({ showToast: mockShowToast, scanSettingsRef, isOnboardingComplete: true })
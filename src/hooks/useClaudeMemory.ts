@@ -1,8 +1,9 @@
 import { useCallback, useEffect, useRef, useState } from 'react';
-import { AgentScope, ClaudeMemory } from '../types';
+import { AgentScope, ClaudeMemory, FileVersion } from '../types';
 import { readClaudeMemory, writeClaudeMemory } from '../utils/tauriCommands';
 import { ToastType } from '../components/Toast';
 import { devLog } from '../utils/devLogger';
+import { getErrorMessage } from '../utils/errorHandler';
 
 export interface UseClaudeMemoryOptions {
   showToast: (type: ToastType, message: string) => void;
@@ -33,6 +34,8 @@ export function useClaudeMemory(options: UseClaudeMemoryOptions): UseClaudeMemor
     global: '',
     project: '',
   });
+  // Version of each file as last read or written, sent back on save to detect outside edits
+  const lastSavedVersionRef = useRef<{ global?: FileVersion; project?: FileVersion }>({});
 
   // Load memory files on mount and when project path changes
   useEffect(() => {
@@ -47,9 +50,11 @@ export function useClaudeMemory(options: UseClaudeMemoryOptions): UseClaudeMemor
           path: globalResult.path,
           content: globalResult.content,
           exists: globalResult.exists,
+          version: globalResult.version,
         };
         setGlobalMemory(globalMem);
         lastSavedContentRef.current.global = globalResult.content;
+        lastSavedVersionRef.current.global = globalResult.version ?? undefined;
 
         // Load project memory if project path is set
         if (projectPath) {
@@ -60,9 +65,11 @@ export function useClaudeMemory(options: UseClaudeMemoryOptions): UseClaudeMemor
             path: projectResult.path,
             content: projectResult.content,
             exists: projectResult.exists,
+            version: projectResult.version,
           };
           setProjectMemory(projectMem);
           lastSavedContentRef.current.project = projectResult.content;
+          lastSavedVersionRef.current.project = projectResult.version ?? undefined;
         } else {
           setProjectMemory(null);
         }
@@ -89,9 +96,11 @@ export function useClaudeMemory(options: UseClaudeMemoryOptions): UseClaudeMemor
           path: globalResult.path,
           content: globalResult.content,
           exists: globalResult.exists,
+          version: globalResult.version,
         };
         setGlobalMemory(globalMem);
         lastSavedContentRef.current.global = globalResult.content;
+        lastSavedVersionRef.current.global = globalResult.version ?? undefined;
       }
 
       if ((!scope || scope === AgentScope.Project) && effectiveProjectPath) {
@@ -102,9 +111,11 @@ export function useClaudeMemory(options: UseClaudeMemoryOptions): UseClaudeMemor
           path: projectResult.path,
           content: projectResult.content,
           exists: projectResult.exists,
+          version: projectResult.version,
         };
         setProjectMemory(projectMem);
         lastSavedContentRef.current.project = projectResult.content;
+        lastSavedVersionRef.current.project = projectResult.version ?? undefined;
       }
     } catch (error) {
       devLog.error('Error loading CLAUDE.md:', error);
@@ -132,16 +143,23 @@ export function useClaudeMemory(options: UseClaudeMemoryOptions): UseClaudeMemor
       const effectiveProjectPath = dynamicProjectPath || projectPath;
       const pathArg = scope === AgentScope.Project ? effectiveProjectPath : undefined;
 
-      const savedPath = await writeClaudeMemory(tauriScope, content, pathArg);
+      const saved = await writeClaudeMemory(
+        tauriScope,
+        content,
+        pathArg,
+        lastSavedVersionRef.current[scopeKey]
+      );
       lastSavedContentRef.current[scopeKey] = content;
+      lastSavedVersionRef.current[scopeKey] = saved.version;
 
       // Update state
       const updatedMemory: ClaudeMemory = {
         id: scopeKey,
         scope,
-        path: savedPath,
+        path: saved.path,
         content,
         exists: true,
+        version: saved.version,
       };
 
       if (scope === AgentScope.Global) {
@@ -153,7 +171,7 @@ export function useClaudeMemory(options: UseClaudeMemoryOptions): UseClaudeMemor
       // Note: We don't show a toast here - the UI shows a "Saved" indicator instead
     } catch (error) {
       devLog.error('Error saving CLAUDE.md:', error);
-      showToast('error', `Failed to save memory: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      showToast('error', `Failed to save memory: ${getErrorMessage(error)}`);
     }
   }, [projectPath, showToast]);
 
@@ -2,7 +2,9 @@ import { useCallback, useState, useRef, useEffect } from 'react';
 import {
   listHooks,
   addHook as addHookCmd,
+  updateHook as updateHookCmd,
   removeHook as removeHookCmd,
+  setHookEnabled as setHookEnabledCmd,
   HookInfoRaw,
   HookConfigRaw,
 } from '../utils/tauriCommands';
@@ -11,11 +13,11 @@ import {
   HookScope,
   HookEventType,
   HookExecutionType,
-  createHookId,
   hookToConfig,
 } from '../types/hooks';
 import { ToastType } from '../components/Toast';
 import { devLog } from '../utils/devLogger';
+import { getErrorMessage } from '../utils/errorHandler';
 import { getStorageItem, setStorageItem } from '../utils/storage';
 
 const HOOKS_CACHE_KEY = 'vinsly-hooks-cache';
@@ -71,6 +73,7 @@ export interface UseHooksResult {
   addHook: (hook: Hook, projectPath?: string) => Promise<void>;
   updateHook: (hook: Hook, oldHook: Hook, projectPath?: string) => Promise<void>;
   removeHook: (hook: Hook, projectPath?: string) => Promise<void>;
+  setHookEnabled: (hook: Hook, enabled: boolean, projectPath?: string) => Promise<void>;
   toggleFavorite: (hook: Hook) => void;
   getHookById: (id: string) => Hook | undefined;
   getHooksByEventType: (eventType: HookEventType) => Hook[];
@@ -174,22 +177,17 @@ export function useHooks({ showToast }: UseHooksOptions): UseHooksResult {
   const addHookFn = useCallback(async (hook: Hook, projectPath?: string) => {
     try {
       const rawConfig = hookToRawConfig(hook);
-      await addHookCmd(hook.scope, hook.eventType, rawConfig, projectPath);
+      const id = await addHookCmd(hook.scope, hook.eventType, rawConfig, projectPath);
 
-      // Add to local state with a generated ID
-      const newHook: Hook = {
-        ...hook,
-        id: createHookId(hook.name, hook.scope, hooks.length),
-      };
-
-      setHooks(prev => [...prev, newHook]);
+      // Add to local state under the id the backend derived from its content
+      setHooks(prev => [...prev, { ...hook, id }]);
       showToast('success', `Added hook "${hook.name}"`);
     } catch (error) {
       devLog.error('Failed to add hook:', error);
-      showToast('error', `Failed to add hook: ${error}`);
+      showToast('error', `Failed to add hook: ${getErrorMessage(error)}`);
       throw error;
     }
-  }, [showToast, hooks.length]);
+  }, [showToast]);
 
   const updateHook = useCallback(async (
     hook: Hook,
@@ -197,57 +195,56 @@ export function useHooks({ showToast }: UseHooksOptions): UseHooksResult {
     projectPath?: string
   ) => {
     try {
-      // Find the index of the old hook in its event type group
-      const hooksOfType = hooksRef.current.filter(
-        h => h.eventType === oldHook.eventType && h.scope === oldHook.scope
-      );
-      const hookIndex = hooksOfType.findIndex(h => h.id === oldHook.id);
-
-      if (hookIndex === -1) {
-        throw new Error('Hook not found');
-      }
-
-      // Remove old hook
-      await removeHookCmd(oldHook.scope, oldHook.eventType, hookIndex, projectPath);
-
-      // Add new hook
       const rawConfig = hookToRawConfig(hook);
-      await addHookCmd(hook.scope, hook.eventType, rawConfig, projectPath);
+      let id: string;
+      if (hook.scope === oldHook.scope) {
+        id = await updateHookCmd(oldHook.scope, oldHook.id, hook.eventType, rawConfig, projectPath);
+      } else {
+        // Moving between settings files: remove from the old one, then add to the new one
+        await removeHookCmd(oldHook.scope, oldHook.id, projectPath);
+        id = await addHookCmd(hook.scope, hook.eventType, rawConfig, projectPath);
+      }
 
       // Update local state
       setHooks(prev => {
         const filtered = prev.filter(h => h.id !== oldHook.id);
-        return [...filtered, hook];
+        return [...filtered, { ...hook, id }];
       });
 
       showToast('success', `Updated hook "${hook.name}"`);
     } catch (error) {
       devLog.error('Failed to update hook:', error);
-      showToast('error', `Failed to update hook: ${error}`);
+      showToast('error', `Failed to update hook: ${getErrorMessage(error)}`);
       throw error;
     }
   }, [showToast]);
 
   const removeHookFn = useCallback(async (hook: Hook, projectPath?: string) => {
     try {
-      // Find the index of the hook in its event type group
-      const hooksOfType = hooksRef.current.filter(
-        h => h.eventType === hook.eventType && h.scope === hook.scope
-      );
-      const hookIndex = hooksOfType.findIndex(h => h.id === hook.id);
-
-      if (hookIndex === -1) {
-        throw new Error('Hook not found');
-      }
-
-      await removeHookCmd(hook.scope, hook.eventType, hookIndex, projectPath);
+      await removeHookCmd(hook.scope, hook.id, projectPath);
 
       // Remove from local state
       setHooks(prev => prev.filter(h => h.id !== hook.id));
       showToast('success', `Removed hook "${hook.name}"`);
     } catch (error) {
       devLog.error('Failed to remove hook:', error);
-      showToast('error', `Failed to remove hook: ${error}`);
+      showToast('error', `Failed to remove hook: ${getErrorMessage(error)}`);
+      throw error;
+    }
+  }, [showToast]);
+
+  const setHookEnabled = useCallback(async (hook: Hook, enabled: boolean, projectPath?: string) => {
+    try {
+      const id = await setHookEnabledCmd(hook.scope, hook.id, enabled, projectPath);
+
+      // Re-enabled hooks can come back under a new id
+      setHooks(prev =>
+        prev.map(h => (h.id === hook.id ? { ...h, id, enabled } : h))
+      );
+      showToast('success', `${enabled ? 'Enabled' : 'Disabled'} hook "${hook.name}"`);
+    } catch (error) {
+      devLog.error('Failed to toggle hook:', error);
+      showToast('error', `Failed to ${enabled ? 'enable' : 'disable'} hook: ${getErrorMessage(error)}`);
       throw error;
     }
   }, [showToast]);
@@ -276,6 +273,7 @@ export function useHooks({ showToast }: UseHooksOptions): UseHooksResult {
     addHook: addHookFn,
     updateHook,
     removeHook: removeHookFn,
+    setHookEnabled,
     toggleFavorite,
     getHookById,
     getHooksByEventType,
@@ -1,9 +1,11 @@
 import { useState, useCallback, useEffect } from 'react';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { MCPServer, MCPAuthStatus } from '../types/mcp';
 import {
   getMCPAuthStatus,
   revokeMCPOAuth,
-  openOAuthUrl,
+  authorizeMCPServer,
+  MCPTokenEvent,
 } from '../utils/tauriCommands';
 import { devLog } from '../utils/devLogger';
 
@@ -17,7 +19,7 @@ export interface UseMCPAuthResult {
   isCheckingAuth: boolean;
   checkAuthStatus: (serverName: string) => Promise<MCPAuthStatus>;
   checkAllAuthStatus: () => Promise<void>;
-  startOAuth: (server: MCPServer, authUrl: string) => Promise<void>;
+  startOAuth: (server: MCPServer, scope?: string) => Promise<void>;
   revokeAuth: (serverName: string) => Promise<void>;
   getAuthStatus: (serverId: string) => MCPAuthStatus;
 }
@@ -65,19 +67,53 @@ export function useMCPAuth(options: UseMCPAuthOptions): UseMCPAuthResult {
     void checkAllAuthStatus();
   }, [enabled, servers.length, checkAllAuthStatus]);
 
-  const startOAuth = useCallback(async (server: MCPServer, authUrl: string) => {
+  // Follow the background token refresher
+  useEffect(() => {
+    if (!enabled) return;
+    let isMounted = true;
+    const unlisteners: UnlistenFn[] = [];
+
+    const setupListeners = async () => {
+      const onRefreshed = await listen<MCPTokenEvent>('mcp-oauth:refreshed', (event) => {
+        if (!isMounted) return;
+        setAuthStatus(prev => ({ ...prev, [event.payload.server_name]: 'authenticated' }));
+      });
+      const onReloginRequired = await listen<MCPTokenEvent>('mcp-oauth:relogin-required', (event) => {
+        if (!isMounted) return;
+        devLog.error(`Sign-in required for ${event.payload.server_name}:`, event.payload.message);
+        setAuthStatus(prev => ({ ...prev, [event.payload.server_name]: 'expired' }));
+      });
+      if (isMounted) {
+        unlisteners.push(onRefreshed, onReloginRequired);
+      } else {
+        onRefreshed();
+        onReloginRequired();
+      }
+    };
+    void setupListeners();
+
+    return () => {
+      isMounted = false;
+      unlisteners.forEach(unlisten => unlisten());
+    };
+  }, [enabled]);
+
+  const startOAuth = useCallback(async (server: MCPServer, scope?: string) => {
+    if (!server.url) return;
     setAuthStatus(prev => ({
       ...prev,
       [server.name]: 'pending',
     }));
 
     try {
-      // Open the OAuth URL in the default browser
-      await openOAuthUrl(authUrl);
-      // Note: The actual token storage would happen after the OAuth callback
-      // This is a simplified flow - full implementation would need a callback handler
+      // Resolves once the browser sign-in completes and the tokens are stored
+      await authorizeMCPServer(server.name, server.url, scope);
+      setAuthStatus(prev => ({
+        ...prev,
+        [server.name]: 'authenticated',
+      }));
     } catch (error) {
-      devLog.error(`Failed to start OAuth for ${server.name}:`, error);
+      devLog.error(`OAuth failed for ${server.name}:`, error);
       setAuthStatus(prev => ({
         ...prev,
         [server.name]: 'error',
@@ -1,10 +1,10 @@
 import { useState, useCallback, useEffect, useRef } from 'react';
-import { MCPServer, MCPServerStatus, MCPHealthResult } from '../types/mcp';
+import { MCPServer, MCPServerStatus, MCPHealthResult, mcpServerToConfig } from '../types/mcp';
 import { checkMCPServerHealth } from '../utils/tauriCommands';
 import { devLog } from '../utils/devLogger';
 
 const HEALTH_CHECK_INTERVAL = 60000; // 1 minute
-const HEALTH_CHECK_TIMEOUT = 5000;   // 5 seconds
+const HEALTH_CHECK_TIMEOUT = 15000;  // 15 seconds; stdio servers are started for the handshake
 
 export interface UseMCPHealthOptions {
   servers: MCPServer[];
@@ -48,7 +48,8 @@ export function useMCPHealth(options: UseMCPHealthOptions): UseMCPHealthResult {
         server.name,
         server.url,
         server.command,
-        HEALTH_CHECK_TIMEOUT
+        HEALTH_CHECK_TIMEOUT,
+        { ...mcpServerToConfig(server) }
       );
 
       const healthResult: MCPHealthResult = {
@@ -56,6 +57,12 @@ export function useMCPHealth(options: UseMCPHealthOptions): UseMCPHealthResult {
         status: result.status as MCPServerStatus,
         latencyMs: result.latency_ms ?? undefined,
         errorMessage: result.error_message ?? undefined,
+        protocolVersion: result.protocol_version ?? undefined,
+        serverInfo: result.server_info ?? undefined,
+        capabilities: result.capabilities ?? undefined,
+        unresolvedVariables: result.unresolved_variables.length > 0
+          ? result.unresolved_variables
+          : undefined,
         checkedAt: Date.now(),
       };
 
@@ -88,8 +95,10 @@ export function useMCPHealth(options: UseMCPHealthOptions): UseMCPHealthResult {
     setIsChecking(true);
 
     try {
-      // Check all servers in parallel
-      await Promise.all(serversRef.current.map(server => checkHealth(server)));
+      // Check all enabled servers in parallel; disabled ones are not started
+      await Promise.all(
+        serversRef.current.filter(server => server.enabled).map(server => checkHealth(server))
+      );
     } finally {
       setIsChecking(false);
     }
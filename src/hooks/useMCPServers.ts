@@ -3,6 +3,7 @@ import {
   listMCPServers,
   addMCPServer as addMCPServerCmd,
   removeMCPServer as removeMCPServerCmd,
+  setMCPServerEnabled as setMCPServerEnabledCmd,
   MCPServerInfoRaw,
   MCPServerConfigRaw,
 } from '../utils/tauriCommands';
@@ -15,6 +16,7 @@ import {
 } from '../types/mcp';
 import { ToastType } from '../components/Toast';
 import { devLog } from '../utils/devLogger';
+import { getErrorMessage } from '../utils/errorHandler';
 import { getStorageItem, setStorageItem } from '../utils/storage';
 
 const MCP_CACHE_KEY = 'vinsly-mcp-servers-cache';
@@ -35,6 +37,7 @@ function rawToMCPServer(raw: MCPServerInfoRaw): MCPServer {
     env: raw.env,
     scope: raw.scope as MCPScope,
     sourcePath: raw.source_path,
+    projectPath: raw.project_path ?? undefined,
     enabled: raw.enabled,
   };
 }
@@ -71,6 +74,7 @@ export interface UseMCPServersResult {
   addServer: (server: MCPServer, projectPath?: string) => Promise<void>;
   updateServer: (server: MCPServer, oldServer: MCPServer, projectPath?: string) => Promise<void>;
   removeServer: (name: string, scope: MCPScope, projectPath?: string) => Promise<void>;
+  setServerEnabled: (server: MCPServer, enabled: boolean) => Promise<void>;
   toggleFavorite: (server: MCPServer) => void;
   getServerById: (id: string) => MCPServer | undefined;
   getServerByName: (name: string, scope: MCPScope) => MCPServer | undefined;
@@ -188,7 +192,7 @@ export function useMCPServers({ showToast }: UseMCPServersOptions): UseMCPServer
       showToast('success', `Added MCP server "${server.name}"`);
     } catch (error) {
       devLog.error('Failed to add MCP server:', error);
-      showToast('error', `Failed to add MCP server: ${error}`);
+      showToast('error', `Failed to add MCP server: ${getErrorMessage(error)}`);
       throw error;
     }
   }, [showToast]);
@@ -216,7 +220,7 @@ export function useMCPServers({ showToast }: UseMCPServersOptions): UseMCPServer
       showToast('success', `Updated MCP server "${server.name}"`);
     } catch (error) {
       devLog.error('Failed to update MCP server:', error);
-      showToast('error', `Failed to update MCP server: ${error}`);
+      showToast('error', `Failed to update MCP server: ${getErrorMessage(error)}`);
       throw error;
     }
   }, [showToast]);
@@ -236,7 +240,22 @@ export function useMCPServers({ showToast }: UseMCPServersOptions): UseMCPServer
       showToast('success', `Removed MCP server "${name}"`);
     } catch (error) {
       devLog.error('Failed to remove MCP server:', error);
-      showToast('error', `Failed to remove MCP server: ${error}`);
+      showToast('error', `Failed to remove MCP server: ${getErrorMessage(error)}`);
+      throw error;
+    }
+  }, [showToast]);
+
+  const setServerEnabled = useCallback(async (server: MCPServer, enabled: boolean) => {
+    try {
+      await setMCPServerEnabledCmd(server.scope, server.name, enabled, server.projectPath);
+
+      setServers(prev =>
+        prev.map(s => (s.id === server.id ? { ...s, enabled } : s))
+      );
+      showToast('success', `${enabled ? 'Enabled' : 'Disabled'} MCP server "${server.name}"`);
+    } catch (error) {
+      devLog.error('Failed to toggle MCP server:', error);
+      showToast('error', `Failed to ${enabled ? 'enable' : 'disable'} MCP server: ${getErrorMessage(error)}`);
       throw error;
     }
   }, [showToast]);
@@ -269,6 +288,7 @@ export function useMCPServers({ showToast }: UseMCPServersOptions): UseMCPServer
     addServer,
     updateServer,
     removeServer,
+    setServerEnabled,
     toggleFavorite,
     getServerById,
     getServerByName,
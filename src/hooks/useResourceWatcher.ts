@@ -0,0 +1,85 @@
+import { useEffect, useRef } from 'react';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+import { devLog } from '../utils/devLogger';
+import {
+  ResourceChangedEvent,
+  startResourceWatcher,
+  stopResourceWatcher,
+} from '../utils/tauriCommands';
+
+// The backend already debounces; this only folds its per-file events into one reload per burst
+const EVENT_BATCH_DELAY_MS = 100;
+
+export interface UseResourceWatcherOptions {
+  enabled: boolean;
+  // Project roots to watch in addition to ~/.claude
+  projectPaths: string[];
+  onChange: (events: ResourceChangedEvent[]) => void;
+}
+
+/**
+ * Keep the backend resource watcher pointed at the current project roots
+ * and hand each burst of 'resource:changed' events to onChange
+ */
+export function useResourceWatcher({ enabled, projectPaths, onChange }: UseResourceWatcherOptions): void {
+  const onChangeRef = useRef(onChange);
+  onChangeRef.current = onChange;
+
+  // Restart only when the set of roots changes, not on every new array instance
+  const projectKey = Array.from(new Set(projectPaths)).sort().join('\n');
+
+  useEffect(() => {
+    if (!enabled) return;
+    const roots = projectKey ? projectKey.split('\n') : [];
+    startResourceWatcher(roots).catch((error) => {
+      devLog.error('Failed to start resource watcher:', error);
+    });
+  }, [enabled, projectKey]);
+
+  useEffect(() => {
+    if (!enabled) return;
+    let isMounted = true;
+    let unlisten: UnlistenFn | null = null;
+    let pending: ResourceChangedEvent[] = [];
+    let timer: ReturnType<typeof setTimeout> | null = null;
+
+    const flush = () => {
+      timer = null;
+      const batch = pending;
+      pending = [];
+      if (isMounted && batch.length > 0) {
+        onChangeRef.current(batch);
+      }
+    };
+
+    const setupListener = async () => {
+      try {
+        const stop = await listen<ResourceChangedEvent>('resource:changed', (event) => {
+          pending.push(event.payload);
+          if (timer === null) {
+            timer = setTimeout(flush, EVENT_BATCH_DELAY_MS);
+          }
+        });
+        if (isMounted) {
+          unlisten = stop;
+        } else {
+          stop();
+        }
+      } catch (error) {
+        devLog.error('Failed to listen for resource changes:', error);
+      }
+    };
+    void setupListener();
+
+    return () => {
+      isMounted = false;
+      if (timer !== null) {
+        clearTimeout(timer);
+      }
+      unlisten?.();
+      stopResourceWatcher().catch((error) => {
+        devLog.error('Failed to stop resource watcher:', error);
+      });
+    };
+  }, [enabled]);
+}
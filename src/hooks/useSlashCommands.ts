@@ -1,5 +1,5 @@
 import { useCallback, useEffect, useRef, useState } from 'react';
-import { AgentScope, SlashCommand } from '../types';
+import { AgentScope, FileVersion, SlashCommand } from '../types';
 import {
   listSlashCommands,
   writeSlashCommand,
@@ -9,6 +9,7 @@ import {
 import { ToastType } from '../components/Toast';
 import { getStorageItem, setStorageItem } from '../utils/storage';
 import { devLog } from '../utils/devLogger';
+import { getErrorMessage } from '../utils/errorHandler';
 import { serializeFrontmatter, parseFrontmatter } from '../utils/frontmatter';
 
 const SLASH_COMMANDS_CACHE_KEY = 'vinsly-slash-commands-cache';
@@ -85,6 +86,7 @@ function parseSlashCommand(file: {
   path: string;
   content: string;
   scope: string;
+  version?: FileVersion;
 }): SlashCommand {
   const { frontmatter, body } = parseCommandContent(file.content);
   return {
@@ -96,6 +98,7 @@ function parseSlashCommand(file: {
     description: frontmatter?.description || extractDescription(body),
     body,
     isFavorite: false,
+    version: file.version,
   };
 }
 
@@ -258,17 +261,26 @@ export function useSlashCommands(options: UseSlashCommandsOptions): UseSlashComm
         // Serialize content with frontmatter
         const serializedContent = serializeCommandContent(commandToSave);
 
-        const absolutePath = await writeSlashCommand(
+        // Only a command saved back to its own file has a version to check against
+        const previous = commandsRef.current.find(cmd => cmd.id === commandToSave.id);
+        const expected =
+          previous && previous.scope === commandToSave.scope && previous.name === commandToSave.name
+            ? previous.version
+            : undefined;
+        const saved = await writeSlashCommand(
           scope,
           commandToSave.name,
           serializedContent,
-          projectPath
+          projectPath,
+          expected
         );
+        const absolutePath = saved.path;
 
         const persistedCommand: SlashCommand = {
           ...commandToSave,
           id: absolutePath,
           path: absolutePath,
+          version: saved.version,
           description: commandToSave.frontmatter?.description || extractDescription(commandToSave.body),
         };
 
@@ -294,10 +306,7 @@ export function useSlashCommands(options: UseSlashCommandsOptions): UseSlashComm
         showToast('success', `Command "/${commandToSave.name}" saved successfully`);
       } catch (error) {
         devLog.error('Error saving command:', error);
-        showToast(
-          'error',
-          `Failed to save command: ${error instanceof Error ? error.message : 'Unknown error'}`
-        );
+        showToast('error', `Failed to save command: ${getErrorMessage(error)}`);
       }
     },
     [showToast]
@@ -3,6 +3,7 @@ import { Agent, AgentScope, LoadAgentsOptions, ScanSettings, Skill } from '../ty
 import { getStorageItem, removeStorageItem, setStorageItem } from '../utils/storage';
 import { devLog } from '../utils/devLogger';
 import {
+  AgentFile,
   listAgents,
   writeAgent,
   deleteAgent as deleteAgentFile,
@@ -21,10 +22,17 @@ import { DEFAULT_SCAN_SETTINGS } from './useScanSettings';
 import { ToastType } from '../components/Toast';
 import { ResourceType } from '../types/resource';
 import { saveFavorites, getFavorites } from '../utils/resourceOperations';
+import { getErrorMessage } from '../utils/errorHandler';
 
 const AGENT_CACHE_KEY = 'vinsly-agent-cache';
 const SKILL_CACHE_KEY = 'vinsly-skill-cache';
 
+// Keep the version the file was listed with so saving it can detect outside edits
+const agentFromFile = (file: AgentFile, scope: AgentScope): Agent | null => {
+  const agent = markdownToAgent(file.content, file.name, scope, file.path);
+  return agent ? { ...agent, version: file.version } : null;
+};
+
 const normalizeProjectRootPath = (input?: string | null): string | null => {
   if (!input) {
     return null;
@@ -430,14 +438,7 @@ export function useWorkspace(options: UseWorkspaceOptions): UseWorkspaceResult {
           if (includeGlobal) {
             const globalAgents = await listAgents('global');
             for (const agentFile of globalAgents) {
-              addAgent(
-                markdownToAgent(
-                  agentFile.content,
-                  agentFile.name,
-                  AgentScope.Global,
-                  agentFile.path
-                )
-              );
+              addAgent(agentFromFile(agentFile, AgentScope.Global));
             }
 
             const globalSkills = await listSkills('global');
@@ -456,14 +457,7 @@ export function useWorkspace(options: UseWorkspaceOptions): UseWorkspaceResult {
                 ]);
 
                 for (const agentFile of projectAgents) {
-                  addAgent(
-                    markdownToAgent(
-                      agentFile.content,
-                      agentFile.name,
-                      AgentScope.Project,
-                      agentFile.path
-                    )
-                  );
+                  addAgent(agentFromFile(agentFile, AgentScope.Project));
                 }
 
                 for (const skillFile of projectSkills) {
@@ -485,14 +479,7 @@ export function useWorkspace(options: UseWorkspaceOptions): UseWorkspaceResult {
                 ]);
 
                 for (const agentFile of watchedAgents) {
-                  addAgent(
-                    markdownToAgent(
-                      agentFile.content,
-                      agentFile.name,
-                      AgentScope.Project,
-                      agentFile.path
-                    )
-                  );
+                  addAgent(agentFromFile(agentFile, AgentScope.Project));
                 }
 
                 for (const skillFile of watchedSkills) {
@@ -577,11 +564,19 @@ export function useWorkspace(options: UseWorkspaceOptions): UseWorkspaceResult {
           throw new Error('Select a project folder before saving a project agent.');
         }
 
-        const absolutePath = await writeAgent(scope, agentToSave.name, markdown, projectPath);
+        // Only an agent saved back to its own file has a version to check against
+        const previous = agentsRef.current.find(agent => agent.id === agentToSave.id);
+        const expected =
+          previous && previous.scope === agentToSave.scope && previous.name === agentToSave.name
+            ? previous.version
+            : undefined;
+        const saved = await writeAgent(scope, agentToSave.name, markdown, projectPath, expected);
+        const absolutePath = saved.path;
         const persistedAgent: Agent = {
           ...agentToSave,
           id: absolutePath || displayPath,
           path: absolutePath || displayPath,
+          version: saved.version,
         };
 
         setAgents(prev => {
@@ -603,10 +598,7 @@ export function useWorkspace(options: UseWorkspaceOptions): UseWorkspaceResult {
         options.showToast('success', `Agent "${agentToSave.name}" saved successfully`);
       } catch (error) {
         devLog.error('Error saving agent:', error);
-        options.showToast(
-          'error',
-          `Failed to save agent: ${error instanceof Error ? error.message : 'Unknown error'}`
-        );
+        options.showToast('error', `Failed to save agent: ${getErrorMessage(error)}`);
       }
     },
     [options.showToast]
@@ -716,11 +708,12 @@ export function useWorkspace(options: UseWorkspaceOptions): UseWorkspaceResult {
             continue;
           }
 
-          const absolutePath = await writeAgent(scope, agent.name, markdown, projectPathForImport);
+          const saved = await writeAgent(scope, agent.name, markdown, projectPathForImport);
           persistedAgents.push({
             ...agent,
-            id: absolutePath,
-            path: absolutePath,
+            id: saved.path,
+            path: saved.path,
+            version: saved.version,
           });
         }
 
@@ -97,7 +97,7 @@ export function createMockSkills(count: number, overrides?: Partial<Skill>): Ski
  */
 export const mockTauriCommands = {
   listAgents: jest.fn().mockResolvedValue([]),
-  writeAgent: jest.fn().mockResolvedValue('/path/to/agent.md'),
+  writeAgent: jest.fn().mockResolvedValue({ path: '/path/to/agent.md', version: { hash: 'abc', mtime_ms: 1 } }),
   deleteAgent: jest.fn().mockResolvedValue(undefined),
   listSkills: jest.fn().mockResolvedValue([]),
   writeSkill: jest.fn().mockResolvedValue('/path/to/skill/SKILL.md'),
@@ -23,6 +23,13 @@ export interface Tool {
   risk: ToolRisk;
 }
 
+// Hash and mtime of a file as last read or written; sent back on save so edits made
+// by another program are reported as a conflict instead of being overwritten
+export interface FileVersion {
+  hash: string;
+  mtime_ms: number | null;
+}
+
 export interface Agent {
   id: string;
   name: string;
@@ -40,6 +47,7 @@ export interface Agent {
   };
   body: string;
   isFavorite?: boolean;
+  version?: FileVersion;
 }
 
 export interface Skill {
@@ -69,6 +77,7 @@ export interface ClaudeMemory {
   path: string;         // Full file path
   content: string;      // Raw markdown content
   exists: boolean;      // Whether file exists on disk
+  version?: FileVersion | null;
   lastModified?: Date;
   isFavorite?: boolean;
 }
@@ -89,6 +98,7 @@ export interface SlashCommand {
   description?: string; // First line or extracted summary (legacy, prefer frontmatter)
   body: string;         // Markdown content after frontmatter
   isFavorite?: boolean;
+  version?: FileVersion;
 }
 
 // Scan Settings Types
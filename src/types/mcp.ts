@@ -26,6 +26,10 @@ export interface MCPHealthResult {
   status: MCPServerStatus;
   latencyMs?: number;
   errorMessage?: string;
+  protocolVersion?: string;               // From the server's initialize result
+  serverInfo?: { name: string; version: string; title?: string };
+  capabilities?: Record<string, unknown>;
+  unresolvedVariables?: string[];         // ${VAR} references that kept the server from starting
   checkedAt: number;
 }
 
@@ -89,6 +93,7 @@ export interface MCPServer {
   env?: Record<string, string>;           // Environment variables
   scope: MCPScope;                        // Where it's configured
   sourcePath: string;                     // File path it came from
+  projectPath?: string;                   // Owning project for project/local scope
   enabled: boolean;                       // Whether it's enabled in settings
   isFavorite?: boolean;                   // User favorite
 }
@@ -175,9 +180,10 @@ export function getMCPConfigPath(scope: MCPScope, projectPath?: string): string
     case 'project':
       return projectPath ? `${projectPath}/.mcp.json` : '.mcp.json';
     case 'local':
+      // Local servers live in this project's entry inside ~/.claude.json
       return projectPath
-        ? `${projectPath}/.claude/settings.local.json`
-        : '.claude/settings.local.json';
+        ? `~/.claude.json (projects["${projectPath}"])`
+        : '~/.claude.json (projects)';
     default:
       return '';
   }
@@ -40,6 +40,10 @@ export function getErrorMessage(error: unknown): string {
   if (typeof error === 'string') {
     return error;
   }
+  // Structured rejections from Rust commands ({ kind, message, ... })
+  if (error && typeof error === 'object' && typeof (error as { message?: unknown }).message === 'string') {
+    return (error as { message: string }).message;
+  }
   return 'Unknown error';
 }
 
@@ -1,12 +1,77 @@
 import { invoke } from '@tauri-apps/api/core';
 
+export interface AgentDefinitionRaw {
+  name: string;
+  description: string;
+  tools: string[] | null;
+  model: string | null;
+  color: string | null;
+  extra: Record<string, unknown>;
+  body: string;
+}
+
 export interface AgentFile {
   name: string;
   path: string;
   content: string;
   scope: string;
+  definition?: AgentDefinitionRaw | null;   // null when the file fails validation
+  version: FileVersionRaw;                  // Pass back as `expected` when saving
+}
+
+export type AgentValidationErrorRaw =
+  | { kind: 'malformed_frontmatter'; message: string }
+  | { kind: 'missing_field'; field: string }
+  | { kind: 'invalid_field'; field: string; message: string }
+  | { kind: 'unknown_model'; model: string; allowed: string[] }
+  | { kind: 'duplicate_tool'; tool: string };
+
+// Content hash (hex SHA-256) and mtime of a file on disk
+export interface FileVersionRaw {
+  hash: string;
+  mtime_ms: number | null;
+}
+
+// Passed to writers to refuse the write if the file changed since it was loaded
+export interface ExpectedVersionRaw {
+  hash?: string | null;
+  mtime_ms?: number | null;
+}
+
+// Resolved value of writers that report the version they left on disk
+export interface SavedFileRaw {
+  path: string;
+  version: FileVersionRaw;
+}
+
+export interface WriteConflictRaw {
+  kind: 'conflict';
+  message: string;
+  path: string;
+  current_content: string | null;   // null when the file was deleted
+  current_version: FileVersionRaw | null;
+}
+
+// Rejection payload of every resource writer that accepts `expected`
+export type WriteErrorRaw = WriteConflictRaw | { kind: 'io'; message: string };
+
+// Rejection payload of write_agent
+export type AgentWriteErrorRaw =
+  | { kind: 'invalid'; message: string; errors: AgentValidationErrorRaw[] }
+  | WriteErrorRaw;
+
+// A problem with one value of an MCP config file
+export interface MCPDiagnosticRaw {
+  pointer: string;                           // JSON pointer into the config file
+  severity: 'error' | 'warning';
+  message: string;
 }
 
+// Rejection payload of add_mcp_server and write_mcp_config
+export type MCPWriteErrorRaw =
+  | { kind: 'invalid'; message: string; diagnostics: MCPDiagnosticRaw[] }
+  | WriteErrorRaw;
+
 export interface SkillFile {
   name: string;
   directory: string;
@@ -26,14 +91,15 @@ export async function readAgent(path: string): Promise<string> {
   return await invoke<string>('read_agent', { path });
 }
 
-// Write an agent file
+// Write an agent file (rejects with AgentWriteErrorRaw)
 export async function writeAgent(
   scope: 'project' | 'global',
   name: string,
   content: string,
-  projectPath?: string
-): Promise<string> {
-  return await invoke<string>('write_agent', { scope, name, content, projectPath, project_path: projectPath });
+  projectPath?: string,
+  expected?: ExpectedVersionRaw
+): Promise<SavedFileRaw> {
+  return await invoke<SavedFileRaw>('write_agent', { scope, name, content, projectPath, project_path: projectPath, expected });
 }
 
 // Delete an agent file
@@ -50,13 +116,15 @@ export async function listSkills(scope: 'project' | 'global', projectPath?: stri
   return await invoke<SkillFile[]>('list_skills', { scope, projectPath, project_path: projectPath });
 }
 
+// Rejects with WriteErrorRaw
 export async function writeSkill(
   scope: 'project' | 'global',
   name: string,
   content: string,
-  projectPath?: string
+  projectPath?: string,
+  expected?: ExpectedVersionRaw
 ): Promise<string> {
-  return await invoke<string>('write_skill', { scope, name, content, projectPath, project_path: projectPath });
+  return await invoke<string>('write_skill', { scope, name, content, projectPath, project_path: projectPath, expected });
 }
 
 export async function deleteSkill(path: string): Promise<void> {
@@ -156,6 +224,7 @@ export interface ClaudeMemoryFile {
   path: string;
   content: string;
   exists: boolean;
+  version: FileVersionRaw | null;   // null when the file does not exist
 }
 
 export async function readClaudeMemory(
@@ -169,16 +238,19 @@ export async function readClaudeMemory(
   });
 }
 
+// Rejects with WriteErrorRaw
 export async function writeClaudeMemory(
   scope: 'project' | 'global',
   content: string,
-  projectPath?: string
-): Promise<string> {
-  return await invoke<string>('write_claude_memory', {
+  projectPath?: string,
+  expected?: ExpectedVersionRaw
+): Promise<SavedFileRaw> {
+  return await invoke<SavedFileRaw>('write_claude_memory', {
     scope,
     content,
     projectPath,
     project_path: projectPath,
+    expected,
   });
 }
 
@@ -202,6 +274,7 @@ export interface SlashCommandFile {
   path: string;
   content: string;
   scope: string;
+  version: FileVersionRaw;   // Pass back as `expected` when saving
 }
 
 export async function listSlashCommands(
@@ -219,18 +292,21 @@ export async function readSlashCommand(path: string): Promise<string> {
   return await invoke<string>('read_slash_command', { path });
 }
 
+// Rejects with WriteErrorRaw
 export async function writeSlashCommand(
   scope: 'project' | 'global',
   name: string,
   content: string,
-  projectPath?: string
-): Promise<string> {
-  return await invoke<string>('write_slash_command', {
+  projectPath?: string,
+  expected?: ExpectedVersionRaw
+): Promise<SavedFileRaw> {
+  return await invoke<SavedFileRaw>('write_slash_command', {
     scope,
     name,
     content,
     projectPath,
     project_path: projectPath,
+    expected,
   });
 }
 
@@ -303,10 +379,12 @@ export interface MCPServerConfigRaw {
   args?: string[];
   headers?: Record<string, string>;
   env?: Record<string, string>;
+  [key: string]: unknown;   // fields Vinsly doesn't model (alwaysAllow, timeout, ...) are passed through
 }
 
 export interface MCPConfigFileRaw {
   mcpServers: Record<string, MCPServerConfigRaw>;
+  [key: string]: unknown;   // rest of ~/.claude.json, preserved on write
 }
 
 export interface MCPServerInfoRaw {
@@ -319,10 +397,11 @@ export interface MCPServerInfoRaw {
   env?: Record<string, string>;
   scope: string;
   source_path: string;
+  project_path?: string | null;   // set for project and local scope
   enabled: boolean;
 }
 
-// List all MCP servers from user and project scopes
+// List all MCP servers from user, project and local scopes
 export async function listMCPServers(projectPath?: string): Promise<MCPServerInfoRaw[]> {
   return await invoke<MCPServerInfoRaw[]>('list_mcp_servers', {
     projectPath,
@@ -342,26 +421,30 @@ export async function readMCPConfig(
   });
 }
 
-// Write MCP config to a specific scope
+// Write MCP config to a specific scope (rejects with MCPWriteErrorRaw)
 export async function writeMCPConfig(
   scope: 'user' | 'project' | 'local',
   config: MCPConfigFileRaw,
-  projectPath?: string
+  projectPath?: string,
+  expected?: ExpectedVersionRaw
 ): Promise<string> {
   return await invoke<string>('write_mcp_config', {
     scope,
     config,
     projectPath,
     project_path: projectPath,
+    expected,
   });
 }
 
-// Add a single MCP server to a config
+// Add a single MCP server to a config (rejects with MCPWriteErrorRaw)
+// Pass overwrite: false to fail instead of replacing a server with the same name
 export async function addMCPServer(
   scope: 'user' | 'project' | 'local',
   name: string,
   serverConfig: MCPServerConfigRaw,
-  projectPath?: string
+  projectPath?: string,
+  overwrite?: boolean
 ): Promise<string> {
   return await invoke<string>('add_mcp_server', {
     scope,
@@ -370,9 +453,97 @@ export async function addMCPServer(
     server_config: serverConfig,
     projectPath,
     project_path: projectPath,
+    overwrite,
+  });
+}
+
+export type MCPClient = 'claude_desktop' | 'cursor' | 'vscode' | 'windsurf';
+
+// Another client's MCP config file in its standard location
+export interface MCPImportSourceRaw {
+  client: MCPClient;
+  path: string;
+  project: boolean;                          // .cursor/mcp.json or .vscode/mcp.json in the project
+  exists: boolean;
+}
+
+export interface DiffLineRaw {
+  kind: 'context' | 'added' | 'removed';
+  text: string;
+}
+
+export interface MCPImportCandidateRaw {
+  name: string;
+  config: MCPServerConfigRaw;
+  status: 'new' | 'unchanged' | 'conflict';  // Compared with the target scope
+  diff: DiffLineRaw[];                       // Existing entry against the imported one
+  warnings: string[];
+}
+
+export interface MCPImportPreviewRaw {
+  client: MCPClient;
+  source_path: string;
+  scope: string;
+  servers: MCPImportCandidateRaw[];
+  skipped: string[];                         // "name: reason" for entries that couldn't be mapped
+}
+
+// List Claude Desktop, Cursor, VS Code and Windsurf config locations
+export async function listMCPImportSources(projectPath?: string): Promise<MCPImportSourceRaw[]> {
+  return await invoke<MCPImportSourceRaw[]>('list_mcp_import_sources', {
+    projectPath,
+    project_path: projectPath,
   });
 }
 
+// Preview importing a client config (a listed source or a user-picked file) into a scope.
+// Write the chosen servers with addMCPServer(..., false) so conflicts are never overwritten
+export async function previewMCPImport(
+  client: MCPClient,
+  path: string,
+  scope: 'user' | 'project' | 'local',
+  projectPath?: string
+): Promise<MCPImportPreviewRaw> {
+  return await invoke<MCPImportPreviewRaw>('preview_mcp_import', {
+    client,
+    path,
+    scope,
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// Result of writing servers into another client's config file
+export interface MCPExportReportRaw {
+  path: string;
+  written: string[];
+  replaced: string[];                        // Already in the file and written over
+  skipped: string[];                         // "name: reason" for servers the client can't run
+  warnings: string[];
+}
+
+// Merge servers from listMCPServers into a client's config file (a listed source path or
+// a user-picked one); unknown fields already in the file are kept
+export async function exportMCPServers(
+  client: MCPClient,
+  servers: MCPServerInfoRaw[],
+  path: string,
+  projectPath?: string
+): Promise<MCPExportReportRaw> {
+  return await invoke<MCPExportReportRaw>('export_mcp_servers', {
+    client,
+    servers,
+    path,
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// Shell script of `claude mcp add-json` commands recreating the servers in their scopes
+export async function getMCPAddJsonScript(servers: MCPServerInfoRaw[]): Promise<string> {
+  return await invoke<string>('get_mcp_add_json_script', { servers });
+}
+
 // Remove an MCP server from a config
 export async function removeMCPServer(
   scope: 'user' | 'project' | 'local',
@@ -387,6 +558,22 @@ export async function removeMCPServer(
   });
 }
 
+// Turn an MCP server off without deleting it, or back on
+export async function setMCPServerEnabled(
+  scope: 'user' | 'project' | 'local',
+  name: string,
+  enabled: boolean,
+  projectPath?: string
+): Promise<string> {
+  return await invoke<string>('set_mcp_server_enabled', {
+    scope,
+    name,
+    enabled,
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
 // Get environment variable values for specified names
 export async function getEnvVars(
   varNames: string[]
@@ -397,21 +584,176 @@ export async function getEnvVars(
   });
 }
 
+// ${VAR} references of a configured server that expand to nothing
+export interface MCPUnresolvedVariablesRaw {
+  scope: string;
+  name: string;
+  variables: string[];
+}
+
+// Report unresolved ${VAR} references per server, across the scopes of listMCPServers
+export async function getMCPUnresolvedVariables(
+  projectPath?: string
+): Promise<MCPUnresolvedVariablesRaw[]> {
+  return await invoke<MCPUnresolvedVariablesRaw[]>('get_mcp_unresolved_variables', {
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+export type MCPApprovalStatus = 'approved' | 'pending' | 'rejected';
+
+// A definition of a server name that Claude Code does not use
+export interface MCPShadowedDefinitionRaw {
+  scope: string;
+  source_path: string;
+  approval: MCPApprovalStatus | null;        // Project scope only
+}
+
+// The definition Claude Code uses for a server name in a project
+export interface MCPEffectiveServerRaw {
+  name: string;
+  scope: string;
+  source_path: string;
+  config: MCPServerConfigRaw;
+  approval: MCPApprovalStatus | null;        // Project scope only
+  active: boolean;                           // False for an unapproved project server
+  shadowed: MCPShadowedDefinitionRaw[];      // Broadest scope first
+}
+
+// Resolve the servers Claude Code would use in a project across user, project and local scope
+export async function getEffectiveMCPServers(projectPath: string): Promise<MCPEffectiveServerRaw[]> {
+  return await invoke<MCPEffectiveServerRaw[]>('get_effective_mcp_servers', {
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// How a project's .mcp.json servers have been answered
+export interface MCPProjectApprovalsRaw {
+  enable_all: boolean;                       // enableAllProjectMcpServers
+  servers: { name: string; approval: MCPApprovalStatus }[];
+}
+
+export async function getMCPJsonApprovals(projectPath: string): Promise<MCPProjectApprovalsRaw> {
+  return await invoke<MCPProjectApprovalsRaw>('get_mcpjson_approvals', {
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// Approve or reject a .mcp.json server before Claude Code asks, or reset it to 'pending'
+// (rejects with WriteErrorRaw)
+export async function setMCPJsonServerApproval(
+  projectPath: string,
+  name: string,
+  approval: MCPApprovalStatus
+): Promise<void> {
+  return await invoke('set_mcpjson_server_approval', {
+    projectPath,
+    project_path: projectPath,
+    name,
+    approval,
+  });
+}
+
+// Approve every current and future .mcp.json server of a project (rejects with WriteErrorRaw)
+export async function setEnableAllProjectMCPServers(
+  projectPath: string,
+  enabled: boolean
+): Promise<void> {
+  return await invoke('set_enable_all_project_mcp_servers', {
+    projectPath,
+    project_path: projectPath,
+    enabled,
+  });
+}
+
+// Diagnostics for one server, or for a whole file when name is null
+export interface MCPServerDiagnosticsRaw {
+  scope: string;
+  name: string | null;
+  source_path: string;
+  pointer: string;
+  diagnostics: MCPDiagnosticRaw[];
+}
+
+// Validate every server, including entries too malformed to be listed
+export async function validateMCPServers(projectPath?: string): Promise<MCPServerDiagnosticsRaw[]> {
+  return await invoke<MCPServerDiagnosticsRaw[]>('validate_mcp_servers', {
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// A header or env value that looks like a plaintext credential
+export interface MCPSecretCandidateRaw {
+  scope: string;
+  name: string;                              // Server name
+  field: 'header' | 'env';
+  key: string;
+}
+
+// Find Authorization headers and *_KEY / *_TOKEN env values stored in plaintext
+export async function detectMCPSecrets(projectPath?: string): Promise<MCPSecretCandidateRaw[]> {
+  return await invoke<MCPSecretCandidateRaw[]>('detect_mcp_secrets', {
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// Move a header or env value to the OS keychain, leaving a ${VINSLY_…} reference.
+// Resolves to the reference name (rejects with WriteErrorRaw)
+export async function moveMCPSecretToKeychain(
+  scope: 'user' | 'project' | 'local',
+  name: string,
+  field: 'header' | 'env',
+  key: string,
+  projectPath?: string
+): Promise<string> {
+  return await invoke<string>('move_mcp_secret_to_keychain', {
+    scope,
+    name,
+    field,
+    key,
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// Shell profile lines that export the keychain secrets for Claude Code started elsewhere
+export async function getMCPSecretsLauncherScript(): Promise<string> {
+  return await invoke<string>('get_mcp_secrets_launcher_script');
+}
+
 // Result of an MCP server health check
+export interface MCPServerInfoDetailsRaw {
+  name: string;
+  version: string;
+  title?: string;
+}
+
 export interface MCPHealthCheckResultRaw {
   server_name: string;
   status: string;
   latency_ms: number | null;
   error_message: string | null;
+  // Set when the initialize handshake succeeded
+  protocol_version: string | null;
+  server_info: MCPServerInfoDetailsRaw | null;
+  capabilities: Record<string, unknown> | null;
+  unresolved_variables: string[];            // ${VAR} references without a value
 }
 
-// Check the health/connectivity of an MCP server
+// Check an MCP server by performing the initialize handshake.
+// serverConfig supplies args, env and headers for the probe.
 export async function checkMCPServerHealth(
   serverType: string,
   serverName: string,
   url?: string,
   command?: string,
-  timeoutMs: number = 5000
+  timeoutMs: number = 5000,
+  serverConfig?: MCPServerConfigRaw
 ): Promise<MCPHealthCheckResultRaw> {
   return await invoke<MCPHealthCheckResultRaw>('check_mcp_server_health', {
     serverType,
@@ -420,6 +762,117 @@ export async function checkMCPServerHealth(
     server_name: serverName,
     url,
     command,
+    serverConfig,
+    server_config: serverConfig,
+    timeoutMs,
+    timeout_ms: timeoutMs,
+  });
+}
+
+// Shapes below follow the MCP spec's tools/list, resources/list and prompts/list results
+export interface MCPToolRaw {
+  name: string;
+  title?: string;
+  description?: string;
+  inputSchema: Record<string, unknown>;
+  outputSchema?: Record<string, unknown>;
+  annotations?: Record<string, unknown>;
+}
+
+export interface MCPResourceRaw {
+  uri: string;
+  name: string;
+  title?: string;
+  description?: string;
+  mimeType?: string;
+}
+
+export interface MCPResourceTemplateRaw {
+  uriTemplate: string;
+  name: string;
+  title?: string;
+  description?: string;
+  mimeType?: string;
+}
+
+export interface MCPPromptRaw {
+  name: string;
+  title?: string;
+  description?: string;
+  arguments?: { name: string; description?: string; required?: boolean }[];
+}
+
+export interface MCPInventoryRaw {
+  server: {
+    protocol_version: string;
+    server_info: MCPServerInfoDetailsRaw;
+    capabilities: Record<string, unknown>;
+    instructions: string | null;
+  };
+  tools: MCPToolRaw[];
+  resources: MCPResourceRaw[];
+  resource_templates: MCPResourceTemplateRaw[];
+  prompts: MCPPromptRaw[];
+  errors: Record<string, string>;            // list method -> error, for lists that failed
+  fetched_at_ms: number;
+}
+
+// Rejection payload of commands that talk to an MCP server
+export type MCPErrorRaw =
+  | { kind: 'transport'; message: string }
+  | { kind: 'timeout'; message: string }
+  | { kind: 'unauthorized'; message: string; www_authenticate: string | null }
+  | { kind: 'rpc'; message: string; code: number; data: unknown };
+
+// List what a configured server exposes. Cached per server until refresh is set
+// or its config changes. Rejects with MCPErrorRaw.
+export async function getMCPServerInventory(
+  scope: 'user' | 'project' | 'local',
+  name: string,
+  projectPath?: string,
+  refresh: boolean = false,
+  timeoutMs?: number
+): Promise<MCPInventoryRaw> {
+  return await invoke<MCPInventoryRaw>('get_mcp_server_inventory', {
+    scope,
+    name,
+    projectPath,
+    project_path: projectPath,
+    refresh,
+    timeoutMs,
+    timeout_ms: timeoutMs,
+  });
+}
+
+export interface MCPToolCallOutcomeRaw {
+  result: Record<string, unknown> | null;   // raw tools/call result
+  error: MCPErrorRaw | null;                // set when no result was produced
+  is_tool_error: boolean;                   // result.isError
+  connect_ms: number;
+  initialize_ms: number | null;
+  call_ms: number | null;
+  total_ms: number;
+}
+
+// Call one tool of a configured server with JSON arguments. Uses the server's env,
+// headers and stored OAuth token. Failures talking to the server are reported in the
+// outcome; a missing or unresolvable config rejects with MCPErrorRaw.
+export async function callMCPTool(
+  scope: 'user' | 'project' | 'local',
+  name: string,
+  toolName: string,
+  args: Record<string, unknown>,
+  projectPath?: string,
+  timeoutMs?: number
+): Promise<MCPToolCallOutcomeRaw> {
+  return await invoke<MCPToolCallOutcomeRaw>('call_mcp_tool', {
+    scope,
+    name,
+    toolName,
+    tool_name: toolName,
+    arguments: args,
+    projectPath,
+    project_path: projectPath,
     timeoutMs,
     timeout_ms: timeoutMs,
   });
@@ -488,6 +941,29 @@ export async function openOAuthUrl(url: string): Promise<void> {
   return await invoke<void>('open_oauth_url', { url });
 }
 
+// Payload of the 'mcp-oauth:refreshed' and 'mcp-oauth:relogin-required' events
+export interface MCPTokenEvent {
+  server_name: string;
+  expires_at?: number;                       // Unix seconds
+  message?: string;                          // Why a new sign-in is needed
+}
+
+// Run the full OAuth flow for a remote MCP server (discovery, client registration,
+// browser sign-in and PKCE code exchange); the tokens end up in the OS keychain
+export async function authorizeMCPServer(
+  serverName: string,
+  serverUrl: string,
+  scope?: string
+): Promise<void> {
+  return await invoke<void>('authorize_mcp_server', {
+    serverName,
+    server_name: serverName,
+    serverUrl,
+    server_url: serverUrl,
+    scope: scope ?? null,
+  });
+}
+
 // ============================================================================
 // Hooks Commands
 // ============================================================================
@@ -500,8 +976,24 @@ export interface HookConfigRaw {
   timeout?: number;                          // Timeout in seconds
 }
 
+// One entry of a matcher group's `hooks` array
+export interface HookCommandRaw {
+  type: 'command' | 'prompt';
+  command?: string;
+  prompt?: string;
+  timeout?: number;
+  [key: string]: unknown;
+}
+
+// Claude Code's settings.json shape: hooks.<Event>[] = { matcher, hooks: [...] }
+export interface HookMatcherGroupRaw {
+  matcher?: string;
+  hooks: HookCommandRaw[];
+  [key: string]: unknown;
+}
+
 export interface HooksConfigFileRaw {
-  hooks: Record<string, HookConfigRaw[]>;
+  hooks: Record<string, HookMatcherGroupRaw[]>;
 }
 
 export interface HookInfoRaw {
@@ -538,21 +1030,23 @@ export async function readHooksConfig(
   });
 }
 
-// Write hooks config to a specific scope
+// Write hooks config to a specific scope (rejects with WriteErrorRaw)
 export async function writeHooksConfig(
   scope: 'user' | 'project' | 'local',
   config: HooksConfigFileRaw,
-  projectPath?: string
+  projectPath?: string,
+  expected?: ExpectedVersionRaw
 ): Promise<string> {
   return await invoke<string>('write_hooks_config', {
     scope,
     config,
     projectPath,
     project_path: projectPath,
+    expected,
   });
 }
 
-// Add a hook to a specific event type
+// Add a hook to a specific event type; resolves to the new hook's id
 export async function addHook(
   scope: 'user' | 'project' | 'local',
   eventType: string,
@@ -570,19 +1064,73 @@ export async function addHook(
   });
 }
 
-// Remove a hook by event type and index
-export async function removeHook(
+// Replace a hook by id; resolves to its new id.
+// Rejects if the id no longer matches a hook in that scope.
+export async function updateHook(
   scope: 'user' | 'project' | 'local',
+  hookId: string,
   eventType: string,
-  hookIndex: number,
+  hookConfig: HookConfigRaw,
+  projectPath?: string
+): Promise<string> {
+  return await invoke<string>('update_hook', {
+    scope,
+    hookId,
+    hook_id: hookId,
+    eventType,
+    event_type: eventType,
+    hookConfig,
+    hook_config: hookConfig,
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// Remove a hook by id
+export async function removeHook(
+  scope: 'user' | 'project' | 'local',
+  hookId: string,
   projectPath?: string
 ): Promise<string> {
   return await invoke<string>('remove_hook', {
+    scope,
+    hookId,
+    hook_id: hookId,
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// Turn a hook off without deleting it, or back on; resolves to the hook's id afterwards
+export async function setHookEnabled(
+  scope: 'user' | 'project' | 'local',
+  hookId: string,
+  enabled: boolean,
+  projectPath?: string
+): Promise<string> {
+  return await invoke<string>('set_hook_enabled', {
+    scope,
+    hookId,
+    hook_id: hookId,
+    enabled,
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// Reorder the hooks of one event; hookIds must list every hook of that event
+export async function reorderHooks(
+  scope: 'user' | 'project' | 'local',
+  eventType: string,
+  hookIds: string[],
+  projectPath?: string
+): Promise<string> {
+  return await invoke<string>('reorder_hooks', {
     scope,
     eventType,
     event_type: eventType,
-    hookIndex,
-    hook_index: hookIndex,
+    hookIds,
+    hook_ids: hookIds,
     projectPath,
     project_path: projectPath,
   });
@@ -622,6 +1170,239 @@ export async function getSessionTokenUsage(workingDirectory: string, sessionStar
   });
 }
 
+// ============================================================================
+// Session Transcripts
+// ============================================================================
+
+export interface TokenUsageRaw {
+  input_tokens: number;
+  output_tokens: number;
+  cache_creation_input_tokens: number;
+  cache_read_input_tokens: number;
+}
+
+export type TranscriptRole = 'user' | 'assistant' | 'system';
+
+export type ContentBlockRaw =
+  | { type: 'text'; text: string }
+  | { type: 'thinking'; thinking: string }
+  | { type: 'tool_use'; id: string; name: string; input: unknown; result_index: number | null }
+  | { type: 'tool_result'; tool_use_id: string; tool_name: string | null; content: string; is_error: boolean }
+  | { type: 'image'; media_type: string | null }
+  | { type: 'other'; kind: string };
+
+export interface TranscriptMessageRaw {
+  index: number;                             // Position in the full session, sidechains included
+  role: TranscriptRole;
+  uuid: string | null;
+  parent_uuid: string | null;
+  timestamp: string | null;
+  model: string | null;
+  message_id: string | null;
+  content: ContentBlockRaw[];
+  usage: TokenUsageRaw | null;
+  is_sidechain: boolean;
+  agent_id: string | null;
+  is_meta: boolean;
+}
+
+export interface SessionSummaryRaw {
+  session_id: string;
+  path: string;
+  title: string | null;
+  first_prompt: string | null;
+  cwd: string | null;
+  git_branch: string | null;
+  started_at: string | null;
+  last_activity_at: string | null;
+  message_count: number;
+  sidechain_message_count: number;
+  has_subagents: boolean;
+  modified: number;
+  size: number;
+}
+
+export interface SessionPageRaw {
+  session_id: string;
+  total: number;
+  offset: number;
+  next_offset: number | null;                // Offset of the next page, null on the last one
+  messages: TranscriptMessageRaw[];
+  skipped_lines: number;
+}
+
+// List the Claude Code sessions recorded for a project, most recently active first
+export async function listClaudeSessions(projectPath: string): Promise<SessionSummaryRaw[]> {
+  return await invoke<SessionSummaryRaw[]>('list_claude_sessions', {
+    projectPath,
+    project_path: projectPath,
+  });
+}
+
+// Page through one session's messages from index offset on; subagent messages only with includeSidechains
+export async function getClaudeSessionMessages(
+  projectPath: string,
+  sessionId: string,
+  offset?: number,
+  limit?: number,
+  includeSidechains?: boolean
+): Promise<SessionPageRaw> {
+  return await invoke<SessionPageRaw>('get_claude_session_messages', {
+    projectPath,
+    project_path: projectPath,
+    sessionId,
+    session_id: sessionId,
+    offset,
+    limit,
+    includeSidechains,
+    include_sidechains: includeSidechains,
+  });
+}
+
+// One session message by a search hit's message_index, sidechain messages included
+export async function getClaudeSessionMessage(
+  projectPath: string,
+  sessionId: string,
+  index: number
+): Promise<TranscriptMessageRaw> {
+  return await invoke<TranscriptMessageRaw>('get_claude_session_message', {
+    projectPath,
+    project_path: projectPath,
+    sessionId,
+    session_id: sessionId,
+    index,
+  });
+}
+
+// USD per million tokens
+export interface ModelPriceRaw {
+  input: number;
+  output: number;
+  cache_write: number;
+  cache_read: number;
+}
+
+// Keyed by model ID prefix; the longest matching prefix wins
+export type ModelPriceTable = Record<string, ModelPriceRaw>;
+
+export interface UsageTotalsRaw extends TokenUsageRaw {
+  messages: number;
+  cost_usd: number;                          // Excludes tokens of unpriced models
+}
+
+export interface SessionUsageRaw {
+  session_id: string;
+  project_dir: string;
+  cwd: string | null;
+  first_day: string;
+  last_day: string;
+  totals: UsageTotalsRaw;
+  by_model: Record<string, UsageTotalsRaw>;
+}
+
+export interface ProjectUsageRaw {
+  project_dir: string;
+  cwd: string | null;
+  sessions: number;
+  totals: UsageTotalsRaw;
+  by_model: Record<string, UsageTotalsRaw>;
+}
+
+export interface UsageReportRaw {
+  totals: UsageTotalsRaw;
+  by_model: Record<string, UsageTotalsRaw>;  // Most expensive first
+  by_day: Record<string, UsageTotalsRaw>;    // YYYY-MM-DD, oldest first
+  sessions: SessionUsageRaw[];
+  projects: ProjectUsageRaw[];
+  unpriced_models: string[];
+}
+
+// Token usage and estimated cost; every project when projectPath is omitted
+export async function getTokenUsageReport(
+  projectPath?: string,
+  since?: string,
+  until?: string
+): Promise<UsageReportRaw> {
+  return await invoke<UsageReportRaw>('get_token_usage_report', {
+    projectPath,
+    project_path: projectPath,
+    since,
+    until,
+  });
+}
+
+export async function getModelPrices(): Promise<ModelPriceTable> {
+  return await invoke<ModelPriceTable>('get_model_prices');
+}
+
+export async function setModelPrices(prices: ModelPriceTable): Promise<void> {
+  return await invoke('set_model_prices', { prices });
+}
+
+// Go back to the built-in prices
+export async function resetModelPrices(): Promise<ModelPriceTable> {
+  return await invoke<ModelPriceTable>('reset_model_prices');
+}
+
+export interface SessionSearchHitRaw {
+  session_id: string;
+  project_dir: string;
+  path: string;
+  message_index: number;                     // Offset in getClaudeSessionMessages without sidechains
+  role: TranscriptRole;
+  timestamp: string | null;
+  is_sidechain: boolean;
+  tools: string[];
+  paths: string[];                           // Files the message's tool calls touched
+  snippet: string;
+}
+
+export interface SessionSearchOptions {
+  projectPath?: string;                      // Every project when omitted
+  since?: string;                            // YYYY-MM-DD, inclusive
+  until?: string;
+  roles?: TranscriptRole[];
+  limit?: number;
+}
+
+export type SessionExportFormat = 'markdown' | 'html' | 'json';
+
+// Write one session to a user-selected file; subagent messages unless includeSidechains is false
+export async function exportClaudeSession(
+  projectPath: string,
+  sessionId: string,
+  format: SessionExportFormat,
+  path: string,
+  includeSidechains?: boolean
+): Promise<void> {
+  return await invoke('export_claude_session', {
+    projectPath,
+    project_path: projectPath,
+    sessionId,
+    session_id: sessionId,
+    format,
+    path,
+    includeSidechains,
+    include_sidechains: includeSidechains,
+  });
+}
+
+// Full-text search over session transcripts; every word must match, newest first
+export async function searchClaudeSessions(
+  query: string,
+  options: SessionSearchOptions = {}
+): Promise<SessionSearchHitRaw[]> {
+  return await invoke<SessionSearchHitRaw[]>('search_claude_sessions', {
+    query,
+    projectPath: options.projectPath,
+    project_path: options.projectPath,
+    since: options.since,
+    until: options.until,
+    roles: options.roles,
+    limit: options.limit,
+  });
+}
+
 // ============================================================================
 // Claude Code CLI Integration (Headless Mode)
 // ============================================================================
@@ -806,3 +1587,30 @@ export async function updateTrayStatus(
     hookCount,
   });
 }
+
+// ============================================================================
+// Resource Watcher
+// ============================================================================
+
+export type ResourceKindRaw = 'agent' | 'skill' | 'command' | 'memory' | 'mcp' | 'settings';
+
+// Payload of the 'resource:changed' event
+export interface ResourceChangedEvent {
+  kind: ResourceKindRaw;
+  scope: string;                             // Same vocabulary as the matching list_* command
+  project_path: string | null;
+  path: string;
+  change: 'created' | 'modified' | 'removed';
+}
+
+// Watch ~/.claude plus the given project roots; calling again replaces the project list
+export async function startResourceWatcher(projectPaths: string[]): Promise<void> {
+  return await invoke('start_resource_watcher', {
+    projectPaths,
+    project_paths: projectPaths,
+  });
+}
+
+export async function stopResourceWatcher(): Promise<void> {
+  return await invoke('stop_resource_watcher');
+}